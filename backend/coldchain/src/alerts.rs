@@ -1,4 +1,5 @@
 use chrono::NaiveDateTime;
+use repository::NotificationPriority;
 use serde::Serialize;
 use service::{
     notification::{
@@ -10,6 +11,8 @@ use service::{
     service_provider::ServiceContext,
 };
 
+use std::collections::HashMap;
+
 use crate::sensor_state::SensorStatus;
 
 /*
@@ -44,7 +47,10 @@ pub struct ColdchainAlert {
     pub store_name: String,
     pub location_name: String,
     pub last_data_time: NaiveDateTime,
+    /// How long ago the last data was received, in English e.g. `3 hours`
     pub data_age: String,
+    /// For templates to show the data age in other languages, e.g. `{{ data_age_seconds | humanise_duration(locale="fr") }}`
+    pub data_age_seconds: Option<i64>,
     pub temperature: String,
     pub alert_type: AlertType,
     pub reminder_number: usize,
@@ -91,9 +97,14 @@ pub fn queue_temperature_alert(
                 e
             ))
         })?,
+        dedup_key: None,
+        // Temperature alerts are always sent, even during the recipient's quiet hours
+        priority: NotificationPriority::Critical,
+        channel_templates: HashMap::new(),
     };
 
-    create_notification_events(ctx, config_id, notification)
+    create_notification_events(ctx, config_id, notification)?;
+    Ok(())
 }
 
 #[cfg(test)]
@@ -134,7 +145,8 @@ mod tests {
             sensor_id: "6a3399dd-10a9-40b7-853e-3ac0634ce6b3".to_string(),
             sensor_name: "E5:4G:D4:6D:A4".to_string(),
             last_data_time: NaiveDateTime::from_str("2023-07-17T17:04:00").unwrap(),
-            data_age: "1 minutes".to_string(),
+            data_age: "1 minute".to_string(),
+            data_age_seconds: Some(60),
             temperature: 10.12345.to_string(),
             alert_type: AlertType::High,
             reminder_number: 0,
@@ -145,11 +157,13 @@ mod tests {
             name: "test".to_string(),
             to_address: get_default_telegram_chat_id(),
             notification_type: NotificationType::Telegram,
+            ..Default::default()
         };
         let recipient2 = NotificationTarget {
             name: "test-email".to_string(),
             to_address: "test@example.com".to_string(),
             notification_type: NotificationType::Email,
+            ..Default::default()
         };
 
         let result = queue_temperature_alert(
@@ -203,6 +217,7 @@ mod tests {
             sensor_name: "E5:4G:D4:6D:A4".to_string(),
             last_data_time: NaiveDateTime::from_str("2023-07-17T17:04:00").unwrap(),
             data_age: "2 minutes".to_string(),
+            data_age_seconds: Some(120),
             temperature: 1.01.to_string(),
             alert_type: AlertType::Low,
             reminder_number: 0,
@@ -213,11 +228,13 @@ mod tests {
             name: "test".to_string(),
             to_address: get_default_telegram_chat_id(),
             notification_type: NotificationType::Telegram,
+            ..Default::default()
         };
         let recipient2 = NotificationTarget {
             name: "test-email".to_string(),
             to_address: "test@example.com".to_string(),
             notification_type: NotificationType::Email,
+            ..Default::default()
         };
 
         let result = queue_temperature_alert(
@@ -271,6 +288,7 @@ mod tests {
             sensor_name: "E5:4G:D4:6D:A4".to_string(),
             last_data_time: NaiveDateTime::from_str("2023-07-17T00:04:00").unwrap(),
             data_age: "2 minutes".to_string(),
+            data_age_seconds: Some(120),
             temperature: 1.01.to_string(),
             alert_type: AlertType::NoData,
             reminder_number: 0,
@@ -281,11 +299,13 @@ mod tests {
             name: "test".to_string(),
             to_address: get_default_telegram_chat_id(),
             notification_type: NotificationType::Telegram,
+            ..Default::default()
         };
         let recipient2 = NotificationTarget {
             name: "test-email".to_string(),
             to_address: "test@example.com".to_string(),
             notification_type: NotificationType::Email,
+            ..Default::default()
         };
 
         let result = queue_temperature_alert(
@@ -1,9 +1,12 @@
-use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use chrono::{DateTime, Duration, Local, NaiveDateTime, Utc};
 use repository::{
     NotificationConfigKind, NotificationConfigRowRepository, NotificationConfigStatus,
+    NotificationRunRowRepository,
 };
 use service::{
+    notification::locale::Locale,
     notification_config::{query::NotificationConfig, recipients::get_notification_targets},
+    notification_run::{finish_notification_run, start_notification_run, NotificationRunOutcome},
     service_provider::ServiceContext,
 };
 
@@ -16,6 +19,9 @@ use crate::{
     ColdChainError, PLUGIN_NAME,
 };
 
+/// How often a run is recorded for a config that keeps failing with the same error
+const REPEATED_ERROR_RUN_INTERVAL_MINUTES: i64 = 60;
+
 pub fn process_coldchain_alerts(
     ctx: &ServiceContext,
     current_time: NaiveDateTime,
@@ -45,22 +51,96 @@ pub fn process_coldchain_alerts(
             );
             continue;
         }
+        let config_id = config.id.clone();
         let result = try_process_coldchain_notifications(ctx, config, current_time);
-        match result {
+        // Configs are checked on every tick, so we only record runs where something happened,
+        // and only record the same error again once REPEATED_ERROR_RUN_INTERVAL_MINUTES has passed
+        let outcome = match result {
             Err(e) => {
                 log::error!("{:?}", e);
+                let error_message = format!("{:?}", e);
+                match is_repeated_error(ctx, &config_id, &error_message, current_time) {
+                    true => None,
+                    false => Some(NotificationRunOutcome {
+                        error_message: Some(error_message),
+                        ..Default::default()
+                    }),
+                }
             }
-            Ok(ProcessingResult::Success) => {
+            Ok(ProcessingResult::Success {
+                sensors_checked,
+                notifications_created,
+            }) => {
                 log::debug!("Successfully processed coldchain config");
+                match notifications_created {
+                    0 => None,
+                    _ => Some(NotificationRunOutcome {
+                        parameter_sets_evaluated: sensors_checked,
+                        notifications_created,
+                        ..Default::default()
+                    }),
+                }
             }
+        };
+
+        if let Some(outcome) = outcome {
+            record_notification_run(ctx, &config_id, current_time, outcome);
         }
     }
 
     Ok(num_configs)
 }
 
+/// True if the same error was recorded for the config recently, so we don't record a run on every tick
+fn is_repeated_error(
+    ctx: &ServiceContext,
+    config_id: &str,
+    error_message: &str,
+    current_time: NaiveDateTime,
+) -> bool {
+    let latest_run = match NotificationRunRowRepository::new(&ctx.connection)
+        .find_latest_by_config_id(config_id)
+    {
+        Ok(latest_run) => latest_run,
+        Err(e) => {
+            log::error!("Failed to get the latest notification run: {:?}", e);
+            return false;
+        }
+    };
+
+    match latest_run {
+        Some(run) => {
+            run.error_message.as_deref() == Some(error_message)
+                && run.started_at
+                    > current_time - Duration::minutes(REPEATED_ERROR_RUN_INTERVAL_MINUTES)
+        }
+        None => false,
+    }
+}
+
+fn record_notification_run(
+    ctx: &ServiceContext,
+    config_id: &str,
+    started_at: NaiveDateTime,
+    outcome: NotificationRunOutcome,
+) {
+    let result = start_notification_run(
+        ctx,
+        config_id,
+        NotificationConfigKind::ColdChain,
+        started_at,
+    )
+    .and_then(|run| finish_notification_run(ctx, run, outcome));
+    if let Err(e) = result {
+        log::error!("Failed to record notification run: {:?}", e);
+    }
+}
+
 enum ProcessingResult {
-    Success,
+    Success {
+        sensors_checked: usize,
+        notifications_created: usize,
+    },
 }
 
 fn try_process_coldchain_notifications(
@@ -111,8 +191,8 @@ fn try_process_coldchain_notifications(
 
         // We need this sensor status to be unique per notification config, so we include the notification config id in the key
         // This means that the same sensor can alarm in two different configs
-        // And duplicate notifications would be sent, e.g. if your email address is in two configuration & you have the same sensor in both
-        // Future deduplication efforts could be considered for this...
+        // Duplicate notifications (e.g. if your email address is in two configurations & you have the same sensor in both)
+        // are caught by the notification dedup window when the events are created
         let sensor_status_key = format!("sensor_status_{}_{}", sensor_id, notification_config.id);
 
         // Check if the status has changed since the last time we checked
@@ -202,9 +282,14 @@ fn try_process_coldchain_notifications(
         }
     }
 
+    let sensors_checked = config.sensor_ids.len();
+
     if alerts.len() == 0 {
         log::info!("No cold chain alerts to send");
-        return Ok(ProcessingResult::Success);
+        return Ok(ProcessingResult::Success {
+            sensors_checked,
+            notifications_created: 0,
+        });
     }
     // TODO: Suppress too many notifications in a short period of time
     // https://github.com/openmsupply/notify/issues/177
@@ -219,6 +304,7 @@ fn try_process_coldchain_notifications(
         ColdChainError::InternalError(format!("Failed to get notification targets: {:?}", e))
     })?;
 
+    let mut notifications_created = 0;
     for alert in alerts {
         // Send the notifications
         let result = queue_temperature_alert(
@@ -230,6 +316,7 @@ fn try_process_coldchain_notifications(
         match result {
             Ok(_) => {
                 log::info!("Successfully sent cold chain alert");
+                notifications_created += notification_targets.len();
             }
             Err(e) => {
                 log::error!("Failed to send cold chain alert: {:?}", e);
@@ -237,7 +324,10 @@ fn try_process_coldchain_notifications(
         }
     }
 
-    Ok(ProcessingResult::Success)
+    Ok(ProcessingResult::Success {
+        sensors_checked,
+        notifications_created,
+    })
 }
 
 pub fn try_process_sensor_notification(
@@ -338,13 +428,12 @@ pub fn try_process_sensor_notification(
         .map(|row| row.log_datetime)
         .unwrap_or_default();
 
-    let data_age: String = match latest_temperature_row.clone() {
-        Some(row) => format!(
-            "{} minutes",
-            (Local::now().naive_local() - row.log_datetime)
-                .num_minutes()
-                .to_string() // TODO: Improve this to show the age in hours/days/weeks/months/years? Ideally it would be translatable?
-        ),
+    // Templates can use `data_age_seconds` to show the age in the recipient's language
+    let data_age_seconds: Option<i64> = latest_temperature_row
+        .as_ref()
+        .map(|row| (Local::now().naive_local() - row.log_datetime).num_seconds());
+    let data_age: String = match data_age_seconds {
+        Some(seconds) => Locale::English.duration(seconds),
         None => "?? minutes".to_string(),
     };
 
@@ -379,6 +468,7 @@ pub fn try_process_sensor_notification(
         sensor_name: sensor_row.sensor_name.clone(),
         last_data_time: last_data_localtime,
         data_age,
+        data_age_seconds,
         temperature: current_temp,
         alert_type: AlertType::Ok,
         reminder_number,
@@ -471,3 +561,59 @@ pub fn evaluate_sensor_status(
     };
     return sensor_status;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use repository::{
+        mock::MockDataInserts, test_db::setup_all, NotificationConfigRow, NotificationRunFilter,
+        NotificationRunRepository,
+    };
+    use service::{service_provider::ServiceProvider, test_utils::get_test_settings};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_repeated_errors_are_recorded_once() {
+        let (_, connection, connection_manager, _) = setup_all(
+            "test_coldchain_repeated_errors_are_recorded_once",
+            MockDataInserts::none(),
+        )
+        .await;
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        NotificationConfigRowRepository::new(&connection)
+            .insert_one(&NotificationConfigRow {
+                id: "broken_coldchain_config".to_string(),
+                kind: NotificationConfigKind::ColdChain,
+                status: NotificationConfigStatus::Enabled,
+                configuration_data: "not a cold chain config".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let run_count = || {
+            NotificationRunRepository::new(&connection)
+                .count(None::<NotificationRunFilter>)
+                .unwrap()
+        };
+
+        // The config is checked on every tick, but the error is only recorded once
+        let now = Utc::now().naive_utc();
+        process_coldchain_alerts(&context, now).unwrap();
+        process_coldchain_alerts(&context, now + Duration::seconds(10)).unwrap();
+        assert_eq!(run_count(), 1);
+
+        // Unless it is still failing after the interval
+        process_coldchain_alerts(
+            &context,
+            now + Duration::minutes(REPEATED_ERROR_RUN_INTERVAL_MINUTES + 1),
+        )
+        .unwrap();
+        assert_eq!(run_count(), 2);
+    }
+}
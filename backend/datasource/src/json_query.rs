@@ -30,6 +30,64 @@ pub fn pg_sql_query_as_json_rows(
     Ok(json_results.into_iter().map(|r| r.data).collect())
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QueryLimits {
+    /// Cancel the query if it takes longer than this
+    pub timeout_seconds: Option<u32>,
+    /// Only return this many rows
+    pub max_rows: Option<u32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LimitedJsonRows {
+    pub rows: Vec<serde_json::Value>,
+    /// True if the query returned more rows than `max_rows`
+    pub truncated: bool,
+}
+
+pub fn pg_sql_query_as_json_rows_with_limits(
+    connection: &mut PgConnection,
+    sql_select_query: String,
+    limits: QueryLimits,
+) -> Result<LimitedJsonRows, DieselError> {
+    connection.batch_execute("SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY;")?;
+
+    // Fetch one extra row so we can tell if the results were truncated
+    let limit_clause = match limits.max_rows {
+        Some(max_rows) => format!("LIMIT {}", max_rows as u64 + 1),
+        None => "".to_string(),
+    };
+
+    let json_row_sql_query = format!(
+        "WITH provided_query AS(
+        {}
+        ) SELECT row_to_json(provided_query) as data FROM provided_query {};",
+        sql_select_query, limit_clause
+    );
+
+    // statement_timeout is set with SET LOCAL so it only applies to this transaction
+    let json_results: Vec<JsonDataRow> = connection.transaction(|connection| {
+        if let Some(timeout_seconds) = limits.timeout_seconds {
+            connection.batch_execute(&format!(
+                "SET LOCAL statement_timeout = {};",
+                timeout_seconds as u64 * 1000
+            ))?;
+        }
+        sql_query(&json_row_sql_query).load(connection)
+    })?;
+
+    let mut rows: Vec<serde_json::Value> = json_results.into_iter().map(|r| r.data).collect();
+    let truncated = match limits.max_rows {
+        Some(max_rows) if rows.len() > max_rows as usize => {
+            rows.truncate(max_rows as usize);
+            true
+        }
+        _ => false,
+    };
+
+    Ok(LimitedJsonRows { rows, truncated })
+}
+
 #[cfg(test)]
 #[cfg(feature = "datasource-tests")]
 mod tests {
@@ -95,6 +153,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_select_with_limits() {
+        let database_url =
+            env::var("DATABASE_URL").expect("the DATABASE_URL environment variable must be set");
+        let mut connection = PgConnection::establish(&database_url)
+            .unwrap_or_else(|e| panic!("Error connecting to {} : {}", database_url, e));
+
+        let sql_query = r#"SELECT generate_series(1, 5) as row_id"#;
+
+        // No limits
+        let result = pg_sql_query_as_json_rows_with_limits(
+            &mut connection,
+            sql_query.to_string(),
+            QueryLimits::default(),
+        )
+        .unwrap();
+        assert_eq!(result.rows.len(), 5);
+        assert!(!result.truncated);
+
+        // Max rows
+        let result = pg_sql_query_as_json_rows_with_limits(
+            &mut connection,
+            sql_query.to_string(),
+            QueryLimits {
+                max_rows: Some(3),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            result.rows,
+            vec![
+                json!({"row_id": 1}),
+                json!({"row_id": 2}),
+                json!({"row_id": 3})
+            ]
+        );
+        assert!(result.truncated);
+
+        // Exactly max rows isn't truncated
+        let result = pg_sql_query_as_json_rows_with_limits(
+            &mut connection,
+            sql_query.to_string(),
+            QueryLimits {
+                max_rows: Some(5),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(result.rows.len(), 5);
+        assert!(!result.truncated);
+
+        // Timeout
+        let result = pg_sql_query_as_json_rows_with_limits(
+            &mut connection,
+            "SELECT pg_sleep(3) as slept".to_string(),
+            QueryLimits {
+                timeout_seconds: Some(1),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err());
+
+        // The timeout shouldn't apply to later queries on the same connection
+        let result = pg_sql_query_as_json_rows(
+            &mut connection,
+            "SELECT current_setting('statement_timeout') as timeout".to_string(),
+        )
+        .unwrap();
+        assert_eq!(result, vec![json!({"timeout": "0"})]);
+    }
+
     #[test]
     fn test_invalid_query() {
         let database_url =
@@ -16,6 +16,9 @@ pub struct BasicRecipientRow {
     pub notification_type: String,
     #[diesel(sql_type = Text)]
     pub to_address: String,
+    /// The whole row, including any extra columns returned by the query
+    #[diesel(sql_type = Json)]
+    pub data: serde_json::Value,
 }
 
 pub fn pg_sql_query_as_recipients(
@@ -27,7 +30,7 @@ pub fn pg_sql_query_as_recipients(
     let recipient_sql_query = format!(
         "WITH provided_query AS(
         {}
-        ) SELECT id, name, notification_type, to_address FROM provided_query;",
+        ) SELECT id, name, notification_type, to_address, row_to_json(provided_query) as data FROM provided_query;",
         sql_select_query
     );
 
@@ -76,6 +79,12 @@ mod tests {
                 name: "Name One".to_string(),
                 notification_type: "EMAIL".to_string(),
                 to_address: "name1@example.com".to_string(),
+                data: serde_json::json!({
+                    "id": "1",
+                    "name": "Name One",
+                    "notification_type": "EMAIL",
+                    "to_address": "name1@example.com"
+                }),
             }]
         );
     }
@@ -7,7 +7,7 @@ use service::service_provider::ServiceProvider;
 
 use super::{
     user_permission::UserPermissionLoader, AuditLogLoader, NotificationConfigLoader,
-    RecipientsLoader,
+    NotificationTemplateVersionLoader, RecipientsLoader,
 };
 
 pub type LoaderMap = Map<AnyLoader>;
@@ -72,5 +72,13 @@ pub async fn get_loaders(
     );
     loaders.insert(notification_config_loader);
 
+    let notification_template_version_loader = DataLoader::new(
+        NotificationTemplateVersionLoader {
+            connection_manager: connection_manager.clone(),
+        },
+        async_std::task::spawn,
+    );
+    loaders.insert(notification_template_version_loader);
+
     loaders
 }
@@ -1,6 +1,7 @@
 mod audit_log;
 mod loader_registry;
 mod notification_config;
+mod notification_template_version;
 mod recipient;
 mod user;
 mod user_permission;
@@ -8,6 +9,7 @@ mod user_permission;
 pub use audit_log::*;
 pub use loader_registry::{get_loaders, LoaderMap, LoaderRegistry};
 pub use notification_config::*;
+pub use notification_template_version::*;
 pub use recipient::*;
 pub use user::*;
 pub use user_permission::*;
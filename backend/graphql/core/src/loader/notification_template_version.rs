@@ -0,0 +1,33 @@
+use repository::{
+    NotificationTemplateVersionRow, NotificationTemplateVersionRowRepository,
+    StorageConnectionManager,
+};
+
+use async_graphql::dataloader::*;
+use async_graphql::*;
+use std::collections::HashMap;
+
+pub struct NotificationTemplateVersionLoader {
+    pub connection_manager: StorageConnectionManager,
+}
+
+#[async_trait::async_trait]
+impl Loader<String> for NotificationTemplateVersionLoader {
+    type Value = Vec<NotificationTemplateVersionRow>;
+    type Error = async_graphql::Error;
+
+    async fn load(
+        &self,
+        notification_template_ids: &[String],
+    ) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let connection = self.connection_manager.connection()?;
+        let repo = NotificationTemplateVersionRowRepository::new(&connection);
+        let mut result_map = HashMap::new();
+
+        for notification_template_id in notification_template_ids {
+            let versions = repo.find_many_by_template_id(notification_template_id)?;
+            result_map.insert(notification_template_id.to_string(), versions);
+        }
+        Ok(result_map)
+    }
+}
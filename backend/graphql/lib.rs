@@ -13,8 +13,10 @@ use graphql_datasource::DatasourceQueries;
 use graphql_general::GeneralQueries;
 
 use graphql_notification_config::{NotificationConfigMutations, NotificationConfigQueries};
-use graphql_notification_event::NotificationEventQueries;
+use graphql_notification_event::{NotificationEventMutations, NotificationEventQueries};
 use graphql_notification_query::{NotificationQueryMutations, NotificationQueryQueries};
+use graphql_notification_run::NotificationRunQueries;
+use graphql_notification_template::{NotificationTemplateMutations, NotificationTemplateQueries};
 use graphql_recipient::{RecipientMutations, RecipientQueries};
 use graphql_recipient_list::{RecipientListMutations, RecipientListQueries};
 use graphql_telegram::mutations::TelegramMutations;
@@ -38,6 +40,8 @@ pub struct FullQuery(
     pub NotificationConfigQueries,
     pub NotificationQueryQueries,
     pub NotificationEventQueries,
+    pub NotificationRunQueries,
+    pub NotificationTemplateQueries,
     pub DatasourceQueries,
 );
 
@@ -49,6 +53,8 @@ pub struct FullMutation(
     pub TelegramMutations,
     pub NotificationConfigMutations,
     pub NotificationQueryMutations,
+    pub NotificationEventMutations,
+    pub NotificationTemplateMutations,
 );
 
 pub type Schema = async_graphql::Schema<FullQuery, FullMutation, async_graphql::EmptySubscription>;
@@ -64,6 +70,8 @@ pub fn full_query() -> FullQuery {
         NotificationConfigQueries,
         NotificationQueryQueries,
         NotificationEventQueries,
+        NotificationRunQueries,
+        NotificationTemplateQueries,
         DatasourceQueries,
     )
 }
@@ -76,6 +84,8 @@ pub fn full_mutation() -> FullMutation {
         TelegramMutations,
         NotificationConfigMutations,
         NotificationQueryMutations,
+        NotificationEventMutations,
+        NotificationTemplateMutations,
     )
 }
 
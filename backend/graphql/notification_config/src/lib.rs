@@ -91,4 +91,14 @@ impl NotificationConfigMutations {
     ) -> Result<DeleteNotificationConfigResponse> {
         delete_notification_config(ctx, &id)
     }
+
+    /// Runs a scheduled notification config immediately.
+    /// Sends to the current user (or the given recipient), or with `dryRun` returns the rendered notifications without sending them.
+    async fn run_notification_config_now(
+        &self,
+        ctx: &Context<'_>,
+        input: RunNotificationConfigNowInput,
+    ) -> Result<RunNotificationConfigNowResponse> {
+        run_notification_config_now(ctx, input)
+    }
 }
@@ -13,6 +13,7 @@ pub struct CreateNotificationConfigInput {
     pub id: String,
     pub title: String,
     pub kind: ConfigKind,
+    pub parameter_schema: Option<String>,
 }
 
 pub fn create_notification_config(
@@ -46,12 +47,14 @@ impl From<CreateNotificationConfigInput> for CreateNotificationConfig {
             id,
             title,
             kind,
+            parameter_schema,
         }: CreateNotificationConfigInput,
     ) -> Self {
         CreateNotificationConfig {
             id,
             title,
             kind: ConfigKind::to_domain(kind),
+            parameter_schema,
         }
     }
 }
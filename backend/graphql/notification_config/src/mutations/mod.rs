@@ -7,11 +7,13 @@ mod create;
 mod delete;
 mod update;
 mod duplicate;
+mod run_now;
 
 pub use create::*;
 pub use delete::*;
 pub use update::*;
 pub use duplicate::*;
+pub use run_now::*;
 
 #[derive(Union)]
 pub enum ModifyNotificationConfigResponse {
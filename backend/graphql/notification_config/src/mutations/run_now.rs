@@ -0,0 +1,133 @@
+use async_graphql::*;
+use graphql_core::{
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use graphql_types::types::NotificationTypeNode;
+use repository::NotificationEventRow;
+use scheduled::{
+    run_now::{ParameterSetResult, RunNotificationConfigNow},
+    NotificationError,
+};
+use service::auth::{Resource, ResourceAccessRequest};
+
+#[derive(InputObject, Clone)]
+pub struct RunNotificationConfigNowInput {
+    pub id: String,
+    /// Send to this recipient instead of the current user
+    pub recipient_id: Option<String>,
+    /// Return the rendered notifications for the configured recipients without sending anything
+    pub dry_run: Option<bool>,
+}
+
+#[derive(SimpleObject)]
+pub struct NotificationPreviewNode {
+    pub to_address: String,
+    pub notification_type: NotificationTypeNode,
+    pub title: Option<String>,
+    pub message: String,
+    pub error_message: Option<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct ParameterSetResultNode {
+    /// The parameter set as a JSON string
+    pub parameters: String,
+    pub skipped_reason: Option<String>,
+    pub notifications: Vec<NotificationPreviewNode>,
+}
+
+#[derive(SimpleObject)]
+pub struct RunNotificationConfigNowNode {
+    pub parameter_sets: Vec<ParameterSetResultNode>,
+}
+
+#[derive(Union)]
+pub enum RunNotificationConfigNowResponse {
+    Response(RunNotificationConfigNowNode),
+}
+
+pub fn run_notification_config_now(
+    ctx: &Context<'_>,
+    input: RunNotificationConfigNowInput,
+) -> Result<RunNotificationConfigNowResponse> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+        },
+    )?;
+
+    let service_context = ctx.service_context(Some(&user))?;
+
+    match scheduled::run_now::run_notification_config_now(&service_context, input.into()) {
+        Ok(results) => Ok(RunNotificationConfigNowResponse::Response(
+            RunNotificationConfigNowNode {
+                parameter_sets: results
+                    .into_iter()
+                    .map(ParameterSetResultNode::from_domain)
+                    .collect(),
+            },
+        )),
+        Err(error) => map_error(error),
+    }
+}
+
+impl From<RunNotificationConfigNowInput> for RunNotificationConfigNow {
+    fn from(
+        RunNotificationConfigNowInput {
+            id,
+            recipient_id,
+            dry_run,
+        }: RunNotificationConfigNowInput,
+    ) -> Self {
+        RunNotificationConfigNow {
+            id,
+            recipient_id,
+            dry_run: dry_run.unwrap_or(false),
+        }
+    }
+}
+
+impl ParameterSetResultNode {
+    pub fn from_domain(result: ParameterSetResult) -> Self {
+        ParameterSetResultNode {
+            parameters: result.parameters.to_string(),
+            skipped_reason: result.skipped_reason,
+            notifications: result
+                .notifications
+                .into_iter()
+                .map(NotificationPreviewNode::from_domain)
+                .collect(),
+        }
+    }
+}
+
+impl NotificationPreviewNode {
+    pub fn from_domain(row: NotificationEventRow) -> Self {
+        NotificationPreviewNode {
+            to_address: row.to_address,
+            notification_type: NotificationTypeNode::from_domain(&row.notification_type),
+            title: row.title,
+            message: row.message,
+            error_message: row.error_message,
+        }
+    }
+}
+
+fn map_error(error: NotificationError) -> Result<RunNotificationConfigNowResponse> {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        NotificationError::BadUserInput(s) => BadUserInput(s),
+        NotificationError::InvalidTemplate(_) => BadUserInput(formatted_error),
+        NotificationError::UnableToParseConfig(_) => BadUserInput(formatted_error),
+        NotificationError::InvalidSendCondition(_) => BadUserInput(formatted_error),
+        NotificationError::InvalidNextDueDate => BadUserInput(formatted_error),
+        NotificationError::InvalidRecipient => BadUserInput(formatted_error),
+        NotificationError::InternalError(s) => InternalError(s),
+    };
+
+    Err(graphql_error.extend())
+}
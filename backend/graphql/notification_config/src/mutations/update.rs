@@ -21,6 +21,7 @@ pub struct UpdateNotificationConfigInput {
     pub recipient_list_ids: Option<Vec<String>>,
     pub sql_recipient_list_ids: Option<Vec<String>>,
     pub next_due_datetime: Option<DateTime<Utc>>,
+    pub parameter_schema: Option<String>,
 }
 
 pub fn update_notification_config(
@@ -60,6 +61,7 @@ impl From<UpdateNotificationConfigInput> for UpdateNotificationConfig {
             recipient_list_ids,
             sql_recipient_list_ids,
             next_due_datetime,
+            parameter_schema,
         }: UpdateNotificationConfigInput,
     ) -> Self {
         UpdateNotificationConfig {
@@ -73,6 +75,7 @@ impl From<UpdateNotificationConfigInput> for UpdateNotificationConfig {
             recipient_list_ids,
             sql_recipient_list_ids,
             next_due_datetime: next_due_datetime.map(|d| d.naive_utc()),
+            parameter_schema,
         }
     }
 }
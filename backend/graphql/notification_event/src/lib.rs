@@ -1,4 +1,6 @@
+mod mutations;
 mod types;
+use self::mutations::*;
 use self::types::*;
 
 use async_graphql::*;
@@ -52,3 +54,37 @@ impl NotificationEventQueries {
         ))
     }
 }
+
+#[derive(Default, Clone)]
+pub struct NotificationEventMutations;
+
+#[Object]
+impl NotificationEventMutations {
+    /// Queue failed, errored or cancelled notifications to be sent again
+    async fn resend_notification_events(
+        &self,
+        ctx: &Context<'_>,
+        input: NotificationEventSelectionInput,
+    ) -> Result<NotificationEventsResponse> {
+        resend_notification_events(ctx, input)
+    }
+
+    /// Stop queued or errored notifications from being sent
+    async fn cancel_notification_events(
+        &self,
+        ctx: &Context<'_>,
+        input: NotificationEventSelectionInput,
+    ) -> Result<NotificationEventsResponse> {
+        cancel_notification_events(ctx, input)
+    }
+
+    /// Render notifications again with corrected templates, using the data they were created with, and queue them to be sent.
+    /// With `preview: true` the re-rendered notifications are returned without saving them.
+    async fn rerender_notification_events(
+        &self,
+        ctx: &Context<'_>,
+        input: RerenderNotificationEventsInput,
+    ) -> Result<NotificationEventsResponse> {
+        rerender_notification_events(ctx, input)
+    }
+}
@@ -0,0 +1,50 @@
+mod cancel;
+pub use cancel::*;
+mod rerender;
+pub use rerender::*;
+mod resend;
+pub use resend::*;
+
+use async_graphql::*;
+use graphql_core::standard_graphql_error::StandardGraphqlError;
+use repository::{EqualFilter, NotificationEventFilter};
+use service::notification_event::ModifyNotificationEventError;
+use std::convert::TryFrom;
+
+use crate::types::NotificationEventFilterInput;
+
+/// Select notification events either by `ids` or by `filter`
+#[derive(InputObject, Clone)]
+pub struct NotificationEventSelectionInput {
+    pub ids: Option<Vec<String>>,
+    pub filter: Option<NotificationEventFilterInput>,
+}
+
+impl TryFrom<NotificationEventSelectionInput> for NotificationEventFilter {
+    type Error = async_graphql::Error;
+
+    fn try_from(input: NotificationEventSelectionInput) -> Result<Self> {
+        match (input.ids, input.filter) {
+            (Some(ids), None) if !ids.is_empty() => {
+                Ok(NotificationEventFilter::new().id(EqualFilter::equal_any(ids)))
+            }
+            (None, Some(filter)) => Ok(NotificationEventFilter::from(filter)),
+            _ => Err(StandardGraphqlError::BadUserInput(
+                "Select notification events by either ids or filter".to_string(),
+            )
+            .extend()),
+        }
+    }
+}
+
+fn map_error(error: ModifyNotificationEventError) -> async_graphql::Error {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        ModifyNotificationEventError::DatabaseError(_) => InternalError(formatted_error),
+        ModifyNotificationEventError::BadUserInput(s) => BadUserInput(s),
+    };
+
+    graphql_error.extend()
+}
@@ -0,0 +1,60 @@
+use async_graphql::*;
+use graphql_core::{standard_graphql_error::validate_auth, ContextExt};
+use repository::NotificationEventFilter;
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    notification_event::rerender::RerenderNotificationEvents,
+};
+use std::convert::TryFrom;
+
+use super::{map_error, NotificationEventSelectionInput};
+use crate::types::{
+    NotificationEventConnector, NotificationEventFilterInput, NotificationEventsResponse,
+};
+
+#[derive(InputObject, Clone)]
+pub struct RerenderNotificationEventsInput {
+    pub ids: Option<Vec<String>>,
+    pub filter: Option<NotificationEventFilterInput>,
+    /// If not set, the existing title is kept
+    pub title_template: Option<String>,
+    pub body_template: String,
+    /// Return the re-rendered notifications without saving or sending them
+    pub preview: Option<bool>,
+}
+
+pub fn rerender_notification_events(
+    ctx: &Context<'_>,
+    input: RerenderNotificationEventsInput,
+) -> Result<NotificationEventsResponse> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+        },
+    )?;
+
+    let service_context = ctx.service_context(Some(&user))?;
+    let filter = NotificationEventFilter::try_from(NotificationEventSelectionInput {
+        ids: input.ids,
+        filter: input.filter,
+    })?;
+
+    match service_context
+        .service_provider
+        .notification_event_service
+        .rerender_notification_events(
+            &service_context,
+            RerenderNotificationEvents {
+                filter,
+                title_template: input.title_template,
+                body_template: input.body_template,
+                preview: input.preview.unwrap_or(false),
+            },
+        ) {
+        Ok(events) => Ok(NotificationEventsResponse::Response(
+            NotificationEventConnector::from_vec(events),
+        )),
+        Err(error) => Err(map_error(error)),
+    }
+}
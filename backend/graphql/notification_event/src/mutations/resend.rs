@@ -0,0 +1,34 @@
+use async_graphql::*;
+use graphql_core::{standard_graphql_error::validate_auth, ContextExt};
+use repository::NotificationEventFilter;
+use service::auth::{Resource, ResourceAccessRequest};
+use std::convert::TryFrom;
+
+use super::{map_error, NotificationEventSelectionInput};
+use crate::types::{NotificationEventConnector, NotificationEventsResponse};
+
+pub fn resend_notification_events(
+    ctx: &Context<'_>,
+    input: NotificationEventSelectionInput,
+) -> Result<NotificationEventsResponse> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+        },
+    )?;
+
+    let service_context = ctx.service_context(Some(&user))?;
+    let filter = NotificationEventFilter::try_from(input)?;
+
+    match service_context
+        .service_provider
+        .notification_event_service
+        .resend_notification_events(&service_context, filter)
+    {
+        Ok(events) => Ok(NotificationEventsResponse::Response(
+            NotificationEventConnector::from_vec(events),
+        )),
+        Err(error) => Err(map_error(error)),
+    }
+}
@@ -0,0 +1,19 @@
+use async_graphql::Enum;
+use repository::NotificationPriority;
+use serde::Serialize;
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EventPriority {
+    Normal,   // Held during the recipient's quiet hours
+    Critical, // Always sent straight away
+}
+
+impl EventPriority {
+    pub fn from_domain(priority: &NotificationPriority) -> EventPriority {
+        match priority {
+            NotificationPriority::Normal => EventPriority::Normal,
+            NotificationPriority::Critical => EventPriority::Critical,
+        }
+    }
+}
@@ -7,8 +7,10 @@ use serde::Serialize;
 pub enum EventStatus {
     Queued,
     Sent,
-    Errored, // Errored will be re-tried
-    Failed,  // Failed will not be re-tried
+    Errored,      // Errored will be re-tried
+    Failed,       // Failed will not be re-tried
+    Deduplicated, // A duplicate of a recent notification, so it won't be sent
+    Cancelled,    // Cancelled by a user before it was sent
 }
 
 impl EventStatus {
@@ -18,6 +20,8 @@ impl EventStatus {
             EventStatus::Sent => NotificationEventStatus::Sent,
             EventStatus::Errored => NotificationEventStatus::Errored,
             EventStatus::Failed => NotificationEventStatus::Failed,
+            EventStatus::Deduplicated => NotificationEventStatus::Deduplicated,
+            EventStatus::Cancelled => NotificationEventStatus::Cancelled,
         }
     }
 
@@ -27,6 +31,8 @@ impl EventStatus {
             NotificationEventStatus::Sent => EventStatus::Sent,
             NotificationEventStatus::Errored => EventStatus::Errored,
             NotificationEventStatus::Failed => EventStatus::Failed,
+            NotificationEventStatus::Deduplicated => EventStatus::Deduplicated,
+            NotificationEventStatus::Cancelled => EventStatus::Cancelled,
         }
     }
 }
@@ -1,5 +1,7 @@
 mod inputs;
 pub use inputs::*;
+mod event_priority;
+pub use event_priority::*;
 mod event_status;
 pub use event_status::*;
 mod notification_event;
@@ -8,7 +8,7 @@ use repository::NotificationEvent;
 use service::ListResult;
 use util::usize_to_u32;
 
-use super::EventStatus;
+use super::{EventPriority, EventStatus};
 
 #[derive(Union)]
 pub enum NotificationEventsResponse {
@@ -53,6 +53,11 @@ impl NotificationEventNode {
         self.row().error_message.to_owned()
     }
 
+    /// Set if the notification was sent without its formatting, e.g. when telegram couldn't parse the markdown
+    pub async fn fallback_reason(&self) -> Option<String> {
+        self.row().fallback_reason.to_owned()
+    }
+
     pub async fn created_at(&self) -> DateTime<Utc> {
         DateTime::<Utc>::from_utc(self.row().created_at, Utc)
     }
@@ -70,6 +75,10 @@ impl NotificationEventNode {
         EventStatus::from_domain(&self.row().status)
     }
 
+    pub async fn priority(&self) -> EventPriority {
+        EventPriority::from_domain(&self.row().priority)
+    }
+
     pub async fn send_attempts(&self) -> i32 {
         self.row().send_attempts
     }
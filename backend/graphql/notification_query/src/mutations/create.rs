@@ -14,6 +14,10 @@ pub struct CreateNotificationQueryInput {
     pub id: String,
     pub name: String,
     pub reference_name: String,
+    /// Cancel the query if it takes longer than this many seconds (0 for no timeout)
+    pub timeout_seconds: Option<i32>,
+    /// Only use the first `maxRows` rows returned by the query (0 for no limit)
+    pub max_rows: Option<i32>,
 }
 
 pub fn create_notification_query(
@@ -47,12 +51,16 @@ impl From<CreateNotificationQueryInput> for CreateNotificationQuery {
             id,
             name,
             reference_name,
+            timeout_seconds,
+            max_rows,
         }: CreateNotificationQueryInput,
     ) -> Self {
         CreateNotificationQuery {
             id,
             name,
             reference_name,
+            timeout_seconds,
+            max_rows,
         }
     }
 }
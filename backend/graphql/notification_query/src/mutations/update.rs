@@ -17,6 +17,10 @@ pub struct UpdateNotificationQueryInput {
     pub description: Option<String>,
     pub query: Option<String>,
     pub required_parameters: Option<Vec<String>>,
+    /// Cancel the query if it takes longer than this many seconds (0 for no timeout)
+    pub timeout_seconds: Option<i32>,
+    /// Only use the first `maxRows` rows returned by the query (0 for no limit)
+    pub max_rows: Option<i32>,
 }
 
 pub fn update_notification_query(
@@ -52,6 +56,8 @@ impl From<UpdateNotificationQueryInput> for UpdateNotificationQuery {
             description,
             query,
             required_parameters,
+            timeout_seconds,
+            max_rows,
         }: UpdateNotificationQueryInput,
     ) -> Self {
         UpdateNotificationQuery {
@@ -61,6 +67,8 @@ impl From<UpdateNotificationQueryInput> for UpdateNotificationQuery {
             description,
             query,
             required_parameters,
+            timeout_seconds,
+            max_rows,
         }
     }
 }
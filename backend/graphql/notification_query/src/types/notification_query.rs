@@ -45,6 +45,14 @@ impl NotificationQueryNode {
 
         Ok(parameters)
     }
+
+    pub async fn timeout_seconds(&self) -> Option<i32> {
+        self.row().timeout_seconds
+    }
+
+    pub async fn max_rows(&self) -> Option<i32> {
+        self.row().max_rows
+    }
 }
 
 impl NotificationQueryNode {
@@ -0,0 +1,55 @@
+mod types;
+use self::types::*;
+
+use async_graphql::*;
+use graphql_core::{
+    pagination::PaginationInput,
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+
+use repository::NotificationRunFilter;
+use repository::PaginationOption;
+use service::auth::{Resource, ResourceAccessRequest};
+
+#[derive(Default, Clone)]
+pub struct NotificationRunQueries;
+
+#[Object]
+impl NotificationRunQueries {
+    /// History of notification config runs, newest first unless a sort is provided
+    pub async fn notification_runs(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Pagination option (first and offset)")] page: Option<PaginationInput>,
+        #[graphql(desc = "Filter option")] filter: Option<NotificationRunFilterInput>,
+        #[graphql(desc = "Sort options (only first sort input is evaluated for this endpoint)")]
+        sort: Option<Vec<NotificationRunSortInput>>,
+    ) -> Result<NotificationRunsResponse> {
+        let user = validate_auth(
+            ctx,
+            &ResourceAccessRequest {
+                resource: Resource::ServerAdmin,
+            },
+        )?;
+
+        let service_context = ctx.service_context(Some(&user))?;
+
+        let runs = service_context
+            .service_provider
+            .notification_run_service
+            .get_notification_runs(
+                &service_context,
+                page.map(PaginationOption::from),
+                filter.map(NotificationRunFilter::from),
+                // Currently only one sort option is supported, use the first from the list.
+                sort.and_then(|mut sort_list| sort_list.pop())
+                    .map(|sort| sort.to_domain()),
+            )
+            .map_err(StandardGraphqlError::from_list_error)?;
+
+        Ok(NotificationRunsResponse::Response(
+            NotificationRunConnector::from_domain(runs),
+        ))
+    }
+}
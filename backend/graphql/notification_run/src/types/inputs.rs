@@ -0,0 +1,68 @@
+use async_graphql::{Enum, InputObject};
+use graphql_core::{
+    generic_filters::{DatetimeFilterInput, EqualFilterStringInput},
+    map_filter,
+};
+use repository::{
+    DatetimeFilter, EqualFilter, NotificationRunFilter, NotificationRunSort,
+    NotificationRunSortField,
+};
+
+use super::RunStatus;
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(rename_items = "camelCase")]
+pub enum NotificationRunSortFieldInput {
+    StartedAt,
+    Status,
+}
+
+#[derive(InputObject, Clone)]
+pub struct EqualFilterRunStatusInput {
+    pub equal_to: Option<RunStatus>,
+    pub equal_any: Option<Vec<RunStatus>>,
+    pub not_equal_to: Option<RunStatus>,
+}
+
+#[derive(InputObject)]
+pub struct NotificationRunSortInput {
+    /// Sort query result by `key`
+    key: NotificationRunSortFieldInput,
+    /// Sort query result is sorted descending or ascending (if not provided the default is
+    /// ascending)
+    desc: Option<bool>,
+}
+impl NotificationRunSortInput {
+    pub fn to_domain(self) -> NotificationRunSort {
+        use NotificationRunSortField as to;
+        use NotificationRunSortFieldInput as from;
+        let key = match self.key {
+            from::StartedAt => to::StartedAt,
+            from::Status => to::Status,
+        };
+
+        NotificationRunSort {
+            key,
+            desc: self.desc,
+        }
+    }
+}
+
+#[derive(Clone, InputObject)]
+pub struct NotificationRunFilterInput {
+    pub id: Option<EqualFilterStringInput>,
+    pub notification_config_id: Option<EqualFilterStringInput>,
+    pub status: Option<EqualFilterRunStatusInput>,
+    pub started_at: Option<DatetimeFilterInput>,
+}
+
+impl From<NotificationRunFilterInput> for NotificationRunFilter {
+    fn from(f: NotificationRunFilterInput) -> Self {
+        NotificationRunFilter {
+            id: f.id.map(EqualFilter::from),
+            notification_config_id: f.notification_config_id.map(EqualFilter::from),
+            status: f.status.map(|t| map_filter!(t, RunStatus::to_domain)),
+            started_at: f.started_at.map(DatetimeFilter::from),
+        }
+    }
+}
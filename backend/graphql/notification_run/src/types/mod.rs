@@ -0,0 +1,6 @@
+mod inputs;
+pub use inputs::*;
+mod run_status;
+pub use run_status::*;
+mod notification_run;
+pub use notification_run::*;
@@ -0,0 +1,139 @@
+use async_graphql::{dataloader::DataLoader, *};
+use chrono::{DateTime, Utc};
+use graphql_core::{loader::NotificationConfigLoader, ContextExt};
+
+use graphql_types::types::{ConfigKind, NotificationConfigNode};
+use repository::NotificationRun;
+use service::{notification_run::QueryTiming, ListResult};
+
+use super::RunStatus;
+
+#[derive(Union)]
+pub enum NotificationRunsResponse {
+    Response(NotificationRunConnector),
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct NotificationRunNode {
+    pub notification_run: NotificationRun,
+}
+
+#[derive(SimpleObject)]
+pub struct QueryTimingNode {
+    /// The reference name of the notification query
+    pub query: String,
+    /// The parameters the query was run with, as a JSON string
+    pub parameters: String,
+    pub duration_ms: i64,
+    pub error: Option<String>,
+}
+
+impl QueryTimingNode {
+    pub fn from_domain(timing: QueryTiming) -> QueryTimingNode {
+        QueryTimingNode {
+            query: timing.query,
+            parameters: timing.parameters.to_string(),
+            duration_ms: timing.duration_ms,
+            error: timing.error,
+        }
+    }
+}
+
+#[Object]
+impl NotificationRunNode {
+    pub async fn id(&self) -> &str {
+        &self.row().id
+    }
+
+    pub async fn notification_config_id(&self) -> &str {
+        &self.row().notification_config_id
+    }
+
+    pub async fn kind(&self) -> ConfigKind {
+        ConfigKind::from_domain(&self.row().kind)
+    }
+
+    pub async fn status(&self) -> RunStatus {
+        RunStatus::from_domain(&self.row().status)
+    }
+
+    pub async fn started_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.row().started_at, Utc)
+    }
+    pub async fn finished_at(&self) -> Option<DateTime<Utc>> {
+        self.row()
+            .finished_at
+            .map(|finished_at| DateTime::<Utc>::from_utc(finished_at, Utc))
+    }
+
+    pub async fn parameter_sets_evaluated(&self) -> i32 {
+        self.row().parameter_sets_evaluated
+    }
+    pub async fn notifications_created(&self) -> i32 {
+        self.row().notifications_created
+    }
+    pub async fn skipped_count(&self) -> i32 {
+        self.row().skipped_count
+    }
+
+    pub async fn skipped_reasons(&self) -> Vec<String> {
+        serde_json::from_str(&self.row().skipped_reasons).unwrap_or_default()
+    }
+
+    pub async fn query_timings(&self) -> Vec<QueryTimingNode> {
+        let timings: Vec<QueryTiming> =
+            serde_json::from_str(&self.row().query_timings).unwrap_or_default();
+        timings
+            .into_iter()
+            .map(QueryTimingNode::from_domain)
+            .collect()
+    }
+
+    pub async fn error_message(&self) -> Option<String> {
+        self.row().error_message.to_owned()
+    }
+
+    pub async fn notification_config(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Option<NotificationConfigNode>> {
+        let loader = ctx.get_loader::<DataLoader<NotificationConfigLoader>>();
+
+        match loader
+            .load_one(self.row().notification_config_id.clone())
+            .await?
+        {
+            Some(config) => Ok(Some(NotificationConfigNode::from_domain(config.into()))),
+            None => Ok(None),
+        }
+    }
+}
+
+impl NotificationRunNode {
+    pub fn from_domain(notification_run: NotificationRun) -> NotificationRunNode {
+        NotificationRunNode { notification_run }
+    }
+
+    pub fn row(&self) -> &NotificationRun {
+        &self.notification_run
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct NotificationRunConnector {
+    total_count: u32,
+    nodes: Vec<NotificationRunNode>,
+}
+
+impl NotificationRunConnector {
+    pub fn from_domain(notification_runs: ListResult<NotificationRun>) -> NotificationRunConnector {
+        NotificationRunConnector {
+            total_count: notification_runs.count,
+            nodes: notification_runs
+                .rows
+                .into_iter()
+                .map(NotificationRunNode::from_domain)
+                .collect(),
+        }
+    }
+}
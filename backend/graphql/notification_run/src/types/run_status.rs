@@ -0,0 +1,29 @@
+use async_graphql::Enum;
+use repository::NotificationRunStatus;
+use serde::Serialize;
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RunStatus {
+    Running,
+    Completed,
+    Errored,
+}
+
+impl RunStatus {
+    pub fn to_domain(self) -> NotificationRunStatus {
+        match self {
+            RunStatus::Running => NotificationRunStatus::Running,
+            RunStatus::Completed => NotificationRunStatus::Completed,
+            RunStatus::Errored => NotificationRunStatus::Errored,
+        }
+    }
+
+    pub fn from_domain(status: &NotificationRunStatus) -> RunStatus {
+        match status {
+            NotificationRunStatus::Running => RunStatus::Running,
+            NotificationRunStatus::Completed => RunStatus::Completed,
+            NotificationRunStatus::Errored => RunStatus::Errored,
+        }
+    }
+}
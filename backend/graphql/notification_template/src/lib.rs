@@ -0,0 +1,88 @@
+mod mutations;
+use self::mutations::*;
+mod types;
+use self::types::*;
+
+use async_graphql::*;
+use graphql_core::{
+    pagination::PaginationInput,
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+use repository::NotificationTemplateFilter;
+use repository::PaginationOption;
+use service::auth::{Resource, ResourceAccessRequest};
+
+#[derive(Default, Clone)]
+pub struct NotificationTemplateQueries;
+
+#[Object]
+impl NotificationTemplateQueries {
+    pub async fn notification_templates(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Pagination option (first and offset)")] page: Option<PaginationInput>,
+        #[graphql(desc = "Filter option")] filter: Option<NotificationTemplateFilterInput>,
+        #[graphql(desc = "Sort options (only first sort input is evaluated for this endpoint)")]
+        sort: Option<Vec<NotificationTemplateSortInput>>,
+    ) -> Result<NotificationTemplatesResponse> {
+        let user = validate_auth(
+            ctx,
+            &ResourceAccessRequest {
+                resource: Resource::ServerAdmin,
+            },
+        )?;
+
+        let service_context = ctx.service_context(Some(&user))?;
+
+        let templates = service_context
+            .service_provider
+            .notification_template_service
+            .get_notification_templates(
+                &service_context,
+                page.map(PaginationOption::from),
+                filter.map(NotificationTemplateFilter::from),
+                // Currently only one sort option is supported, use the first from the list.
+                sort.and_then(|mut sort_list| sort_list.pop())
+                    .map(|sort| sort.to_domain()),
+            )
+            .map_err(StandardGraphqlError::from_list_error)?;
+
+        Ok(NotificationTemplatesResponse::Response(
+            NotificationTemplateConnector::from_domain(templates),
+        ))
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct NotificationTemplateMutations;
+
+#[Object]
+impl NotificationTemplateMutations {
+    /// Templates are checked with Tera before they are saved.
+    /// A template with the same name as a file template, e.g. `coldchain/temperature.md`, is used instead of the file.
+    async fn create_notification_template(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateNotificationTemplateInput,
+    ) -> Result<ModifyNotificationTemplateResponse> {
+        create_notification_template(ctx, input)
+    }
+
+    /// Changing the template creates a new version, previous versions are kept
+    async fn update_notification_template(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateNotificationTemplateInput,
+    ) -> Result<ModifyNotificationTemplateResponse> {
+        update_notification_template(ctx, input)
+    }
+
+    async fn delete_notification_template(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> Result<DeleteNotificationTemplateResponse> {
+        delete_notification_template(ctx, &id)
+    }
+}
@@ -0,0 +1,62 @@
+use async_graphql::*;
+use graphql_core::{standard_graphql_error::validate_auth, ContextExt};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    notification_template::create::CreateNotificationTemplate,
+};
+
+use crate::types::NotificationTemplateNode;
+
+use super::{map_error, ModifyNotificationTemplateResponse};
+
+#[derive(InputObject, Clone)]
+pub struct CreateNotificationTemplateInput {
+    pub id: String,
+    /// Use the name of a file template, e.g. `coldchain/temperature.md`, to replace it
+    pub name: String,
+    pub description: Option<String>,
+    pub template: String,
+}
+
+pub fn create_notification_template(
+    ctx: &Context<'_>,
+    input: CreateNotificationTemplateInput,
+) -> Result<ModifyNotificationTemplateResponse> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+        },
+    )?;
+
+    let service_context = ctx.service_context(Some(&user))?;
+
+    match service_context
+        .service_provider
+        .notification_template_service
+        .create_notification_template(&service_context, input.into())
+    {
+        Ok(notification_template) => Ok(ModifyNotificationTemplateResponse::Response(
+            NotificationTemplateNode::from_domain(notification_template),
+        )),
+        Err(error) => map_error(error),
+    }
+}
+
+impl From<CreateNotificationTemplateInput> for CreateNotificationTemplate {
+    fn from(
+        CreateNotificationTemplateInput {
+            id,
+            name,
+            description,
+            template,
+        }: CreateNotificationTemplateInput,
+    ) -> Self {
+        CreateNotificationTemplate {
+            id,
+            name,
+            description,
+            template,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use async_graphql::*;
+use graphql_core::{
+    standard_graphql_error::{validate_auth, StandardGraphqlError},
+    ContextExt,
+};
+
+use graphql_types::types::DeleteResponse;
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    notification_template::delete::DeleteNotificationTemplateError,
+};
+
+pub fn delete_notification_template(
+    ctx: &Context<'_>,
+    id: &str,
+) -> Result<DeleteNotificationTemplateResponse> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+        },
+    )?;
+
+    let service_context = ctx.service_context(Some(&user))?;
+
+    match service_context
+        .service_provider
+        .notification_template_service
+        .delete_notification_template(&service_context, id)
+    {
+        Ok(id) => Ok(DeleteNotificationTemplateResponse::Response(
+            DeleteResponse(id),
+        )),
+        Err(error) => map_error(error),
+    }
+}
+
+#[derive(Union)]
+pub enum DeleteNotificationTemplateResponse {
+    Response(DeleteResponse),
+}
+
+fn map_error(error: DeleteNotificationTemplateError) -> Result<DeleteNotificationTemplateResponse> {
+    use StandardGraphqlError::*;
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        DeleteNotificationTemplateError::NotificationTemplateDoesNotExist => {
+            BadUserInput(formatted_error)
+        }
+        DeleteNotificationTemplateError::TemplateInUse(s) => BadUserInput(s),
+        DeleteNotificationTemplateError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    Err(graphql_error.extend())
+}
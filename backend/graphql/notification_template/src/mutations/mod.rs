@@ -0,0 +1,45 @@
+use async_graphql::*;
+use graphql_core::standard_graphql_error::StandardGraphqlError::*;
+use service::notification_template::ModifyNotificationTemplateError;
+
+mod create;
+mod delete;
+mod update;
+
+pub use create::*;
+pub use delete::*;
+pub use update::*;
+
+use crate::types::NotificationTemplateNode;
+
+#[derive(Union)]
+pub enum ModifyNotificationTemplateResponse {
+    Response(NotificationTemplateNode),
+}
+
+pub fn map_error(
+    error: ModifyNotificationTemplateError,
+) -> Result<ModifyNotificationTemplateResponse> {
+    let formatted_error = format!("{:#?}", error);
+
+    let graphql_error = match error {
+        // Standard Graphql Errors
+        ModifyNotificationTemplateError::NotificationTemplateDoesNotExist => {
+            BadUserInput(formatted_error)
+        }
+        ModifyNotificationTemplateError::NotificationTemplateAlreadyExists => {
+            BadUserInput(formatted_error)
+        }
+        ModifyNotificationTemplateError::NameAlreadyExists => {
+            BadUserInput("Template name must be unique".to_string())
+        }
+        ModifyNotificationTemplateError::InvalidNotificationTemplateName => {
+            BadUserInput("Template name can't be empty or contain spaces or quotes".to_string())
+        }
+        ModifyNotificationTemplateError::InvalidTemplate(s) => BadUserInput(s),
+        ModifyNotificationTemplateError::ModifiedRecordNotFound => InternalError(formatted_error),
+        ModifyNotificationTemplateError::DatabaseError(_) => InternalError(formatted_error),
+    };
+
+    Err(graphql_error.extend())
+}
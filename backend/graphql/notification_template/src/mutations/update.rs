@@ -0,0 +1,60 @@
+use async_graphql::*;
+use graphql_core::{standard_graphql_error::validate_auth, ContextExt};
+use service::{
+    auth::{Resource, ResourceAccessRequest},
+    notification_template::update::UpdateNotificationTemplate,
+};
+
+use crate::types::NotificationTemplateNode;
+
+use super::{map_error, ModifyNotificationTemplateResponse};
+
+#[derive(InputObject, Clone)]
+pub struct UpdateNotificationTemplateInput {
+    pub id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub template: Option<String>,
+}
+
+pub fn update_notification_template(
+    ctx: &Context<'_>,
+    input: UpdateNotificationTemplateInput,
+) -> Result<ModifyNotificationTemplateResponse> {
+    let user = validate_auth(
+        ctx,
+        &ResourceAccessRequest {
+            resource: Resource::ServerAdmin,
+        },
+    )?;
+
+    let service_context = ctx.service_context(Some(&user))?;
+    match service_context
+        .service_provider
+        .notification_template_service
+        .update_notification_template(&service_context, input.into())
+    {
+        Ok(notification_template) => Ok(ModifyNotificationTemplateResponse::Response(
+            NotificationTemplateNode::from_domain(notification_template),
+        )),
+        Err(error) => map_error(error),
+    }
+}
+
+impl From<UpdateNotificationTemplateInput> for UpdateNotificationTemplate {
+    fn from(
+        UpdateNotificationTemplateInput {
+            id,
+            name,
+            description,
+            template,
+        }: UpdateNotificationTemplateInput,
+    ) -> Self {
+        UpdateNotificationTemplate {
+            id,
+            name,
+            description,
+            template,
+        }
+    }
+}
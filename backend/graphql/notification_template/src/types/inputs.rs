@@ -0,0 +1,52 @@
+use async_graphql::{Enum, InputObject};
+use graphql_core::generic_filters::{EqualFilterStringInput, StringFilterInput};
+use repository::{
+    EqualFilter, NotificationTemplateFilter, NotificationTemplateSort,
+    NotificationTemplateSortField, StringFilter,
+};
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(rename_items = "camelCase")]
+pub enum NotificationTemplateSortFieldInput {
+    Name,
+}
+
+#[derive(InputObject)]
+pub struct NotificationTemplateSortInput {
+    /// Sort query result by `key`
+    key: NotificationTemplateSortFieldInput,
+    /// Sort query result is sorted descending or ascending (if not provided the default is
+    /// ascending)
+    desc: Option<bool>,
+}
+impl NotificationTemplateSortInput {
+    pub fn to_domain(self) -> NotificationTemplateSort {
+        use NotificationTemplateSortField as to;
+        use NotificationTemplateSortFieldInput as from;
+        let key = match self.key {
+            from::Name => to::Name,
+        };
+
+        NotificationTemplateSort {
+            key,
+            desc: self.desc,
+        }
+    }
+}
+
+#[derive(Clone, InputObject)]
+pub struct NotificationTemplateFilterInput {
+    pub id: Option<EqualFilterStringInput>,
+    pub name: Option<StringFilterInput>,
+    pub search: Option<String>,
+}
+
+impl From<NotificationTemplateFilterInput> for NotificationTemplateFilter {
+    fn from(f: NotificationTemplateFilterInput) -> Self {
+        NotificationTemplateFilter {
+            id: f.id.map(EqualFilter::from),
+            name: f.name.map(StringFilter::from),
+            search: f.search,
+        }
+    }
+}
@@ -0,0 +1,4 @@
+mod inputs;
+pub use inputs::*;
+mod notification_template;
+pub use notification_template::*;
@@ -0,0 +1,111 @@
+use async_graphql::{dataloader::DataLoader, Context, Object, SimpleObject, Union};
+use chrono::{DateTime, Utc};
+use graphql_core::{loader::NotificationTemplateVersionLoader, ContextExt};
+
+use repository::{NotificationTemplate, NotificationTemplateVersionRow};
+use service::ListResult;
+
+#[derive(Union)]
+pub enum NotificationTemplatesResponse {
+    Response(NotificationTemplateConnector),
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct NotificationTemplateNode {
+    pub notification_template: NotificationTemplate,
+}
+
+#[Object]
+impl NotificationTemplateNode {
+    pub async fn id(&self) -> &str {
+        &self.row().id
+    }
+    pub async fn name(&self) -> &str {
+        &self.row().name
+    }
+    pub async fn description(&self) -> &str {
+        &self.row().description
+    }
+    pub async fn template(&self) -> &str {
+        &self.row().template
+    }
+    pub async fn version(&self) -> i32 {
+        self.row().version
+    }
+    pub async fn created_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.row().created_at, Utc)
+    }
+    pub async fn updated_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.row().updated_at, Utc)
+    }
+
+    /// Every saved version of the template, newest first
+    pub async fn versions(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<NotificationTemplateVersionNode>> {
+        let loader = ctx.get_loader::<DataLoader<NotificationTemplateVersionLoader>>();
+        let result = loader
+            .load_one(self.row().id.to_string())
+            .await?
+            .unwrap_or_default();
+
+        Ok(result
+            .into_iter()
+            .map(NotificationTemplateVersionNode::from_domain)
+            .collect())
+    }
+}
+
+impl NotificationTemplateNode {
+    pub fn from_domain(notification_template: NotificationTemplate) -> NotificationTemplateNode {
+        NotificationTemplateNode {
+            notification_template,
+        }
+    }
+
+    pub fn row(&self) -> &NotificationTemplate {
+        &self.notification_template
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct NotificationTemplateVersionNode {
+    pub version: i32,
+    pub template: String,
+    pub created_at: DateTime<Utc>,
+    /// The user who saved this version
+    pub user_id: Option<String>,
+}
+
+impl NotificationTemplateVersionNode {
+    pub fn from_domain(row: NotificationTemplateVersionRow) -> Self {
+        NotificationTemplateVersionNode {
+            version: row.version,
+            template: row.template,
+            created_at: DateTime::<Utc>::from_utc(row.created_at, Utc),
+            user_id: row.user_id,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct NotificationTemplateConnector {
+    total_count: u32,
+    nodes: Vec<NotificationTemplateNode>,
+}
+
+impl NotificationTemplateConnector {
+    pub fn from_domain(
+        notification_templates: ListResult<NotificationTemplate>,
+    ) -> NotificationTemplateConnector {
+        NotificationTemplateConnector {
+            total_count: notification_templates.count,
+            nodes: notification_templates
+                .rows
+                .into_iter()
+                .map(NotificationTemplateNode::from_domain)
+                .collect(),
+        }
+    }
+}
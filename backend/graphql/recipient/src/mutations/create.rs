@@ -40,6 +40,13 @@ pub struct CreateRecipientInput {
     pub name: String,
     pub to_address: String,
     pub notification_type: NotificationTypeNode,
+    /// Local time `HH:MM`, only critical notifications are sent during quiet hours
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    /// IANA timezone name e.g. `Pacific/Auckland`, defaults to UTC
+    pub timezone: Option<String>,
+    /// Language tag e.g. `fr` or `fr-CA`, used to choose translated templates
+    pub locale: Option<String>,
 }
 
 impl From<CreateRecipientInput> for CreateRecipient {
@@ -49,6 +56,10 @@ impl From<CreateRecipientInput> for CreateRecipient {
             name,
             to_address,
             notification_type,
+            quiet_hours_start,
+            quiet_hours_end,
+            timezone,
+            locale,
         }: CreateRecipientInput,
     ) -> Self {
         CreateRecipient {
@@ -56,6 +67,10 @@ impl From<CreateRecipientInput> for CreateRecipient {
             name,
             to_address,
             notification_type: NotificationTypeNode::to_domain(notification_type),
+            quiet_hours_start,
+            quiet_hours_end,
+            timezone,
+            locale,
         }
     }
 }
@@ -73,6 +88,8 @@ fn map_error(error: ModifyRecipientError) -> Result<CreateRecipientResponse> {
         // Standard Graphql Errors
         ModifyRecipientError::RecipientAlreadyExists => BadUserInput(formatted_error),
         ModifyRecipientError::RecipientDoesNotExist => BadUserInput(formatted_error),
+        ModifyRecipientError::InvalidQuietHours(s) => BadUserInput(s),
+        ModifyRecipientError::InvalidLocale(s) => BadUserInput(s),
         ModifyRecipientError::DatabaseError(_) => InternalError(formatted_error),
         ModifyRecipientError::ModifiedRecordNotFound => InternalError(formatted_error),
         ModifyRecipientError::GenericError(s) => InternalError(s),
@@ -40,6 +40,11 @@ pub struct UpdateRecipientInput {
     pub id: String,
     pub name: Option<String>,
     pub to_address: Option<String>,
+    /// Set to an empty string to remove the quiet hours, timezone or locale
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
 }
 
 impl From<UpdateRecipientInput> for UpdateRecipient {
@@ -48,12 +53,20 @@ impl From<UpdateRecipientInput> for UpdateRecipient {
             id,
             name,
             to_address,
+            quiet_hours_start,
+            quiet_hours_end,
+            timezone,
+            locale,
         }: UpdateRecipientInput,
     ) -> Self {
         UpdateRecipient {
             id,
             name,
             to_address,
+            quiet_hours_start,
+            quiet_hours_end,
+            timezone,
+            locale,
         }
     }
 }
@@ -71,6 +84,8 @@ fn map_error(error: ModifyRecipientError) -> Result<UpdateRecipientResponse> {
         // Standard Graphql Errors
         ModifyRecipientError::RecipientDoesNotExist => BadUserInput(formatted_error),
         ModifyRecipientError::RecipientAlreadyExists => BadUserInput(formatted_error),
+        ModifyRecipientError::InvalidQuietHours(s) => BadUserInput(s),
+        ModifyRecipientError::InvalidLocale(s) => BadUserInput(s),
         ModifyRecipientError::ModifiedRecordNotFound => InternalError(formatted_error),
         ModifyRecipientError::DatabaseError(_) => InternalError(formatted_error),
         ModifyRecipientError::GenericError(s) => InternalError(s),
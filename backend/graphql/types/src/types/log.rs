@@ -35,6 +35,11 @@ pub enum LogNodeType {
     SqlRecipientListUpdated,
     NotificationQueryCreated,
     NotificationQueryUpdated,
+    NotificationEventResent,
+    NotificationEventCancelled,
+    NotificationEventRerendered,
+    NotificationTemplateCreated,
+    NotificationTemplateUpdated,
 }
 
 #[Object]
@@ -101,6 +106,11 @@ impl LogNodeType {
             }
             LogType::NotificationQueryCreated => LogNodeType::NotificationQueryCreated,
             LogType::NotificationQueryUpdated => LogNodeType::NotificationQueryUpdated,
+            LogType::NotificationEventResent => LogNodeType::NotificationEventResent,
+            LogType::NotificationEventCancelled => LogNodeType::NotificationEventCancelled,
+            LogType::NotificationEventRerendered => LogNodeType::NotificationEventRerendered,
+            LogType::NotificationTemplateCreated => LogNodeType::NotificationTemplateCreated,
+            LogType::NotificationTemplateUpdated => LogNodeType::NotificationTemplateUpdated,
         }
     }
 
@@ -124,6 +134,11 @@ impl LogNodeType {
             }
             LogNodeType::NotificationQueryCreated => LogType::NotificationQueryCreated,
             LogNodeType::NotificationQueryUpdated => LogType::NotificationQueryUpdated,
+            LogNodeType::NotificationEventResent => LogType::NotificationEventResent,
+            LogNodeType::NotificationEventCancelled => LogType::NotificationEventCancelled,
+            LogNodeType::NotificationEventRerendered => LogType::NotificationEventRerendered,
+            LogNodeType::NotificationTemplateCreated => LogType::NotificationTemplateCreated,
+            LogNodeType::NotificationTemplateUpdated => LogType::NotificationTemplateUpdated,
         }
     }
 }
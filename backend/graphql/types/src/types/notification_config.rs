@@ -48,6 +48,12 @@ impl NotificationConfigNode {
         &self.row().parameter_query_id
     }
 
+    /// JSON array of parameter definitions, each with a `name`, `type` (string, number, date or enum),
+    /// `required` flag, and `options` for enums
+    pub async fn parameter_schema(&self) -> &str {
+        &self.row().parameter_schema
+    }
+
     pub async fn recipient_ids(&self) -> &[String] {
         &self.row().recipient_ids
     }
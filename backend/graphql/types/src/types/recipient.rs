@@ -39,6 +39,18 @@ impl RecipientNode {
     pub async fn notification_type(&self) -> NotificationTypeNode {
         NotificationTypeNode::from_domain(&self.row().notification_type)
     }
+    pub async fn quiet_hours_start(&self) -> &Option<String> {
+        &self.row().quiet_hours_start
+    }
+    pub async fn quiet_hours_end(&self) -> &Option<String> {
+        &self.row().quiet_hours_end
+    }
+    pub async fn timezone(&self) -> &Option<String> {
+        &self.row().timezone
+    }
+    pub async fn locale(&self) -> &Option<String> {
+        &self.row().locale
+    }
 
     pub async fn audit_logs(
         &self,
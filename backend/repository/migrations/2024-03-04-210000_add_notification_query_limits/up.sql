@@ -0,0 +1,2 @@
+ALTER TABLE notification_query ADD COLUMN timeout_seconds INTEGER;
+ALTER TABLE notification_query ADD COLUMN max_rows INTEGER;
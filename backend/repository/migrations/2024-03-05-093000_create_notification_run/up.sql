@@ -0,0 +1,17 @@
+CREATE TABLE
+    IF NOT EXISTS notification_run (
+        id TEXT PRIMARY KEY,
+        notification_config_id TEXT NOT NULL,
+        kind TEXT NOT NULL, -- The kind of notification config, e.g. SCHEDULED or COLD_CHAIN
+        status TEXT NOT NULL,
+        started_at TIMESTAMP NOT NULL,
+        finished_at TIMESTAMP NULL,
+        parameter_sets_evaluated INTEGER NOT NULL DEFAULT 0,
+        notifications_created INTEGER NOT NULL DEFAULT 0,
+        skipped_count INTEGER NOT NULL DEFAULT 0,
+        skipped_reasons TEXT NOT NULL DEFAULT '[]', -- JSON array of strings
+        query_timings TEXT NOT NULL DEFAULT '[]', -- JSON array of query timing objects
+        error_message TEXT NULL
+    );
+
+CREATE INDEX IF NOT EXISTS notification_run_config_started_at ON notification_run (notification_config_id, started_at);
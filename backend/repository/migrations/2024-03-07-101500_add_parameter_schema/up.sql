@@ -0,0 +1 @@
+ALTER TABLE notification_config ADD COLUMN parameter_schema TEXT NOT NULL DEFAULT '[]';
@@ -0,0 +1,3 @@
+ALTER TABLE notification_event ADD COLUMN dedup_key TEXT; -- Identifies notifications with the same recipient and content
+
+CREATE INDEX IF NOT EXISTS notification_event_dedup_key_created_at ON notification_event (dedup_key, created_at);
@@ -0,0 +1,5 @@
+ALTER TABLE notification_event ADD COLUMN priority TEXT NOT NULL DEFAULT 'NORMAL';
+
+ALTER TABLE recipient ADD COLUMN quiet_hours_start TEXT; -- Local time in the recipient's timezone e.g. 22:00
+ALTER TABLE recipient ADD COLUMN quiet_hours_end TEXT; -- e.g. 06:00
+ALTER TABLE recipient ADD COLUMN timezone TEXT; -- IANA timezone name e.g. Pacific/Auckland, UTC if not set
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS notification_template_version;
+DROP TABLE IF EXISTS notification_template;
@@ -0,0 +1,22 @@
+CREATE TABLE
+    IF NOT EXISTS notification_template (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE, -- The name used to reference the template, if it is the same as a file template e.g. coldchain/temperature.md it replaces it
+        description TEXT NOT NULL DEFAULT '',
+        template TEXT NOT NULL,
+        version INTEGER NOT NULL DEFAULT 1,
+        created_at TIMESTAMP NOT NULL,
+        updated_at TIMESTAMP NOT NULL
+    );
+
+CREATE TABLE
+    IF NOT EXISTS notification_template_version (
+        id TEXT PRIMARY KEY,
+        notification_template_id TEXT NOT NULL REFERENCES notification_template (id),
+        version INTEGER NOT NULL,
+        template TEXT NOT NULL,
+        created_at TIMESTAMP NOT NULL,
+        user_id TEXT NULL -- The user who saved this version
+    );
+
+CREATE INDEX IF NOT EXISTS notification_template_version_template_id ON notification_template_version (notification_template_id, version);
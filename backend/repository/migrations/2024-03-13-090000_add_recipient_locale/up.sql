@@ -0,0 +1 @@
+ALTER TABLE recipient ADD COLUMN locale TEXT; -- Language tag e.g. fr or fr-CA, the default templates are used if not set
@@ -0,0 +1 @@
+ALTER TABLE notification_event ADD COLUMN fallback_reason TEXT; -- Why the notification was sent without its formatting, e.g. telegram couldn't parse the markdown
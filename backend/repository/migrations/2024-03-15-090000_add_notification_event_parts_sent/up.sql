@@ -0,0 +1 @@
+ALTER TABLE notification_event ADD COLUMN parts_sent INTEGER NOT NULL DEFAULT 0; -- How many parts of a long message have been sent, so a retry carries on from the first unsent part
@@ -5,6 +5,7 @@ use crate::repository_error::RepositoryError;
 use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use diesel_derive_enum::DbEnum;
+use serde::Serialize;
 
 table! {
     audit_log (id) {
@@ -16,8 +17,9 @@ table! {
     }
 }
 
-#[derive(DbEnum, Debug, Clone, PartialEq, Eq)]
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq, Serialize)]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum LogType {
     UserLoggedIn,
     UserAccountCreated,
@@ -35,9 +37,14 @@ pub enum LogType {
     SqlRecipientListUpdated,
     NotificationQueryCreated,
     NotificationQueryUpdated,
+    NotificationEventResent,
+    NotificationEventCancelled,
+    NotificationEventRerendered,
+    NotificationTemplateCreated,
+    NotificationTemplateUpdated,
 }
 
-#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq)]
+#[derive(Clone, Queryable, Insertable, AsChangeset, Debug, PartialEq, Serialize)]
 #[changeset_options(treat_none_as_null = "true")]
 #[table_name = "audit_log"]
 pub struct AuditLogRow {
@@ -71,6 +78,27 @@ impl<'a> AuditLogRowRepository<'a> {
             .optional()?;
         Ok(result)
     }
+
+    /// Oldest first, up to `limit` entries from before `before`
+    pub fn find_older_than(
+        &self,
+        before: NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<AuditLogRow>, RepositoryError> {
+        let result = audit_log_dsl::audit_log
+            .filter(audit_log_dsl::datetime.lt(before))
+            .order(audit_log_dsl::datetime.asc())
+            .limit(limit)
+            .load(&self.connection.connection)?;
+        Ok(result)
+    }
+
+    pub fn delete_by_ids(&self, ids: &[String]) -> Result<usize, RepositoryError> {
+        let deleted =
+            diesel::delete(audit_log_dsl::audit_log.filter(audit_log_dsl::id.eq_any(ids)))
+                .execute(&self.connection.connection)?;
+        Ok(deleted)
+    }
 }
 
 #[cfg(test)]
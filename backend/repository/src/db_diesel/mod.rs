@@ -15,6 +15,11 @@ pub mod notification_event;
 pub mod notification_event_row;
 pub mod notification_query;
 pub mod notification_query_row;
+pub mod notification_run;
+pub mod notification_run_row;
+pub mod notification_template;
+pub mod notification_template_row;
+pub mod notification_template_version_row;
 pub mod plugin_store;
 pub mod recipient;
 pub mod recipient_list;
@@ -40,6 +45,11 @@ pub use notification_event::*;
 pub use notification_event_row::*;
 pub use notification_query::*;
 pub use notification_query_row::*;
+pub use notification_run::*;
+pub use notification_run_row::*;
+pub use notification_template::*;
+pub use notification_template_row::*;
+pub use notification_template_version_row::*;
 pub use plugin_store::*;
 pub use recipient::*;
 pub use recipient_list::*;
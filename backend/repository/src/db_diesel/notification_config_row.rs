@@ -20,6 +20,7 @@ table! {
         sql_recipient_list_ids -> Text,
         last_run_datetime -> Nullable<Timestamp>,
         next_due_datetime -> Nullable<Timestamp>,
+        parameter_schema -> Text,
     }
 }
 
@@ -93,6 +94,7 @@ pub struct NotificationConfigRow {
     pub sql_recipient_list_ids: String, // JSON array of strings (ids)
     pub last_run_datetime: Option<NaiveDateTime>,
     pub next_due_datetime: Option<NaiveDateTime>,
+    pub parameter_schema: String, // JSON array of parameter definitions
 }
 
 pub struct NotificationConfigRowRepository<'a> {
@@ -170,4 +172,17 @@ impl<'a> NotificationConfigRowRepository<'a> {
         query.execute(&self.connection.connection)?;
         Ok(())
     }
+
+    pub fn set_status_by_id(
+        &self,
+        id: &str,
+        status: NotificationConfigStatus,
+    ) -> Result<(), RepositoryError> {
+        let query = diesel::update(notification_config_dsl::notification_config)
+            .filter(notification_config_dsl::id.eq(id))
+            .set(notification_config_dsl::status.eq(status));
+
+        query.execute(&self.connection.connection)?;
+        Ok(())
+    }
 }
@@ -3,6 +3,7 @@ use crate::{repository_error::RepositoryError, NotificationType};
 use diesel::prelude::*;
 use diesel_derive_enum::DbEnum;
 use notification_event::dsl as notification_event_dsl;
+use serde::{Deserialize, Serialize};
 
 /*
 CREATE TABLE
@@ -39,22 +40,48 @@ table! {
         send_attempts -> Integer,
         error_message -> Nullable<Text>,
         context -> Nullable<Text>,
+        dedup_key -> Nullable<Text>,
+        priority -> crate::db_diesel::notification_event_row::NotificationPriorityMapping,
+        fallback_reason -> Nullable<Text>,
+        parts_sent -> Integer,
     }
 }
 
-#[derive(DbEnum, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq, Hash, Default, Serialize)]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum NotificationEventStatus {
     #[default]
     Queued,
     Sent,
-    Errored, // Errored will be re-tried
-    Failed,  // Failed will not be re-tried
+    Errored,      // Errored will be re-tried
+    Failed,       // Failed will not be re-tried
+    Deduplicated, // The same notification was recently queued for this recipient, so this one won't be sent
+    Cancelled,    // Cancelled by a user before it was sent
+}
+
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationPriority {
+    #[default]
+    Normal, // Held until the end of the recipient's quiet hours
+    Critical, // Always sent straight away, e.g. temperature alerts
 }
 
 #[derive(
-    Clone, Queryable, Insertable, Identifiable, Debug, PartialEq, Eq, AsChangeset, Default,
+    Clone,
+    Queryable,
+    Insertable,
+    Identifiable,
+    Debug,
+    PartialEq,
+    Eq,
+    AsChangeset,
+    Default,
+    Serialize,
 )]
+#[changeset_options(treat_none_as_null = "true")]
 #[table_name = "notification_event"]
 pub struct NotificationEventRow {
     pub id: String,
@@ -71,6 +98,12 @@ pub struct NotificationEventRow {
     pub send_attempts: i32,
     pub error_message: Option<String>,
     pub context: Option<String>, // JSON object, the tera context for the event
+    pub dedup_key: Option<String>,
+    pub priority: NotificationPriority,
+    /// Set if the notification was sent without its formatting, e.g. when telegram couldn't parse the markdown
+    pub fallback_reason: Option<String>,
+    /// How many of the messages a notification is sent as (e.g. the parts of a long telegram message, then any charts) have been sent
+    pub parts_sent: i32,
 }
 
 pub struct NotificationEventRowRepository<'a> {
@@ -104,19 +137,89 @@ impl<'a> NotificationEventRowRepository<'a> {
         Ok(())
     }
 
+    pub fn find_one_by_id(
+        &self,
+        id: &str,
+    ) -> Result<Option<NotificationEventRow>, RepositoryError> {
+        let result = notification_event_dsl::notification_event
+            .filter(notification_event_dsl::id.eq(id))
+            .first(&self.connection.connection)
+            .optional()?;
+        Ok(result)
+    }
+
+    pub fn find_many_by_ids(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<NotificationEventRow>, RepositoryError> {
+        let result = notification_event_dsl::notification_event
+            .filter(notification_event_dsl::id.eq_any(ids))
+            .load::<NotificationEventRow>(&self.connection.connection)?;
+        Ok(result)
+    }
+
+    /// Notifications that are waiting to be sent, or re-tried, and are due now
     pub fn un_sent(&self) -> Result<Vec<NotificationEventRow>, RepositoryError> {
+        let now = chrono::Utc::now().naive_utc();
         let result = notification_event_dsl::notification_event
+            .filter(notification_event_dsl::status.eq_any(vec![
+                NotificationEventStatus::Queued,
+                NotificationEventStatus::Errored,
+            ]))
             .filter(
-                notification_event_dsl::status
-                    .eq(NotificationEventStatus::Queued)
-                    .or(notification_event_dsl::status
-                        .eq(NotificationEventStatus::Errored)
-                        .and(notification_event_dsl::retry_at.le(diesel::dsl::now))),
+                notification_event_dsl::retry_at
+                    .is_null()
+                    .or(notification_event_dsl::retry_at.le(now)),
             )
             .load::<NotificationEventRow>(&self.connection.connection)?;
         Ok(result)
     }
 
+    /// The most recent notification with this dedup key created since `since`, that has been or will be sent
+    pub fn find_recent_by_dedup_key(
+        &self,
+        dedup_key: &str,
+        since: chrono::NaiveDateTime,
+    ) -> Result<Option<NotificationEventRow>, RepositoryError> {
+        let result = notification_event_dsl::notification_event
+            .filter(notification_event_dsl::dedup_key.eq(dedup_key))
+            .filter(notification_event_dsl::created_at.ge(since))
+            .filter(notification_event_dsl::status.eq_any(vec![
+                NotificationEventStatus::Queued,
+                NotificationEventStatus::Sent,
+                NotificationEventStatus::Errored,
+            ]))
+            .order(notification_event_dsl::created_at.desc())
+            .first::<NotificationEventRow>(&self.connection.connection)
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Oldest first, up to `limit` notifications with one of the statuses created before `before`
+    pub fn find_older_than(
+        &self,
+        statuses: Vec<NotificationEventStatus>,
+        before: chrono::NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<NotificationEventRow>, RepositoryError> {
+        let result = notification_event_dsl::notification_event
+            .filter(notification_event_dsl::status.eq_any(statuses))
+            .filter(notification_event_dsl::created_at.lt(before))
+            .order(notification_event_dsl::created_at.asc())
+            .limit(limit)
+            .load::<NotificationEventRow>(&self.connection.connection)?;
+        Ok(result)
+    }
+
+    pub fn delete_by_ids(&self, ids: &[String]) -> Result<usize, RepositoryError> {
+        let deleted = diesel::delete(
+            notification_event_dsl::notification_event
+                .filter(notification_event_dsl::id.eq_any(ids)),
+        )
+        .execute(&self.connection.connection)?;
+        Ok(deleted)
+    }
+
     // Used for tests only
     pub fn errors(&self) -> Result<Vec<NotificationEventRow>, RepositoryError> {
         let result = notification_event_dsl::notification_event
@@ -15,6 +15,8 @@ table! {
         required_parameters -> Text,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        timeout_seconds -> Nullable<Integer>,
+        max_rows -> Nullable<Integer>,
     }
 }
 
@@ -22,6 +24,7 @@ table! {
     Clone, Queryable, Identifiable, Insertable, AsChangeset, Debug, PartialEq, Eq, Default,
 )]
 #[table_name = "notification_query"]
+#[changeset_options(treat_none_as_null = "true")]
 pub struct NotificationQueryRow {
     pub id: String,
     pub name: String,
@@ -31,6 +34,8 @@ pub struct NotificationQueryRow {
     pub required_parameters: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub timeout_seconds: Option<i32>, // Cancel the query if it runs longer than this
+    pub max_rows: Option<i32>,        // Only return this many rows from the query
 }
 
 pub struct NotificationQueryRowRepository<'a> {
@@ -0,0 +1,141 @@
+use super::{
+    notification_run_row::{notification_run, notification_run::dsl as notification_run_dsl},
+    DBType, NotificationRunRow, StorageConnection,
+};
+use crate::{
+    diesel_macros::{apply_date_time_filter, apply_equal_filter, apply_sort},
+    repository_error::RepositoryError,
+    DatetimeFilter, EqualFilter, NotificationRunStatus, Pagination, Sort,
+};
+
+use diesel::{dsl::IntoBoxed, prelude::*};
+
+pub type NotificationRun = NotificationRunRow;
+
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct NotificationRunFilter {
+    pub id: Option<EqualFilter<String>>,
+    pub notification_config_id: Option<EqualFilter<String>>,
+    pub status: Option<EqualFilter<NotificationRunStatus>>,
+    pub started_at: Option<DatetimeFilter>,
+}
+
+impl NotificationRunFilter {
+    pub fn new() -> NotificationRunFilter {
+        NotificationRunFilter::default()
+    }
+
+    pub fn id(mut self, filter: EqualFilter<String>) -> Self {
+        self.id = Some(filter);
+        self
+    }
+
+    pub fn notification_config_id(mut self, filter: EqualFilter<String>) -> Self {
+        self.notification_config_id = Some(filter);
+        self
+    }
+
+    pub fn status(mut self, filter: EqualFilter<NotificationRunStatus>) -> Self {
+        self.status = Some(filter);
+        self
+    }
+
+    pub fn started_at(mut self, filter: DatetimeFilter) -> Self {
+        self.started_at = Some(filter);
+        self
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum NotificationRunSortField {
+    StartedAt,
+    Status,
+}
+
+pub type NotificationRunSort = Sort<NotificationRunSortField>;
+
+pub struct NotificationRunRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> NotificationRunRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        NotificationRunRepository { connection }
+    }
+
+    pub fn count(&self, filter: Option<NotificationRunFilter>) -> Result<i64, RepositoryError> {
+        let query = create_filtered_query(filter);
+
+        Ok(query.count().get_result(&self.connection.connection)?)
+    }
+
+    pub fn query_by_filter(
+        &self,
+        filter: NotificationRunFilter,
+    ) -> Result<Vec<NotificationRun>, RepositoryError> {
+        self.query(Pagination::new(), Some(filter), None)
+    }
+
+    pub fn query_one(
+        &self,
+        filter: NotificationRunFilter,
+    ) -> Result<Option<NotificationRun>, RepositoryError> {
+        Ok(self.query_by_filter(filter)?.pop())
+    }
+
+    pub fn query(
+        &self,
+        pagination: Pagination,
+        filter: Option<NotificationRunFilter>,
+        sort: Option<NotificationRunSort>,
+    ) -> Result<Vec<NotificationRun>, RepositoryError> {
+        let mut query = create_filtered_query(filter);
+
+        if let Some(sort) = sort {
+            match sort.key {
+                NotificationRunSortField::StartedAt => {
+                    apply_sort!(query, sort, notification_run_dsl::started_at);
+                }
+                NotificationRunSortField::Status => {
+                    apply_sort!(query, sort, notification_run_dsl::status);
+                }
+            }
+        } else {
+            // Most recent runs first
+            query = query.order(notification_run_dsl::started_at.desc())
+        }
+
+        let final_query = query
+            .offset(pagination.offset as i64)
+            .limit(pagination.limit as i64);
+
+        let result = final_query.load::<NotificationRun>(&self.connection.connection)?;
+        Ok(result)
+    }
+}
+
+type BoxedQuery = IntoBoxed<'static, notification_run::table, DBType>;
+
+fn create_filtered_query(filter: Option<NotificationRunFilter>) -> BoxedQuery {
+    let mut query = notification_run_dsl::notification_run.into_boxed();
+
+    if let Some(f) = filter {
+        let NotificationRunFilter {
+            id,
+            notification_config_id,
+            status,
+            started_at,
+        } = f;
+
+        apply_equal_filter!(query, id, notification_run_dsl::id);
+        apply_equal_filter!(
+            query,
+            notification_config_id,
+            notification_run_dsl::notification_config_id
+        );
+        apply_equal_filter!(query, status, notification_run_dsl::status);
+        apply_date_time_filter!(query, started_at, notification_run_dsl::started_at);
+    }
+
+    query
+}
@@ -0,0 +1,97 @@
+use super::{
+    notification_run_row::notification_run::dsl as notification_run_dsl, StorageConnection,
+};
+use crate::{repository_error::RepositoryError, NotificationConfigKind};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel_derive_enum::DbEnum;
+
+table! {
+    notification_run (id) {
+        id -> Text,
+        notification_config_id -> Text,
+        kind -> crate::db_diesel::notification_config_row::NotificationConfigKindMapping,
+        status -> crate::db_diesel::notification_run_row::NotificationRunStatusMapping,
+        started_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+        parameter_sets_evaluated -> Integer,
+        notifications_created -> Integer,
+        skipped_count -> Integer,
+        skipped_reasons -> Text,
+        query_timings -> Text,
+        error_message -> Nullable<Text>,
+    }
+}
+
+#[derive(DbEnum, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum NotificationRunStatus {
+    #[default]
+    Running, // Still running, or the server stopped before the run finished
+    Completed,
+    Errored,
+}
+
+#[derive(
+    Clone, Queryable, Identifiable, Insertable, AsChangeset, Debug, PartialEq, Eq, Default,
+)]
+#[table_name = "notification_run"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct NotificationRunRow {
+    pub id: String,
+    pub notification_config_id: String,
+    pub kind: NotificationConfigKind,
+    pub status: NotificationRunStatus,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub parameter_sets_evaluated: i32,
+    pub notifications_created: i32,
+    pub skipped_count: i32,
+    pub skipped_reasons: String, // JSON array of strings
+    pub query_timings: String,   // JSON array of objects {query, parameters, duration_ms, error}
+    pub error_message: Option<String>,
+}
+
+pub struct NotificationRunRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> NotificationRunRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        NotificationRunRowRepository { connection }
+    }
+
+    pub fn insert_one(&self, row: &NotificationRunRow) -> Result<(), RepositoryError> {
+        diesel::insert_into(notification_run_dsl::notification_run)
+            .values(row)
+            .execute(&self.connection.connection)?;
+        Ok(())
+    }
+
+    pub fn update_one(&self, row: &NotificationRunRow) -> Result<(), RepositoryError> {
+        diesel::update(row)
+            .set(row)
+            .execute(&self.connection.connection)?;
+        Ok(())
+    }
+
+    pub fn find_one_by_id(&self, id: &str) -> Result<Option<NotificationRunRow>, RepositoryError> {
+        let result = notification_run_dsl::notification_run
+            .filter(notification_run_dsl::id.eq(id))
+            .first(&self.connection.connection)
+            .optional()?;
+        Ok(result)
+    }
+
+    pub fn find_latest_by_config_id(
+        &self,
+        notification_config_id: &str,
+    ) -> Result<Option<NotificationRunRow>, RepositoryError> {
+        let result = notification_run_dsl::notification_run
+            .filter(notification_run_dsl::notification_config_id.eq(notification_config_id))
+            .order(notification_run_dsl::started_at.desc())
+            .first(&self.connection.connection)
+            .optional()?;
+        Ok(result)
+    }
+}
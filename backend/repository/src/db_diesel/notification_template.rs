@@ -0,0 +1,143 @@
+use super::{
+    notification_template_row::{
+        notification_template, notification_template::dsl as notification_template_dsl,
+    },
+    DBType, NotificationTemplateRow, StorageConnection,
+};
+use crate::{
+    diesel_macros::{apply_equal_filter, apply_sort_no_case, apply_string_filter},
+    repository_error::RepositoryError,
+    EqualFilter, Pagination, Sort, StringFilter,
+};
+
+use diesel::{dsl::IntoBoxed, prelude::*};
+
+pub type NotificationTemplate = NotificationTemplateRow;
+
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct NotificationTemplateFilter {
+    pub id: Option<EqualFilter<String>>,
+    pub name: Option<StringFilter>,
+    pub search: Option<String>,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum NotificationTemplateSortField {
+    Name,
+    Id,
+}
+
+pub type NotificationTemplateSort = Sort<NotificationTemplateSortField>;
+
+pub struct NotificationTemplateRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> NotificationTemplateRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        NotificationTemplateRepository { connection }
+    }
+
+    pub fn count(
+        &self,
+        filter: Option<NotificationTemplateFilter>,
+    ) -> Result<i64, RepositoryError> {
+        let query = create_filtered_query(filter);
+
+        Ok(query.count().get_result(&self.connection.connection)?)
+    }
+
+    pub fn query_by_filter(
+        &self,
+        filter: NotificationTemplateFilter,
+    ) -> Result<Vec<NotificationTemplate>, RepositoryError> {
+        self.query(Pagination::new(), Some(filter), None)
+    }
+
+    pub fn query_one(
+        &self,
+        filter: NotificationTemplateFilter,
+    ) -> Result<Option<NotificationTemplate>, RepositoryError> {
+        Ok(self.query_by_filter(filter)?.pop())
+    }
+
+    pub fn query(
+        &self,
+        pagination: Pagination,
+        filter: Option<NotificationTemplateFilter>,
+        sort: Option<NotificationTemplateSort>,
+    ) -> Result<Vec<NotificationTemplate>, RepositoryError> {
+        let mut query = create_filtered_query(filter);
+
+        if let Some(sort) = sort {
+            match sort.key {
+                NotificationTemplateSortField::Name => {
+                    apply_sort_no_case!(query, sort, notification_template_dsl::name);
+                }
+                NotificationTemplateSortField::Id => {
+                    apply_sort_no_case!(query, sort, notification_template_dsl::id);
+                }
+            }
+        } else {
+            query = query.order(notification_template_dsl::name.asc())
+        }
+
+        let final_query = query
+            .offset(pagination.offset as i64)
+            .limit(pagination.limit as i64);
+
+        // // Debug diesel query
+        // println!(
+        //     "{}",
+        //     diesel::debug_query::<DBType, _>(&final_query).to_string()
+        // );
+
+        let result = final_query.load::<NotificationTemplate>(&self.connection.connection)?;
+        Ok(result)
+    }
+}
+
+type BoxedQuery = IntoBoxed<'static, notification_template::table, DBType>;
+
+fn create_filtered_query(filter: Option<NotificationTemplateFilter>) -> BoxedQuery {
+    let mut query = notification_template_dsl::notification_template.into_boxed();
+
+    if let Some(f) = filter {
+        let NotificationTemplateFilter { id, name, search } = f;
+
+        apply_equal_filter!(query, id, notification_template_dsl::id);
+        apply_string_filter!(query, name, notification_template_dsl::name);
+
+        if let Some(search) = search {
+            let search_term = format!("%{}%", search);
+            query = query.filter(
+                notification_template_dsl::name
+                    .like(search_term.clone())
+                    .or(notification_template_dsl::description.like(search_term.clone()))
+                    .or(notification_template_dsl::template.like(search_term.clone())),
+            );
+        }
+    }
+
+    query
+}
+
+impl NotificationTemplateFilter {
+    pub fn new() -> NotificationTemplateFilter {
+        NotificationTemplateFilter::default()
+    }
+
+    pub fn id(mut self, filter: EqualFilter<String>) -> Self {
+        self.id = Some(filter);
+        self
+    }
+    pub fn name(mut self, filter: StringFilter) -> Self {
+        self.name = Some(filter);
+        self
+    }
+
+    pub fn search(mut self, filter: String) -> Self {
+        self.search = Some(filter);
+        self
+    }
+}
@@ -0,0 +1,83 @@
+use super::{
+    notification_template_row::notification_template::dsl as notification_template_dsl,
+    StorageConnection,
+};
+use crate::repository_error::RepositoryError;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+table! {
+    notification_template (id) {
+        id -> Text,
+        name -> Text,
+        description -> Text,
+        template -> Text,
+        version -> Integer,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+#[derive(
+    Clone, Queryable, Identifiable, Insertable, AsChangeset, Debug, PartialEq, Eq, Default,
+)]
+#[table_name = "notification_template"]
+pub struct NotificationTemplateRow {
+    pub id: String,
+    pub name: String, // e.g. coldchain/temperature.md, replaces the file template with the same name
+    pub description: String,
+    pub template: String,
+    pub version: i32, // Incremented each time the template changes
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+pub struct NotificationTemplateRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> NotificationTemplateRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        NotificationTemplateRowRepository { connection }
+    }
+
+    pub fn insert_one(&self, row: &NotificationTemplateRow) -> Result<(), RepositoryError> {
+        let query =
+            diesel::insert_into(notification_template_dsl::notification_template).values(row);
+        query.execute(&self.connection.connection)?;
+        Ok(())
+    }
+
+    pub fn update_one(&self, row: &NotificationTemplateRow) -> Result<(), RepositoryError> {
+        let query = diesel::update(row).set(row);
+        query.execute(&self.connection.connection)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, notification_template_id: &str) -> Result<(), RepositoryError> {
+        diesel::delete(
+            notification_template_dsl::notification_template
+                .filter(notification_template_dsl::id.eq(notification_template_id)),
+        )
+        .execute(&self.connection.connection)?;
+        Ok(())
+    }
+
+    pub fn find_one_by_id(
+        &self,
+        id: &str,
+    ) -> Result<Option<NotificationTemplateRow>, RepositoryError> {
+        let result = notification_template_dsl::notification_template
+            .filter(notification_template_dsl::id.eq(id))
+            .first(&self.connection.connection)
+            .optional()?;
+        Ok(result)
+    }
+
+    pub fn find_all(&self) -> Result<Vec<NotificationTemplateRow>, RepositoryError> {
+        let result = notification_template_dsl::notification_template
+            .order(notification_template_dsl::name.asc())
+            .load(&self.connection.connection)?;
+        Ok(result)
+    }
+}
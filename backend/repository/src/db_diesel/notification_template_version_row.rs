@@ -0,0 +1,77 @@
+use super::{
+    notification_template_version_row::notification_template_version::dsl as notification_template_version_dsl,
+    StorageConnection,
+};
+use crate::repository_error::RepositoryError;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+table! {
+    notification_template_version (id) {
+        id -> Text,
+        notification_template_id -> Text,
+        version -> Integer,
+        template -> Text,
+        created_at -> Timestamp,
+        user_id -> Nullable<Text>,
+    }
+}
+
+/// A copy of each version of a notification template, so changes can be reviewed or reverted
+#[derive(Clone, Queryable, Insertable, Debug, PartialEq, Eq, Default)]
+#[table_name = "notification_template_version"]
+pub struct NotificationTemplateVersionRow {
+    pub id: String,
+    pub notification_template_id: String,
+    pub version: i32,
+    pub template: String,
+    pub created_at: NaiveDateTime,
+    pub user_id: Option<String>,
+}
+
+pub struct NotificationTemplateVersionRowRepository<'a> {
+    connection: &'a StorageConnection,
+}
+
+impl<'a> NotificationTemplateVersionRowRepository<'a> {
+    pub fn new(connection: &'a StorageConnection) -> Self {
+        NotificationTemplateVersionRowRepository { connection }
+    }
+
+    pub fn insert_one(&self, row: &NotificationTemplateVersionRow) -> Result<(), RepositoryError> {
+        let query =
+            diesel::insert_into(notification_template_version_dsl::notification_template_version)
+                .values(row);
+        query.execute(&self.connection.connection)?;
+        Ok(())
+    }
+
+    /// Returns the newest version first
+    pub fn find_many_by_template_id(
+        &self,
+        notification_template_id: &str,
+    ) -> Result<Vec<NotificationTemplateVersionRow>, RepositoryError> {
+        let result = notification_template_version_dsl::notification_template_version
+            .filter(
+                notification_template_version_dsl::notification_template_id
+                    .eq(notification_template_id),
+            )
+            .order(notification_template_version_dsl::version.desc())
+            .load(&self.connection.connection)?;
+        Ok(result)
+    }
+
+    pub fn delete_by_template_id(
+        &self,
+        notification_template_id: &str,
+    ) -> Result<(), RepositoryError> {
+        diesel::delete(
+            notification_template_version_dsl::notification_template_version.filter(
+                notification_template_version_dsl::notification_template_id
+                    .eq(notification_template_id),
+            ),
+        )
+        .execute(&self.connection.connection)?;
+        Ok(())
+    }
+}
@@ -14,6 +14,10 @@ table! {
         notification_type -> crate::db_diesel::recipient_row::NotificationTypeMapping,
         to_address -> Text,
         deleted_datetime -> Nullable<Timestamp>,
+        quiet_hours_start -> Nullable<Text>,
+        quiet_hours_end -> Nullable<Text>,
+        timezone -> Nullable<Text>,
+        locale -> Nullable<Text>,
     }
 }
 
@@ -54,12 +58,20 @@ impl NotificationType {
     Clone, Queryable, Identifiable, Insertable, AsChangeset, Debug, PartialEq, Eq, Default,
 )]
 #[table_name = "recipient"]
+#[changeset_options(treat_none_as_null = "true")]
 pub struct RecipientRow {
     pub id: String,
     pub name: String,
     pub notification_type: NotificationType,
     pub to_address: String,
     pub deleted_datetime: Option<NaiveDateTime>,
+    /// Start of the recipient's quiet hours as local time `HH:MM`, low priority notifications aren't sent during quiet hours
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    /// IANA timezone name for the quiet hours, e.g. `Pacific/Auckland`. UTC if not set
+    pub timezone: Option<String>,
+    /// Language tag e.g. `fr` or `fr-CA`, used to choose the templates and formatting for the recipient's notifications
+    pub locale: Option<String>,
 }
 
 pub struct RecipientRowRepository<'a> {
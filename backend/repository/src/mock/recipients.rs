@@ -19,6 +19,7 @@ pub fn mock_recipient_a() -> RecipientRow {
         notification_type: NotificationType::Email,
         to_address: String::from("a@openmsupply.foundation"),
         deleted_datetime: None,
+        ..Default::default()
     }
 }
 
@@ -29,6 +30,7 @@ pub fn mock_recipient_aa() -> RecipientRow {
         notification_type: NotificationType::Email,
         to_address: String::from("aa@openmsupply.foundation"),
         deleted_datetime: None,
+        ..Default::default()
     }
 }
 
@@ -39,6 +41,7 @@ pub fn mock_recipient_b() -> RecipientRow {
         notification_type: NotificationType::Email,
         to_address: String::from("b@openmsupply.foundation"),
         deleted_datetime: None,
+        ..Default::default()
     }
 }
 
@@ -49,6 +52,7 @@ pub fn mock_recipient_c() -> RecipientRow {
         notification_type: NotificationType::Telegram,
         to_address: String::from("chat_id_c"),
         deleted_datetime: None,
+        ..Default::default()
     }
 }
 
@@ -61,5 +65,6 @@ pub fn mock_recipient_d_deleted() -> RecipientRow {
         deleted_datetime: Some(
             NaiveDateTime::parse_from_str("2023-01-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap(),
         ),
+        ..Default::default()
     }
 }
@@ -29,6 +29,7 @@ mod repository_test {
 
     use crate::{
         backup_sqlite, mock::MockDataInserts, test_db, KeyValueStoreRepository, KeyValueType,
+        NotificationEventRow, NotificationEventRowRepository, NotificationEventStatus,
         UserAccountRowRepository,
     };
 
@@ -75,6 +76,58 @@ mod repository_test {
         assert_eq!(result, None);
     }
 
+    #[actix_rt::test]
+    async fn test_notification_event_un_sent() {
+        let (_, connection, _, _) =
+            test_db::setup_all("notification_event_un_sent", MockDataInserts::none()).await;
+
+        let repo = NotificationEventRowRepository::new(&connection);
+        let now = chrono::Utc::now().naive_utc();
+        let rows = vec![
+            ("queued", NotificationEventStatus::Queued, None),
+            (
+                "queued_later",
+                NotificationEventStatus::Queued,
+                Some(now + chrono::Duration::minutes(5)),
+            ),
+            (
+                "errored_due",
+                NotificationEventStatus::Errored,
+                Some(now - chrono::Duration::minutes(5)),
+            ),
+            (
+                "errored_later",
+                NotificationEventStatus::Errored,
+                Some(now + chrono::Duration::minutes(5)),
+            ),
+            (
+                "errored_no_retry_at",
+                NotificationEventStatus::Errored,
+                None,
+            ),
+            ("sent", NotificationEventStatus::Sent, None),
+            ("failed", NotificationEventStatus::Failed, None),
+        ];
+        for (id, status, retry_at) in rows {
+            repo.insert_one(&NotificationEventRow {
+                id: id.to_string(),
+                status,
+                retry_at,
+                created_at: now,
+                updated_at: now,
+                ..Default::default()
+            })
+            .unwrap();
+        }
+
+        let mut un_sent: Vec<String> = repo.un_sent().unwrap().into_iter().map(|r| r.id).collect();
+        un_sent.sort();
+        assert_eq!(
+            un_sent,
+            vec!["errored_due", "errored_no_retry_at", "queued"]
+        );
+    }
+
     #[actix_rt::test]
     async fn test_backup() {
         let (_, connection, _, _) =
@@ -0,0 +1,164 @@
+use chrono::{Duration, NaiveDateTime};
+use repository::{NotificationEventRowRepository, NotificationEventStatus};
+use serde::{Deserialize, Serialize};
+use service::service_provider::ServiceContext;
+use util::hash::sha256;
+
+use crate::{NotificationError, PLUGIN_NAME};
+
+/// Records what was last sent for a parameter set, so we can tell if the data has changed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SentState {
+    pub data_hash: String,
+    pub sent_datetime: NaiveDateTime,
+    /// The notification events created for the data, if none of them are sent the data is sent again next time
+    #[serde(default)]
+    pub event_ids: Vec<String>,
+}
+
+impl SentState {
+    pub fn from_string(json_string: &str) -> Result<Self, NotificationError> {
+        serde_json::from_str(json_string)
+            .map_err(|e| NotificationError::InternalError(format!("{:?}", e)))
+    }
+
+    pub fn to_json_string(&self) -> Result<String, NotificationError> {
+        serde_json::to_string(self)
+            .map_err(|e| NotificationError::InternalError(format!("{:?}", e)))
+    }
+
+    /// Returns true if the data is the same as last time, and the heartbeat period (if any) hasn't elapsed
+    pub fn is_unchanged(
+        &self,
+        data_hash: &str,
+        now: NaiveDateTime,
+        send_unchanged_after_days: Option<u32>,
+    ) -> bool {
+        if self.data_hash != data_hash {
+            return false;
+        }
+        match send_unchanged_after_days {
+            Some(days) => now < self.sent_datetime + Duration::days(days as i64),
+            None => true,
+        }
+    }
+}
+
+/// Returns false if none of the notification events for the sent state were, or will be, sent.
+/// e.g. they all failed or were cancelled, so the data still needs to be sent.
+pub fn is_delivered(ctx: &ServiceContext, state: &SentState) -> Result<bool, NotificationError> {
+    // States recorded before the events were tracked
+    if state.event_ids.is_empty() {
+        return Ok(true);
+    }
+
+    let events = NotificationEventRowRepository::new(&ctx.connection)
+        .find_many_by_ids(&state.event_ids)
+        .map_err(|e| NotificationError::InternalError(format!("{:?}", e)))?;
+    Ok(events.iter().any(|event| {
+        matches!(
+            event.status,
+            NotificationEventStatus::Queued
+                | NotificationEventStatus::Errored
+                | NotificationEventStatus::Sent
+        )
+    }))
+}
+
+pub fn hash_template_data(template_data: &serde_json::Value) -> String {
+    // serde_json sorts object keys, so the same data always produces the same string
+    sha256(&template_data.to_string())
+}
+
+/// The key is unique per notification config and parameter set
+pub fn sent_state_key(config_id: &str, parameters: &serde_json::Value) -> String {
+    format!(
+        "sent_state_{}_{}",
+        config_id,
+        sha256(&parameters.to_string())
+    )
+}
+
+pub fn get_sent_state(
+    ctx: &ServiceContext,
+    key: &str,
+) -> Result<Option<SentState>, NotificationError> {
+    let value = ctx
+        .service_provider
+        .plugin_service
+        .get_value(ctx, PLUGIN_NAME.to_string(), key.to_string())
+        .map_err(|e| {
+            NotificationError::InternalError(format!("Failed to get sent state: {:?}", e))
+        })?;
+
+    match value {
+        Some(value) => match SentState::from_string(&value) {
+            Ok(state) => Ok(Some(state)),
+            Err(e) => {
+                // If we can't parse the previous state, treat it as changed so the notification is still sent
+                log::error!("Failed to parse sent state {}: {:?}", key, e);
+                Ok(None)
+            }
+        },
+        None => Ok(None),
+    }
+}
+
+pub fn set_sent_state(
+    ctx: &ServiceContext,
+    key: &str,
+    state: &SentState,
+) -> Result<(), NotificationError> {
+    ctx.service_provider
+        .plugin_service
+        .set_value(
+            ctx,
+            PLUGIN_NAME.to_string(),
+            key.to_string(),
+            state.to_json_string()?,
+        )
+        .map_err(|e| NotificationError::InternalError(format!("Failed to set sent state: {:?}", e)))
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_is_unchanged() {
+        let sent_datetime = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let state = SentState {
+            data_hash: hash_template_data(&json!({"stores": ["a", "b"]})),
+            sent_datetime,
+            event_ids: vec![],
+        };
+
+        let same_hash = hash_template_data(&json!({"stores": ["a", "b"]}));
+        let different_hash = hash_template_data(&json!({"stores": ["a"]}));
+
+        let next_day = sent_datetime + Duration::days(1);
+        let next_week = sent_datetime + Duration::days(7);
+
+        assert!(state.is_unchanged(&same_hash, next_day, None));
+        assert!(state.is_unchanged(&same_hash, next_week, None));
+        assert!(!state.is_unchanged(&different_hash, next_day, None));
+
+        // Heartbeat after 7 days
+        assert!(state.is_unchanged(&same_hash, next_day, Some(7)));
+        assert!(!state.is_unchanged(&same_hash, next_week, Some(7)));
+    }
+
+    #[test]
+    fn test_hash_template_data_is_stable() {
+        assert_eq!(
+            hash_template_data(&json!({"a": 1, "b": [1, 2]})),
+            hash_template_data(&json!({"b": [1, 2], "a": 1}))
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use tera::{Context, Tera};
+
+use crate::NotificationError;
+
+/// Evaluates a send condition against the template data for a parameter set.
+///
+/// The condition can either be a bare tera expression such as `stock_levels | length > 5`,
+/// or wrapped in double curly braces `{{ stock_levels | length > 5 }}`.
+/// Non boolean values use tera's truthiness rules, e.g. an empty query result is false.
+pub fn evaluate_send_condition(
+    condition: &str,
+    template_data: &serde_json::Value,
+) -> Result<bool, NotificationError> {
+    let condition = condition.trim();
+
+    let expression = match condition
+        .strip_prefix("{{")
+        .and_then(|c| c.strip_suffix("}}"))
+    {
+        Some(inner) => inner.trim(),
+        None => condition,
+    };
+
+    let tera_context = Context::from_value(template_data.clone()).map_err(|e| {
+        NotificationError::InternalError(format!(
+            "Failed to convert template data to tera context: {}",
+            e
+        ))
+    })?;
+
+    let template = format!(
+        "{{% if {} %}}true{{% else %}}false{{% endif %}}",
+        expression
+    );
+
+    let result = Tera::one_off(&template, &tera_context, false).map_err(|e| {
+        NotificationError::InvalidSendCondition(format!(
+            "Unable to evaluate send condition `{}`: {:?}",
+            condition, e
+        ))
+    })?;
+
+    Ok(result == "true")
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_evaluate_send_condition() {
+        let data = json!({
+            "stock_levels": [{"item": "a"}, {"item": "b"}],
+            "low_stock": [{"days_remaining": 5}],
+            "store_name": "Store A"
+        });
+
+        // Wrapped expressions
+        assert!(evaluate_send_condition("{{ stock_levels | length > 1 }}", &data).unwrap());
+        assert!(!evaluate_send_condition("{{ stock_levels | length > 5 }}", &data).unwrap());
+
+        // Bare expressions
+        assert!(evaluate_send_condition("low_stock.0.days_remaining < 7", &data).unwrap());
+        assert!(!evaluate_send_condition("low_stock.0.days_remaining < 3", &data).unwrap());
+        assert!(evaluate_send_condition("store_name == \"Store A\"", &data).unwrap());
+
+        // Logical operators
+        assert!(evaluate_send_condition(
+            "stock_levels | length > 1 and low_stock | length > 0",
+            &data
+        )
+        .unwrap());
+        assert!(!evaluate_send_condition("not stock_levels", &data).unwrap());
+    }
+
+    #[test]
+    fn test_evaluate_send_condition_errors() {
+        let data = json!({ "stock_levels": [] });
+
+        // Unknown variable
+        let result = evaluate_send_condition("missing_query | length > 1", &data);
+        assert!(matches!(
+            result,
+            Err(NotificationError::InvalidSendCondition(_))
+        ));
+
+        // Invalid expression
+        let result = evaluate_send_condition("{{ stock_levels | length > }}", &data);
+        assert!(matches!(
+            result,
+            Err(NotificationError::InvalidSendCondition(_))
+        ));
+    }
+}
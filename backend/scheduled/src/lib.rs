@@ -1,19 +1,33 @@
+use repository::RepositoryError;
 use service::{
     plugin::{PluginError, PluginTrait},
     service_provider::ServiceContext,
 };
 
+pub mod changes;
+pub mod condition;
 pub mod parse;
 pub mod process;
 pub mod query;
+pub mod run_now;
+
+const PLUGIN_NAME: &str = "ScheduledNotification";
 
 #[derive(Debug)]
 pub enum NotificationError {
-    InvalidTemplate,
+    InvalidTemplate(String),
     InvalidRecipient,
     UnableToParseConfig(String),
     InternalError(String),
     InvalidNextDueDate,
+    InvalidSendCondition(String),
+    BadUserInput(String),
+}
+
+impl From<RepositoryError> for NotificationError {
+    fn from(error: RepositoryError) -> Self {
+        NotificationError::InternalError(format!("{:?}", error))
+    }
 }
 
 pub struct ScheduledNotificationPlugin {}
@@ -27,7 +41,7 @@ impl PluginTrait for ScheduledNotificationPlugin {
     }
 
     fn name(&self) -> String {
-        "ScheduledNotification".to_string()
+        PLUGIN_NAME.to_string()
     }
 
     fn tick(&self, ctx: &ServiceContext) -> Result<(), PluginError> {
@@ -1,5 +1,8 @@
-use chrono::{DateTime, Days, Months, Utc};
+use chrono::{DateTime, Days, Duration, Months, Utc};
+use repository::{NotificationPriority, NotificationType};
 use serde::{Deserialize, Serialize};
+use service::notification::enqueue::{ChannelTemplates, TemplateDefinition};
+use std::collections::HashMap;
 
 use crate::NotificationError;
 
@@ -32,11 +35,20 @@ use crate::NotificationError;
     ],
     "scheduleFrequency": "daily",
     "scheduleStartTime": "2023-10-11T02:09:31.221Z",
+    "scheduleEndTime": "2024-10-11T02:09:31.221Z",
+    "maxOccurrences": 10,
     "sqlRecipientListIds": [
         "3f6194ad-1fbb-494b-8ffb-c0f2e1b455d0"
     ],
     "status": "DISABLED",
+    "sendCondition": "{{ query1 | length > 5 }}",
+    "onlySendWhenChanged": true,
+    "sendUnchangedAfterDays": 7,
+    "personalisePerRecipient": false,
+    "priority": "normal",
     "subjectTemplate": "Title Template",
+    "emailBodyTemplate": "Some Template with more detail",
+    "telegramBodyTemplate": "Short Template",
     "title": "Some Notification Name"
 }
 */
@@ -48,12 +60,41 @@ pub struct ScheduledNotificationPluginConfig {
     pub title: String,
     pub body_template: String,
     pub subject_template: String,
+    /// Used instead of `body_template` for email recipients
+    #[serde(default)]
+    pub email_body_template: Option<String>,
+    /// Used instead of `body_template` for telegram recipients
+    #[serde(default)]
+    pub telegram_body_template: Option<String>,
+    /// One of `once`, `daily`, `weekly` or `monthly`
     pub schedule_frequency: String,
     pub schedule_start_time: DateTime<Utc>,
+    /// Don't send any notifications after this time, the config is disabled after the last one is sent
+    #[serde(default)]
+    pub schedule_end_time: Option<DateTime<Utc>>,
+    /// Only send this many times, the config is disabled after the last one is sent
+    #[serde(default)]
+    pub max_occurrences: Option<u32>,
     #[serde(default)]
     pub notification_query_ids: Vec<String>,
     #[serde(default)]
     pub required_query_ids: Vec<String>,
+    /// Optional tera expression evaluated against the query results for each parameter set.
+    /// If it doesn't evaluate to true, no notification is sent for that parameter set.
+    #[serde(default)]
+    pub send_condition: Option<String>,
+    /// Only send a notification for a parameter set if the template data has changed since the last one was sent
+    #[serde(default)]
+    pub only_send_when_changed: bool,
+    /// When `only_send_when_changed` is set, still send the notification if this many days have passed since the last one
+    #[serde(default)]
+    pub send_unchanged_after_days: Option<u32>,
+    /// Run the queries separately for each recipient, with the recipient's details added to the parameters as `recipient`
+    #[serde(default)]
+    pub personalise_per_recipient: bool,
+    /// `normal` or `critical`, critical notifications are sent even during the recipient's quiet hours
+    #[serde(default)]
+    pub priority: NotificationPriority,
 }
 
 impl ScheduledNotificationPluginConfig {
@@ -64,6 +105,28 @@ impl ScheduledNotificationPluginConfig {
         Ok(config)
     }
 
+    /// The body templates for each type of recipient, if they are different from `body_template`
+    pub fn channel_templates(&self) -> HashMap<NotificationType, ChannelTemplates> {
+        let mut channel_templates = HashMap::new();
+        let channel_body_templates = [
+            (NotificationType::Email, &self.email_body_template),
+            (NotificationType::Telegram, &self.telegram_body_template),
+        ];
+        for (notification_type, body_template) in channel_body_templates {
+            // Empty templates are treated as not set, as they can be left blank in the UI
+            if let Some(body_template) = body_template.as_ref().filter(|t| !t.trim().is_empty()) {
+                channel_templates.insert(
+                    notification_type,
+                    ChannelTemplates {
+                        title_template: None,
+                        body_template: Some(TemplateDefinition::Template(body_template.clone())),
+                    },
+                );
+            }
+        }
+        channel_templates
+    }
+
     pub fn next_due_date(
         &self,
         now_utc: DateTime<Utc>,
@@ -73,6 +136,8 @@ impl ScheduledNotificationPluginConfig {
         // Then add the duration to the schedule_start_time until we're past now
 
         match self.schedule_frequency.as_str() {
+            // A one off notification is always due at the start time, it's disabled once it has been sent
+            "once" => return Ok(self.schedule_start_time),
             "weekly" => {
                 let mut next_due_date = self.schedule_start_time;
                 while next_due_date < now_utc {
@@ -115,6 +180,58 @@ impl ScheduledNotificationPluginConfig {
             }
         }
     }
+
+    /// Returns true if there shouldn't be any more notifications after the one due at `due_datetime`
+    pub fn is_final_occurrence(
+        &self,
+        due_datetime: DateTime<Utc>,
+    ) -> Result<bool, NotificationError> {
+        if self.schedule_frequency == "once" {
+            return Ok(true);
+        }
+
+        if let Some(end_time) = self.schedule_end_time {
+            let following_due_datetime = self.next_due_date(due_datetime + Duration::seconds(1))?;
+            if following_due_datetime > end_time {
+                return Ok(true);
+            }
+        }
+
+        match self.max_occurrences {
+            Some(max_occurrences) => Ok(self.occurrences_up_to(due_datetime)? >= max_occurrences),
+            None => Ok(false),
+        }
+    }
+
+    /// Returns true if the notification due at `due_datetime` is after the end time or maximum number of occurrences,
+    /// e.g. because the schedule was changed after the notification was last sent
+    pub fn is_past_end(&self, due_datetime: DateTime<Utc>) -> Result<bool, NotificationError> {
+        if let Some(end_time) = self.schedule_end_time {
+            if due_datetime > end_time {
+                return Ok(true);
+            }
+        }
+
+        match self.max_occurrences {
+            Some(max_occurrences) => Ok(self.occurrences_up_to(due_datetime)? > max_occurrences),
+            None => Ok(false),
+        }
+    }
+
+    /// Counts how many times the notification has been due, up to and including `due_datetime`
+    fn occurrences_up_to(&self, due_datetime: DateTime<Utc>) -> Result<u32, NotificationError> {
+        if self.schedule_frequency == "once" {
+            return Ok((due_datetime >= self.schedule_start_time) as u32);
+        }
+
+        let mut occurrences = 0;
+        let mut occurrence = self.schedule_start_time;
+        while occurrence <= due_datetime {
+            occurrences += 1;
+            occurrence = self.next_due_date(occurrence + Duration::seconds(1))?;
+        }
+        Ok(occurrences)
+    }
 }
 
 #[cfg(test)]
@@ -167,6 +284,36 @@ mod test {
         // TODO: add tests for sqlQueries, recipientIds, recipientListIds, sqlRecipientListIds?
     }
 
+    #[test]
+    fn test_channel_templates() {
+        let config = ScheduledNotificationPluginConfig::from_string(
+            r#"{
+    "id": "channel_templates",
+    "title": "Stock report",
+    "bodyTemplate": "There are {{ stock }} units in stock",
+    "subjectTemplate": "Stock report",
+    "emailBodyTemplate": "",
+    "telegramBodyTemplate": "{{ stock }} units",
+    "scheduleFrequency": "daily",
+    "scheduleStartTime": "2023-08-29T12:00:00.000Z"
+}"#,
+        )
+        .unwrap();
+
+        let channel_templates = config.channel_templates();
+        // An empty email template is the same as not setting it
+        assert_eq!(channel_templates.len(), 1);
+        assert!(matches!(
+            &channel_templates[&NotificationType::Telegram].body_template,
+            Some(TemplateDefinition::Template(template)) if template == "{{ stock }} units"
+        ));
+
+        // Older configs don't have channel templates
+        assert!(ScheduledNotificationPluginConfig::default()
+            .channel_templates()
+            .is_empty());
+    }
+
     #[test]
     fn test_parse_config_daily() {
         let config = ScheduledNotificationPluginConfig {
@@ -308,4 +455,71 @@ mod test {
             Utc.with_ymd_and_hms(2024, 03, 31, 7, 0, 0).unwrap()
         );
     }
+
+    #[test]
+    fn test_once() {
+        let config = ScheduledNotificationPluginConfig {
+            schedule_frequency: "once".to_string(),
+            schedule_start_time: Utc.with_ymd_and_hms(2024, 03, 12, 9, 0, 0).unwrap(),
+            ..Default::default()
+        };
+
+        // Always due at the start time
+        let now_utc: DateTime<Utc> = Utc.with_ymd_and_hms(2024, 03, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            config.next_due_date(now_utc).unwrap(),
+            config.schedule_start_time
+        );
+        let now_utc: DateTime<Utc> = Utc.with_ymd_and_hms(2024, 03, 13, 0, 0, 0).unwrap();
+        assert_eq!(
+            config.next_due_date(now_utc).unwrap(),
+            config.schedule_start_time
+        );
+
+        assert!(config
+            .is_final_occurrence(config.schedule_start_time)
+            .unwrap());
+        assert!(!config.is_past_end(config.schedule_start_time).unwrap());
+    }
+
+    #[test]
+    fn test_max_occurrences() {
+        let config = ScheduledNotificationPluginConfig {
+            schedule_frequency: "daily".to_string(),
+            schedule_start_time: Utc.with_ymd_and_hms(2024, 03, 1, 9, 0, 0).unwrap(),
+            max_occurrences: Some(3),
+            ..Default::default()
+        };
+
+        let second = Utc.with_ymd_and_hms(2024, 03, 2, 9, 0, 0).unwrap();
+        let third = Utc.with_ymd_and_hms(2024, 03, 3, 9, 0, 0).unwrap();
+        let fourth = Utc.with_ymd_and_hms(2024, 03, 4, 9, 0, 0).unwrap();
+
+        assert!(!config.is_final_occurrence(second).unwrap());
+        assert!(config.is_final_occurrence(third).unwrap());
+
+        assert!(!config.is_past_end(third).unwrap());
+        assert!(config.is_past_end(fourth).unwrap());
+    }
+
+    #[test]
+    fn test_schedule_end_time() {
+        let config = ScheduledNotificationPluginConfig {
+            schedule_frequency: "weekly".to_string(),
+            schedule_start_time: Utc.with_ymd_and_hms(2024, 03, 1, 9, 0, 0).unwrap(),
+            schedule_end_time: Some(Utc.with_ymd_and_hms(2024, 03, 20, 0, 0, 0).unwrap()),
+            ..Default::default()
+        };
+
+        let second = Utc.with_ymd_and_hms(2024, 03, 8, 9, 0, 0).unwrap();
+        let third = Utc.with_ymd_and_hms(2024, 03, 15, 9, 0, 0).unwrap();
+        let fourth = Utc.with_ymd_and_hms(2024, 03, 22, 9, 0, 0).unwrap();
+
+        assert!(!config.is_final_occurrence(second).unwrap());
+        // The following week is after the end time
+        assert!(config.is_final_occurrence(third).unwrap());
+
+        assert!(!config.is_past_end(third).unwrap());
+        assert!(config.is_past_end(fourth).unwrap());
+    }
 }
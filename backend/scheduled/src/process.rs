@@ -1,18 +1,29 @@
 use std::collections::HashMap;
 
 use chrono::{DateTime, NaiveDateTime, Utc};
-use repository::{NotificationConfigKind, NotificationConfigRowRepository};
+use repository::{
+    NotificationConfigKind, NotificationConfigRowRepository, NotificationConfigStatus,
+    NotificationEventStatus,
+};
 use service::{
-    notification::enqueue::{create_notification_events, NotificationContext, TemplateDefinition},
+    notification::enqueue::{
+        create_notification_events, NotificationContext, NotificationTarget, TemplateDefinition,
+    },
     notification_config::{
         parameters::get_notification_parameters, query::NotificationConfig,
         recipients::get_notification_targets,
     },
+    notification_run::{finish_notification_run, start_notification_run, NotificationRunOutcome},
     service_provider::ServiceContext,
 };
 
 use crate::{
-    parse::ScheduledNotificationPluginConfig, query::get_notification_query_results,
+    changes::{
+        get_sent_state, hash_template_data, is_delivered, sent_state_key, set_sent_state, SentState,
+    },
+    condition::evaluate_send_condition,
+    parse::ScheduledNotificationPluginConfig,
+    query::{get_notification_query_results, NotificationQueryResult, QueryCache},
     NotificationError,
 };
 
@@ -42,10 +53,34 @@ pub fn process_scheduled_notifications(
             scheduled_notification.id,
         );
 
-        match try_process_scheduled_notifications(ctx, scheduled_notification, current_time) {
+        // Record the run, so we can see what happened afterwards
+        let run = match start_notification_run(
+            ctx,
+            &scheduled_notification.id,
+            NotificationConfigKind::Scheduled,
+            current_time,
+        ) {
+            Ok(run) => Some(run),
+            Err(e) => {
+                log::error!("Failed to record notification run: {:?}", e);
+                None
+            }
+        };
+
+        let mut query_cache = QueryCache::new();
+        let outcome = match try_process_scheduled_notifications(
+            ctx,
+            scheduled_notification,
+            current_time,
+            &mut query_cache,
+        ) {
             Err(e) => {
                 log::error!("{:?}", e);
                 errored_notifications += 1;
+                NotificationRunOutcome {
+                    error_message: Some(format!("{:?}", e)),
+                    ..Default::default()
+                }
             }
             Ok(processing_result) => {
                 log::info!(
@@ -56,6 +91,22 @@ pub fn process_scheduled_notifications(
                 successful_notification_configs += 1;
                 created_notifications += processing_result.notifications_created;
                 skipped_notifications += processing_result.skipped_count;
+                NotificationRunOutcome {
+                    parameter_sets_evaluated: processing_result.parameter_sets_evaluated,
+                    notifications_created: processing_result.notifications_created,
+                    skipped_reasons: processing_result.skipped_reasons,
+                    ..Default::default()
+                }
+            }
+        };
+
+        if let Some(run) = run {
+            let outcome = NotificationRunOutcome {
+                query_timings: query_cache.take_query_timings(),
+                ..outcome
+            };
+            if let Err(e) = finish_notification_run(ctx, run, outcome) {
+                log::error!("Failed to record notification run: {:?}", e);
             }
         }
         let end_time = Utc::now();
@@ -79,18 +130,31 @@ pub fn process_scheduled_notifications(
 
 #[derive(Debug, PartialEq)]
 struct ProcessingResult {
+    parameter_sets_evaluated: usize,
     skipped_count: usize,
     notifications_created: usize,
+    skipped_reasons: Vec<String>,
+}
+
+impl ProcessingResult {
+    fn skip(&mut self, reason: String) {
+        log::info!("{}", reason);
+        self.skipped_count += 1;
+        self.skipped_reasons.push(reason);
+    }
 }
 
 fn try_process_scheduled_notifications(
     ctx: &ServiceContext,
     scheduled_notification: NotificationConfig,
     now: NaiveDateTime,
+    query_cache: &mut QueryCache,
 ) -> Result<ProcessingResult, NotificationError> {
     let mut notification_result = ProcessingResult {
+        parameter_sets_evaluated: 0,
         skipped_count: 0,
         notifications_created: 0,
+        skipped_reasons: vec![],
     };
 
     // Load the notification config
@@ -116,33 +180,156 @@ fn try_process_scheduled_notifications(
     let previous_due_datetime = match previous_due_datetime {
         Some(dt) => dt,
         None => {
-            log::info!(
+            notification_result.skip(format!(
                 "No previous due time for scheduled notification {}, setting to {}",
-                scheduled_notification.id,
-                next_due_datetime
-            );
-
-            notification_result.skipped_count += 1;
+                scheduled_notification.id, next_due_datetime
+            ));
 
             return Ok(notification_result);
         }
     };
 
     if previous_due_datetime > now {
-        log::info!(
+        notification_result.skip(format!(
             "Scheduled notification {} is not due yet (previous due: {}, now: {}), skipping",
-            scheduled_notification.id,
-            previous_due_datetime,
-            now
-        );
+            scheduled_notification.id, previous_due_datetime, now
+        ));
 
-        notification_result.skipped_count += 1;
+        return Ok(notification_result);
+    }
 
+    let previous_due_datetime_utc = DateTime::from_utc(previous_due_datetime, Utc);
+    if config.is_past_end(previous_due_datetime_utc)? {
+        notification_result.skip(format!(
+            "Scheduled notification {} has reached the end of its schedule, disabling",
+            scheduled_notification.id
+        ));
+        disable_notification_config(ctx, &scheduled_notification.id)?;
         return Ok(notification_result);
     }
 
-    let param_results = get_notification_parameters(ctx, &scheduled_notification);
-    let mut all_params = match param_results {
+    let all_params = get_parameter_sets(ctx, &scheduled_notification)?;
+
+    // Prepare all the notifications first, so nothing is queued if any of them fail
+    let mut notifications = vec![];
+    for template_params in all_params {
+        notification_result.parameter_sets_evaluated += 1;
+
+        let prepared_notifications = prepare_parameter_set(
+            ctx,
+            &scheduled_notification,
+            &config,
+            template_params,
+            None,
+            query_cache,
+        )?;
+
+        for prepared in prepared_notifications {
+            let (sql_params, notification_targets, template_data) = match prepared {
+                PreparedParameterSet::Ready {
+                    parameters,
+                    notification_targets,
+                    template_data,
+                } => (parameters, notification_targets, template_data),
+                PreparedParameterSet::Skipped { reason, .. } => {
+                    notification_result.skip(reason);
+                    continue;
+                }
+            };
+
+            // If we only send when the data has changed, compare with what we sent last time
+            let sent_state = if config.only_send_when_changed {
+                let key = sent_state_key(&scheduled_notification.id, &sql_params);
+                let data_hash = hash_template_data(&template_data);
+                if let Some(previous) = get_sent_state(ctx, &key)? {
+                    if previous.is_unchanged(&data_hash, now, config.send_unchanged_after_days)
+                        && is_delivered(ctx, &previous)?
+                    {
+                        notification_result.skip(format!(
+                            "Data unchanged since last notification at {} for parameter set {}, skipping",
+                            previous.sent_datetime, sql_params
+                        ));
+                        continue;
+                    }
+                }
+                Some((key, data_hash))
+            } else {
+                None
+            };
+
+            let notification = NotificationContext {
+                title_template: Some(TemplateDefinition::Template(
+                    config.subject_template.clone(),
+                )),
+                body_template: TemplateDefinition::Template(config.body_template.clone()),
+                template_data,
+                recipients: notification_targets,
+                dedup_key: None,
+                priority: config.priority.clone(),
+                channel_templates: config.channel_templates(),
+            };
+            notifications.push((notification, sent_state));
+        }
+    }
+
+    // Queue the notifications, and disable one off and bounded schedules once their last notification is queued.
+    // If anything fails nothing is queued, and the notification is tried again on the next check.
+    let is_final_occurrence = config.is_final_occurrence(previous_due_datetime_utc)?;
+    ctx.connection
+        .transaction_sync(|_| -> Result<(), NotificationError> {
+            for (notification, sent_state) in notifications {
+                let events = create_notification_events(
+                    ctx,
+                    Some(scheduled_notification.id.clone()),
+                    notification,
+                )
+                .map_err(|e| NotificationError::InternalError(format!("{:?}", e)))?;
+                notification_result.notifications_created += 1;
+
+                // Only remember the data if it is going to be sent, otherwise it's sent again next time
+                let event_ids: Vec<String> = events
+                    .into_iter()
+                    .filter(|event| event.status == NotificationEventStatus::Queued)
+                    .map(|event| event.id)
+                    .collect();
+                if let Some((key, data_hash)) = sent_state {
+                    if !event_ids.is_empty() {
+                        let state = SentState {
+                            data_hash,
+                            sent_datetime: now,
+                            event_ids,
+                        };
+                        set_sent_state(ctx, &key, &state)?;
+                    }
+                }
+            }
+
+            if is_final_occurrence {
+                log::info!(
+                    "Scheduled notification {} has queued its final notification, disabling",
+                    scheduled_notification.id
+                );
+                disable_notification_config(ctx, &scheduled_notification.id)?;
+            }
+            Ok(())
+        })
+        .map_err(|error| error.to_inner_error())?;
+
+    Ok(notification_result)
+}
+
+fn disable_notification_config(ctx: &ServiceContext, id: &str) -> Result<(), NotificationError> {
+    NotificationConfigRowRepository::new(&ctx.connection)
+        .set_status_by_id(id, NotificationConfigStatus::Disabled)
+        .map_err(|e| NotificationError::InternalError(format!("{:?}", e)))
+}
+
+pub(crate) fn get_parameter_sets(
+    ctx: &ServiceContext,
+    scheduled_notification: &NotificationConfig,
+) -> Result<Vec<HashMap<String, serde_json::Value>>, NotificationError> {
+    let param_results = get_notification_parameters(ctx, scheduled_notification);
+    let all_params = match param_results {
         Ok(val) => val,
         Err(e) => {
             return Err(NotificationError::InternalError(format!(
@@ -152,76 +339,176 @@ fn try_process_scheduled_notifications(
         }
     };
 
-    if all_params.len() == 0 {
+    if all_params.is_empty() {
         // If no parameters are provided, create a single empty parameter set
-        all_params = vec![HashMap::new()];
+        return Ok(vec![HashMap::new()]);
     }
 
-    for mut template_params in all_params {
-        // Put sql queries and appropriate data into Json Value for template
-        let sql_params = serde_json::to_value(&template_params).map_err(|e| {
-            NotificationError::InternalError(format!("Failed to parse sql params data: {:?}", e))
-        })?;
+    Ok(all_params)
+}
 
-        log::info!("Processing parameter set: {}", sql_params);
+pub(crate) enum PreparedParameterSet {
+    Ready {
+        parameters: serde_json::Value,
+        notification_targets: Vec<NotificationTarget>,
+        template_data: serde_json::Value,
+    },
+    Skipped {
+        parameters: serde_json::Value,
+        reason: String,
+    },
+}
 
-        // Get the recipients
-        let notification_targets = get_notification_targets(
-            ctx,
-            &scheduled_notification,
-            sql_params.clone(),
-        )
-        .map_err(|e| {
-            NotificationError::InternalError(format!("Failed to get notification targets: {:?}", e))
-        })?;
+/// Finds the recipients for a parameter set, then runs the queries and checks the send condition.
+/// When the config is personalised per recipient there is a result for each recipient, otherwise a single result for all of them.
+/// If `send_to` is set it is used instead of the configured recipients, e.g. when running a config now to test it.
+pub(crate) fn prepare_parameter_set(
+    ctx: &ServiceContext,
+    scheduled_notification: &NotificationConfig,
+    config: &ScheduledNotificationPluginConfig,
+    template_params: HashMap<String, serde_json::Value>,
+    send_to: Option<&NotificationTarget>,
+    query_cache: &mut QueryCache,
+) -> Result<Vec<PreparedParameterSet>, NotificationError> {
+    // Put sql queries and appropriate data into Json Value for template
+    let sql_params = serde_json::to_value(&template_params).map_err(|e| {
+        NotificationError::InternalError(format!("Failed to parse sql params data: {:?}", e))
+    })?;
+
+    log::info!("Processing parameter set: {}", sql_params);
+
+    // Get the recipients
+    let mut notification_targets = match send_to {
+        Some(send_to) => vec![send_to.clone()],
+        None => get_notification_targets(ctx, scheduled_notification, sql_params.clone()).map_err(
+            |e| {
+                NotificationError::InternalError(format!(
+                    "Failed to get notification targets: {:?}",
+                    e
+                ))
+            },
+        )?,
+    };
 
-        // If there are no recipients, skip this parameter set
-        if notification_targets.is_empty() {
-            log::info!("No notification targets, skipping");
-            notification_result.skipped_count += 1;
-            continue;
-        }
+    // If there are no recipients, skip this parameter set
+    if notification_targets.is_empty() {
+        return Ok(vec![PreparedParameterSet::Skipped {
+            reason: format!(
+                "No notification targets for parameter set {}, skipping",
+                sql_params
+            ),
+            parameters: sql_params,
+        }]);
+    }
 
-        let sql_query_parameters = get_notification_query_results(
+    if !config.personalise_per_recipient {
+        let prepared = prepare_template_data(
             ctx,
-            sql_params,
-            &config,
-            config.required_query_ids.clone(),
+            config,
+            template_params,
+            notification_targets,
+            query_cache,
         )?;
+        return Ok(vec![prepared]);
+    }
 
-        // If any required queries were skipped, skip this notification
-        let sql_query_parameters = match sql_query_parameters {
-            crate::query::NotificationQueryResult::Success(results) => results,
-            crate::query::NotificationQueryResult::Skipped(reason) => {
-                log::info!("Skipping notification: {}", reason);
-                notification_result.skipped_count += 1;
-                continue;
-            }
-        };
-
-        // Template data should include the notification config parameters, plus the results of any queries
-        template_params.extend(sql_query_parameters);
+    // A recipient could be in more than one list, but should only get one notification
+    notification_targets.sort_by(|a, b| a.to_address.cmp(&b.to_address));
+    notification_targets.dedup_by(|a, b| a.to_address == b.to_address);
 
-        let template_data = serde_json::to_value(template_params).map_err(|e| {
-            NotificationError::InternalError(format!("Failed to parse template data: {:?}", e))
+    let mut prepared = vec![];
+    for notification_target in notification_targets {
+        let mut recipient_params = template_params.clone();
+        let recipient = serde_json::to_value(&notification_target).map_err(|e| {
+            NotificationError::InternalError(format!("Failed to parse recipient data: {:?}", e))
         })?;
+        recipient_params.insert("recipient".to_string(), recipient);
 
-        // Send the notification
-        let notification = NotificationContext {
-            title_template: Some(TemplateDefinition::Template(
-                config.subject_template.clone(),
-            )),
-            body_template: TemplateDefinition::Template(config.body_template.clone()),
-            template_data: template_data,
-            recipients: notification_targets,
-        };
+        prepared.push(prepare_template_data(
+            ctx,
+            config,
+            recipient_params,
+            vec![notification_target],
+            query_cache,
+        )?);
+    }
 
-        create_notification_events(ctx, Some(scheduled_notification.id.clone()), notification)
-            .map_err(|e| NotificationError::InternalError(format!("{:?}", e)))?;
-        notification_result.notifications_created += 1;
+    Ok(prepared)
+}
+
+/// Runs the queries for the parameters and checks the send condition.
+/// Returns the template data if a notification should be sent, or the reason it was skipped.
+fn prepare_template_data(
+    ctx: &ServiceContext,
+    config: &ScheduledNotificationPluginConfig,
+    mut template_params: HashMap<String, serde_json::Value>,
+    notification_targets: Vec<NotificationTarget>,
+    query_cache: &mut QueryCache,
+) -> Result<PreparedParameterSet, NotificationError> {
+    let sql_params = serde_json::to_value(&template_params).map_err(|e| {
+        NotificationError::InternalError(format!("Failed to parse sql params data: {:?}", e))
+    })?;
+
+    let sql_query_parameters = get_notification_query_results(
+        ctx,
+        sql_params.clone(),
+        config,
+        config.required_query_ids.clone(),
+        query_cache,
+    )?;
+
+    // If any required queries were skipped, skip this notification
+    let sql_query_parameters = match sql_query_parameters {
+        NotificationQueryResult::Success(results) => results,
+        NotificationQueryResult::Skipped(reason) => {
+            return Ok(PreparedParameterSet::Skipped {
+                parameters: sql_params,
+                reason: format!("Skipping notification: {}", reason),
+            });
+        }
+    };
+
+    // Template data should include the notification config parameters, plus the results of any queries
+    template_params.extend(sql_query_parameters);
+
+    let template_data = serde_json::to_value(template_params).map_err(|e| {
+        NotificationError::InternalError(format!("Failed to parse template data: {:?}", e))
+    })?;
+
+    // If a send condition is configured, only send when it evaluates to true
+    if let Some(send_condition) = config
+        .send_condition
+        .as_ref()
+        .filter(|condition| !condition.trim().is_empty())
+    {
+        match evaluate_send_condition(send_condition, &template_data) {
+            Ok(true) => {}
+            Ok(false) => {
+                return Ok(PreparedParameterSet::Skipped {
+                    reason: format!(
+                        "Send condition `{}` was not met for parameter set {}, skipping",
+                        send_condition, sql_params
+                    ),
+                    parameters: sql_params,
+                });
+            }
+            Err(e) => {
+                return Ok(PreparedParameterSet::Skipped {
+                    reason: format!(
+                        "Send condition could not be evaluated for parameter set {}, skipping: {:?}",
+                        sql_params, e
+                    ),
+                    parameters: sql_params,
+                });
+            }
+        }
     }
 
-    Ok(notification_result)
+    Ok(PreparedParameterSet::Ready {
+        parameters: sql_params,
+        notification_targets,
+        template_data,
+    })
 }
 
 #[cfg(test)]
@@ -231,12 +518,15 @@ mod test {
 
     use chrono::Days;
     use repository::mock::{
-        mock_notification_query_with_params, mock_recipient_a,
+        mock_notification_query_with_params, mock_recipient_a, mock_recipient_b,
         mock_recipient_list_with_recipient_members_a_and_b, mock_sql_recipient_list_with_no_param,
         mock_sql_recipient_list_with_param,
     };
-    use repository::NotificationEventRowRepository;
     use repository::{mock::MockDataInserts, test_db::setup_all};
+    use repository::{
+        NotificationConfigRow, NotificationEventRowRepository, NotificationQueryRow,
+        NotificationQueryRowRepository,
+    };
     use service::test_utils::email_test::send_test_emails;
     use service::test_utils::get_test_settings;
 
@@ -316,14 +606,17 @@ mod test {
             &service_context,
             notification_config,
             chrono::Utc::now().naive_utc(),
+            &mut QueryCache::new(),
         )
         .unwrap();
 
         assert_eq!(
             result,
             ProcessingResult {
+                parameter_sets_evaluated: 1,
                 skipped_count: 0,
-                notifications_created: 1
+                notifications_created: 1,
+                skipped_reasons: vec![],
             }
         );
 
@@ -378,14 +671,17 @@ mod test {
             &service_context,
             notification_config,
             chrono::Utc::now().naive_utc(),
+            &mut QueryCache::new(),
         )
         .unwrap();
 
         assert_eq!(
             result,
             ProcessingResult {
+                parameter_sets_evaluated: 1,
                 skipped_count: 0,
-                notifications_created: 1
+                notifications_created: 1,
+                skipped_reasons: vec![],
             }
         );
 
@@ -442,14 +738,17 @@ mod test {
             &service_context,
             notification_config,
             chrono::Utc::now().naive_utc(),
+            &mut QueryCache::new(),
         )
         .unwrap();
 
         assert_eq!(
             result,
             ProcessingResult {
+                parameter_sets_evaluated: 1,
                 skipped_count: 0,
-                notifications_created: 1
+                notifications_created: 1,
+                skipped_reasons: vec![],
             }
         );
 
@@ -509,14 +808,17 @@ mod test {
             &service_context,
             notification_config,
             chrono::Utc::now().naive_utc(),
+            &mut QueryCache::new(),
         )
         .unwrap();
 
         assert_eq!(
             result,
             ProcessingResult {
+                parameter_sets_evaluated: 1,
                 skipped_count: 0,
-                notifications_created: 1
+                notifications_created: 1,
+                skipped_reasons: vec![],
             }
         );
 
@@ -584,14 +886,17 @@ mod test {
             &service_context,
             notification_config,
             chrono::Utc::now().naive_utc(),
+            &mut QueryCache::new(),
         )
         .unwrap();
 
         assert_eq!(
             result,
             ProcessingResult {
+                parameter_sets_evaluated: 1,
                 skipped_count: 0,
-                notifications_created: 1
+                notifications_created: 1,
+                skipped_reasons: vec![],
             }
         ); // Should be skipped due to template error
 
@@ -601,4 +906,445 @@ mod test {
 
         assert_eq!(notification_events.len(), 0);
     }
+
+    // Test that parameter sets are skipped when the send condition isn't met
+    #[tokio::test]
+    async fn test_try_process_scheduled_notifications_with_send_condition() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_try_process_scheduled_notifications_with_send_condition",
+            MockDataInserts::none()
+                .sql_recipient_lists()
+                .notification_queries(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+
+        let service_context = ServiceContext::new(service_provider).unwrap();
+
+        // Only send when the sensor is above it's limit
+        let sch_config = ScheduledNotificationPluginConfig {
+            body_template: "Latest Temperature: {{ latest_temperature }}".to_string(),
+            subject_template: "Sensor Data".to_string(),
+            schedule_frequency: "daily".to_string(),
+            schedule_start_time: Utc::now().checked_sub_days(Days::new(1)).unwrap(),
+            notification_query_ids: vec![mock_notification_query_with_params().id],
+            send_condition: Some("{{ query1.0.is_above_limit }}".to_string()),
+            ..Default::default()
+        };
+
+        // Two parameter sets, only the first is above the limit
+        let notification_config = NotificationConfig {
+            id: "notification_config_1".to_string(),
+            kind: NotificationConfigKind::Scheduled,
+            sql_recipient_list_ids: vec![mock_sql_recipient_list_with_no_param().id],
+            parameters: "[{\"sensor_limit\":\"8\",\"latest_temperature\":\"8.5\"},{\"sensor_limit\":\"8\",\"latest_temperature\":\"7.5\"}]".to_string(),
+            next_due_datetime: Some(chrono::Utc::now().naive_utc()),
+            configuration_data: serde_json::to_string(&sch_config).unwrap(),
+            ..Default::default()
+        };
+
+        let result = try_process_scheduled_notifications(
+            &service_context,
+            notification_config,
+            chrono::Utc::now().naive_utc(),
+            &mut QueryCache::new(),
+        )
+        .unwrap();
+
+        assert_eq!(result.skipped_count, 1);
+        assert_eq!(result.notifications_created, 1);
+        assert_eq!(result.skipped_reasons.len(), 1);
+        assert!(result.skipped_reasons[0].contains("Send condition"));
+
+        let repo = NotificationEventRowRepository::new(&service_context.connection);
+        let notification_events = repo.un_sent().unwrap();
+
+        assert_eq!(notification_events.len(), 1);
+        assert_eq!(notification_events[0].message, "Latest Temperature: 8.5");
+    }
+
+    // Test that notifications are only sent when the data changes, unless the heartbeat period has passed
+    #[tokio::test]
+    async fn test_try_process_scheduled_notifications_only_send_when_changed() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_try_process_scheduled_notifications_only_send_when_changed",
+            MockDataInserts::none().recipients(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+
+        let service_context = ServiceContext::new(service_provider).unwrap();
+
+        let sch_config = ScheduledNotificationPluginConfig {
+            body_template: "Store {{ store }}".to_string(),
+            subject_template: "Unchanged Report".to_string(),
+            schedule_frequency: "daily".to_string(),
+            schedule_start_time: Utc::now().checked_sub_days(Days::new(1)).unwrap(),
+            only_send_when_changed: true,
+            send_unchanged_after_days: Some(7),
+            ..Default::default()
+        };
+
+        let notification_config = NotificationConfig {
+            id: "notification_config_1".to_string(),
+            kind: NotificationConfigKind::Scheduled,
+            recipient_ids: vec![mock_recipient_a().id],
+            parameters: "[{\"store\":\"A\"}]".to_string(),
+            next_due_datetime: Some(chrono::Utc::now().naive_utc()),
+            configuration_data: serde_json::to_string(&sch_config).unwrap(),
+            ..Default::default()
+        };
+
+        // First run sends the notification
+        let now = chrono::Utc::now().naive_utc();
+        let result = try_process_scheduled_notifications(
+            &service_context,
+            notification_config.clone(),
+            now,
+            &mut QueryCache::new(),
+        )
+        .unwrap();
+        assert_eq!(result.notifications_created, 1);
+
+        // Second run the next day is skipped as nothing has changed
+        let now = now + chrono::Duration::days(1);
+        let result = try_process_scheduled_notifications(
+            &service_context,
+            notification_config.clone(),
+            now,
+            &mut QueryCache::new(),
+        )
+        .unwrap();
+        assert_eq!(result.notifications_created, 0);
+        assert_eq!(result.skipped_count, 1);
+        assert!(result.skipped_reasons[0].contains("Data unchanged"));
+
+        // A different parameter set is tracked separately, so is still sent
+        let changed_config = NotificationConfig {
+            parameters: "[{\"store\":\"A\",\"extra\":\"value\"}]".to_string(),
+            ..notification_config.clone()
+        };
+        let result = try_process_scheduled_notifications(
+            &service_context,
+            changed_config,
+            now,
+            &mut QueryCache::new(),
+        )
+        .unwrap();
+        assert_eq!(result.notifications_created, 1);
+
+        // After the heartbeat period the unchanged notification is sent anyway
+        let now = now + chrono::Duration::days(7);
+        let result = try_process_scheduled_notifications(
+            &service_context,
+            notification_config,
+            now,
+            &mut QueryCache::new(),
+        )
+        .unwrap();
+        assert_eq!(result.notifications_created, 1);
+
+        let repo = NotificationEventRowRepository::new(&service_context.connection);
+        let notification_events = repo.un_sent().unwrap();
+        assert_eq!(notification_events.len(), 3);
+    }
+
+    // Test that unchanged data is sent again if the last notification wasn't sent
+    #[tokio::test]
+    async fn test_try_process_scheduled_notifications_only_send_when_changed_after_failure() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_try_process_scheduled_notifications_only_send_when_changed_after_failure",
+            MockDataInserts::none().recipients(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+
+        let service_context = ServiceContext::new(service_provider).unwrap();
+
+        let sch_config = ScheduledNotificationPluginConfig {
+            body_template: "Store {{ store }}".to_string(),
+            subject_template: "Unchanged Report".to_string(),
+            schedule_frequency: "daily".to_string(),
+            schedule_start_time: Utc::now().checked_sub_days(Days::new(1)).unwrap(),
+            only_send_when_changed: true,
+            ..Default::default()
+        };
+
+        let notification_config = NotificationConfig {
+            id: "notification_config_1".to_string(),
+            kind: NotificationConfigKind::Scheduled,
+            recipient_ids: vec![mock_recipient_a().id],
+            parameters: "[{\"store\":\"A\"}]".to_string(),
+            next_due_datetime: Some(chrono::Utc::now().naive_utc()),
+            configuration_data: serde_json::to_string(&sch_config).unwrap(),
+            ..Default::default()
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+        let result = try_process_scheduled_notifications(
+            &service_context,
+            notification_config.clone(),
+            now,
+            &mut QueryCache::new(),
+        )
+        .unwrap();
+        assert_eq!(result.notifications_created, 1);
+
+        // The notification fails to send
+        let repo = NotificationEventRowRepository::new(&service_context.connection);
+        let mut event = repo.un_sent().unwrap().pop().unwrap();
+        event.status = NotificationEventStatus::Failed;
+        repo.update_one(&event).unwrap();
+
+        // So the unchanged data is sent again on the next run
+        let now = now + chrono::Duration::days(1);
+        let result = try_process_scheduled_notifications(
+            &service_context,
+            notification_config.clone(),
+            now,
+            &mut QueryCache::new(),
+        )
+        .unwrap();
+        assert_eq!(result.notifications_created, 1);
+
+        // Once it is sent, the unchanged data is skipped
+        let mut event = repo.un_sent().unwrap().pop().unwrap();
+        event.status = NotificationEventStatus::Sent;
+        repo.update_one(&event).unwrap();
+
+        let now = now + chrono::Duration::days(1);
+        let result = try_process_scheduled_notifications(
+            &service_context,
+            notification_config,
+            now,
+            &mut QueryCache::new(),
+        )
+        .unwrap();
+        assert_eq!(result.notifications_created, 0);
+        assert!(result.skipped_reasons[0].contains("Data unchanged"));
+    }
+
+    // Test that the queries are run for each recipient when personalised
+    #[tokio::test]
+    async fn test_try_process_scheduled_notifications_personalised_per_recipient() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_try_process_scheduled_notifications_personalised_per_recipient",
+            MockDataInserts::none()
+                .recipients()
+                .recipient_lists()
+                .recipient_list_members(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+
+        let service_context = ServiceContext::new(service_provider).unwrap();
+
+        let query = NotificationQueryRow {
+            id: "recipient_query".to_string(),
+            reference_name: "my_stores".to_string(),
+            query: "SELECT '{{ recipient.name }}' as recipient_name, '{{ store }}' as store"
+                .to_string(),
+            ..Default::default()
+        };
+        NotificationQueryRowRepository::new(&service_context.connection)
+            .insert_one(&query)
+            .unwrap();
+
+        let sch_config = ScheduledNotificationPluginConfig {
+            body_template: "{{ my_stores.0.recipient_name }}: {{ my_stores.0.store }}".to_string(),
+            subject_template: "Personalised Report".to_string(),
+            schedule_frequency: "daily".to_string(),
+            schedule_start_time: Utc::now().checked_sub_days(Days::new(1)).unwrap(),
+            notification_query_ids: vec![query.id.clone()],
+            personalise_per_recipient: true,
+            ..Default::default()
+        };
+
+        let notification_config = NotificationConfig {
+            id: "notification_config_1".to_string(),
+            kind: NotificationConfigKind::Scheduled,
+            recipient_list_ids: vec![mock_recipient_list_with_recipient_members_a_and_b().id],
+            parameters: "[{\"store\":\"A\"}]".to_string(),
+            next_due_datetime: Some(chrono::Utc::now().naive_utc()),
+            configuration_data: serde_json::to_string(&sch_config).unwrap(),
+            ..Default::default()
+        };
+
+        let mut query_cache = QueryCache::new();
+        let result = try_process_scheduled_notifications(
+            &service_context,
+            notification_config,
+            chrono::Utc::now().naive_utc(),
+            &mut query_cache,
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            ProcessingResult {
+                parameter_sets_evaluated: 1,
+                skipped_count: 0,
+                notifications_created: 2,
+                skipped_reasons: vec![],
+            }
+        );
+        // The query is run once for each recipient
+        assert_eq!(query_cache.take_query_timings().len(), 2);
+
+        let repo = NotificationEventRowRepository::new(&service_context.connection);
+        let notification_events = repo.un_sent().unwrap();
+        assert_eq!(notification_events.len(), 2);
+
+        for recipient in [mock_recipient_a(), mock_recipient_b()] {
+            let event = notification_events
+                .iter()
+                .find(|event| event.to_address == recipient.to_address)
+                .unwrap();
+            assert_eq!(event.message, format!("{}: A", recipient.name));
+        }
+    }
+
+    // Test that a one off notification is sent once, then disabled
+    #[tokio::test]
+    async fn test_try_process_scheduled_notifications_once() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_try_process_scheduled_notifications_once",
+            MockDataInserts::none().recipients(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+
+        let service_context = ServiceContext::new(service_provider).unwrap();
+
+        let now = chrono::Utc::now().naive_utc();
+        let sch_config = ScheduledNotificationPluginConfig {
+            body_template: "Maintenance on Tuesday".to_string(),
+            subject_template: "Maintenance".to_string(),
+            schedule_frequency: "once".to_string(),
+            schedule_start_time: DateTime::from_utc(now, Utc),
+            ..Default::default()
+        };
+
+        let config_row = NotificationConfigRow {
+            id: "notification_config_once".to_string(),
+            kind: NotificationConfigKind::Scheduled,
+            status: NotificationConfigStatus::Enabled,
+            recipient_ids: format!("[\"{}\"]", mock_recipient_a().id),
+            next_due_datetime: Some(now),
+            configuration_data: serde_json::to_string(&sch_config).unwrap(),
+            ..Default::default()
+        };
+        let repo = NotificationConfigRowRepository::new(&service_context.connection);
+        repo.insert_one(&config_row).unwrap();
+
+        let result = try_process_scheduled_notifications(
+            &service_context,
+            NotificationConfig::from(config_row.clone()),
+            now,
+            &mut QueryCache::new(),
+        )
+        .unwrap();
+        assert_eq!(result.notifications_created, 1);
+
+        let config_row = repo.find_one_by_id(&config_row.id).unwrap().unwrap();
+        assert_eq!(config_row.status, NotificationConfigStatus::Disabled);
+    }
+
+    // Test that a one off notification that fails isn't lost, and is only sent once it succeeds
+    #[tokio::test]
+    async fn test_process_scheduled_notifications_once_with_error() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_process_scheduled_notifications_once_with_error",
+            MockDataInserts::none().recipients(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+
+        let service_context = ServiceContext::new(service_provider).unwrap();
+
+        let now = chrono::Utc::now().naive_utc();
+        let sch_config = ScheduledNotificationPluginConfig {
+            body_template: "Maintenance on {{ day".to_string(), // This template can't be compiled
+            subject_template: "Maintenance".to_string(),
+            schedule_frequency: "once".to_string(),
+            schedule_start_time: DateTime::from_utc(now, Utc),
+            ..Default::default()
+        };
+
+        let config_row = NotificationConfigRow {
+            id: "notification_config_once_with_error".to_string(),
+            kind: NotificationConfigKind::Scheduled,
+            status: NotificationConfigStatus::Enabled,
+            recipient_ids: format!("[\"{}\"]", mock_recipient_a().id),
+            parameters: "[{\"day\":\"Tuesday\"},{\"day\":\"Wednesday\"}]".to_string(),
+            next_due_datetime: Some(now),
+            configuration_data: serde_json::to_string(&sch_config).unwrap(),
+            ..Default::default()
+        };
+        let repo = NotificationConfigRowRepository::new(&service_context.connection);
+        repo.insert_one(&config_row).unwrap();
+
+        // It fails, so nothing is sent and it stays enabled to be tried again
+        let result = process_scheduled_notifications(&service_context, now).unwrap();
+        assert_eq!(result, 1);
+
+        let stored_row = repo.find_one_by_id(&config_row.id).unwrap().unwrap();
+        assert_eq!(stored_row.status, NotificationConfigStatus::Enabled);
+
+        let event_repo = NotificationEventRowRepository::new(&service_context.connection);
+        assert_eq!(event_repo.un_sent().unwrap().len(), 0);
+        assert_eq!(event_repo.errors().unwrap().len(), 0);
+
+        // Once the template is fixed, it is sent on the next check for every parameter set, then disabled
+        let sch_config = ScheduledNotificationPluginConfig {
+            body_template: "Maintenance on {{ day }}".to_string(),
+            ..sch_config
+        };
+        repo.update_one(&NotificationConfigRow {
+            configuration_data: serde_json::to_string(&sch_config).unwrap(),
+            ..stored_row
+        })
+        .unwrap();
+
+        let next_check = now + chrono::Duration::seconds(10);
+        let result = process_scheduled_notifications(&service_context, next_check).unwrap();
+        assert_eq!(result, 1);
+
+        let stored_row = repo.find_one_by_id(&config_row.id).unwrap().unwrap();
+        assert_eq!(stored_row.status, NotificationConfigStatus::Disabled);
+        assert_eq!(event_repo.un_sent().unwrap().len(), 2);
+
+        // It isn't processed again
+        let result = process_scheduled_notifications(
+            &service_context,
+            next_check + chrono::Duration::seconds(10),
+        )
+        .unwrap();
+        assert_eq!(result, 0);
+        assert_eq!(event_repo.un_sent().unwrap().len(), 2);
+    }
 }
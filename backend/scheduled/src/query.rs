@@ -1,9 +1,16 @@
 use std::collections::HashMap;
 
+use datasource::QueryLimits;
 use log::info;
-use repository::{EqualFilter, NotificationQueryFilter, NotificationQueryRepository};
+use repository::{
+    EqualFilter, NotificationQueryFilter, NotificationQueryRepository, NotificationQueryRow,
+};
 use serde_json::json;
-use service::service_provider::ServiceContext;
+use service::{
+    datasource::{render_sql_query, DatasourceServiceError, QueryResult},
+    notification_run::QueryTiming,
+    service_provider::ServiceContext,
+};
 
 use crate::{parse::ScheduledNotificationPluginConfig, NotificationError};
 
@@ -12,11 +19,41 @@ pub enum NotificationQueryResult {
     Skipped(String),
 }
 
+/// Caches query results for the duration of a single scheduled run.
+/// Results are keyed by the query id and the rendered SQL, so queries that don't use any parameters
+/// (or render to the same SQL for different parameter sets) are only run once per run.
+/// Only successful results are cached.
+/// The time taken by each query that is actually run is also recorded, for the run history.
+#[derive(Default)]
+pub struct QueryCache {
+    results: HashMap<(String, String), QueryResult>,
+    query_timings: Vec<QueryTiming>,
+}
+
+impl QueryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn take_query_timings(&mut self) -> Vec<QueryTiming> {
+        std::mem::take(&mut self.query_timings)
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
 pub fn get_notification_query_results(
     ctx: &ServiceContext,
     parameters: serde_json::Value,
     config: &ScheduledNotificationPluginConfig,
     required_query_ids: Vec<String>,
+    query_cache: &mut QueryCache,
 ) -> Result<NotificationQueryResult, NotificationError> {
     let mut query_results = HashMap::new();
 
@@ -32,16 +69,32 @@ pub fn get_notification_query_results(
             NotificationError::InternalError(format!("Unable to get notification queries: {:?}", e))
         })?;
 
-    // loop through all the notification query ids, run them, and store the results
-    for query in queries {
-        let now = chrono::Utc::now();
-        let result = ctx
-            .service_provider
-            .datasource_service
-            .run_sql_query_with_parameters(query.query.clone(), parameters.clone());
+    // run all the notification queries, then store the results
+    for (query, result, duration) in run_queries(ctx, &queries, &parameters, query_cache)? {
         let query_json = match result {
-            Ok(result) => serde_json::from_str(&result.results)
-                .unwrap_or_else(|_| json!([{"error": "Unable to parse query result"}])),
+            Ok(result) => {
+                if let Some(query_error) = &result.query_error {
+                    log::error!(
+                        "Query {} for {}({}) returned an error: {}",
+                        query.reference_name,
+                        config.title,
+                        config.id,
+                        query_error
+                    );
+                }
+                if result.truncated {
+                    log::warn!(
+                        "Query {} for {}({}) returned more than {} rows, only the first {} rows will be used",
+                        query.reference_name,
+                        config.title,
+                        config.id,
+                        query.max_rows.unwrap_or_default(),
+                        query.max_rows.unwrap_or_default()
+                    );
+                }
+                serde_json::from_str(&result.results)
+                    .unwrap_or_else(|_| json!([{"error": "Unable to parse query result"}]))
+            }
             Err(e) => {
                 log::error!(
                     "Error running query {} for {}({}) : {:?}",
@@ -66,20 +119,21 @@ pub fn get_notification_query_results(
                 None => {
                     return Err(NotificationError::InternalError(format!(
                         "Required query {} did not return an array (got: {})",
-                        query.reference_name,
-                        query_json
+                        query.reference_name, query_json
                     )));
                 }
                 _ => {}
             }
         }
 
-        let end_time = chrono::Utc::now();
-        info!(
-            "Query {} took {}ms",
-            query.reference_name,
-            (end_time - now).num_milliseconds()
-        );
+        match duration {
+            Some(duration) => info!(
+                "Query {} took {}ms",
+                query.reference_name,
+                duration.num_milliseconds()
+            ),
+            None => info!("Query {} used a cached result", query.reference_name),
+        }
 
         query_results.insert(query.reference_name, query_json);
     }
@@ -87,6 +141,126 @@ pub fn get_notification_query_results(
     Ok(NotificationQueryResult::Success(query_results))
 }
 
+type QueryRun = (
+    NotificationQueryRow,
+    Result<QueryResult, DatasourceServiceError>,
+    Option<chrono::Duration>, // None if the result came from the cache
+);
+
+/// Runs the queries concurrently, using at most as many threads as there are connections in the datasource pool.
+/// Queries with a result already in the cache aren't run again.
+/// Results are returned in the same order as the queries.
+fn run_queries(
+    ctx: &ServiceContext,
+    queries: &[NotificationQueryRow],
+    parameters: &serde_json::Value,
+    query_cache: &mut QueryCache,
+) -> Result<Vec<QueryRun>, NotificationError> {
+    let datasource_service = &ctx.service_provider.datasource_service;
+    let concurrency = (datasource_service.get_connection_pool().pool.max_size() as usize).max(1);
+
+    // If the query can't be rendered there's no cache key, the datasource service will report the error when it's run
+    let cache_keys: Vec<Option<(String, String)>> = queries
+        .iter()
+        .map(|query| {
+            render_sql_query(&query.query, parameters.clone())
+                .ok()
+                .map(|sql| (query.id.clone(), sql))
+        })
+        .collect();
+
+    let uncached_queries: Vec<&NotificationQueryRow> = queries
+        .iter()
+        .zip(cache_keys.iter())
+        .filter(|(_, key)| match key {
+            Some(key) => !query_cache.results.contains_key(key),
+            None => true,
+        })
+        .map(|(query, _)| query)
+        .collect();
+
+    let mut new_runs: HashMap<String, QueryRun> = HashMap::new();
+    for chunk in uncached_queries.chunks(concurrency) {
+        let chunk_runs = std::thread::scope(|scope| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|query| {
+                    scope.spawn(move || {
+                        let start_time = chrono::Utc::now();
+                        let result = datasource_service.run_sql_query_with_limits(
+                            query.query.clone(),
+                            parameters.clone(),
+                            query_limits(query),
+                        );
+                        (
+                            (*query).clone(),
+                            result,
+                            Some(chrono::Utc::now() - start_time),
+                        )
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|_| {
+            NotificationError::InternalError("Notification query thread panicked".to_string())
+        })?;
+
+        for query_run in chunk_runs {
+            new_runs.insert(query_run.0.id.clone(), query_run);
+        }
+    }
+
+    let mut query_runs = Vec::new();
+    for (query, cache_key) in queries.iter().zip(cache_keys) {
+        if let Some(query_run) = new_runs.remove(&query.id) {
+            let (_, result, duration) = &query_run;
+            query_cache.query_timings.push(QueryTiming {
+                query: query.reference_name.clone(),
+                parameters: parameters.clone(),
+                duration_ms: duration.map(|d| d.num_milliseconds()).unwrap_or_default(),
+                error: match result {
+                    Ok(result) => result.query_error.clone(),
+                    Err(e) => Some(format!("{:?}", e)),
+                },
+            });
+            // Errors such as timeouts aren't cached, so the query is tried again for the next parameter set
+            if let (Some(cache_key), Ok(result)) = (cache_key, result) {
+                if result.query_error.is_none() {
+                    query_cache.results.insert(cache_key, result.clone());
+                }
+            }
+            query_runs.push(query_run);
+            continue;
+        }
+
+        let cached_result = cache_key
+            .and_then(|key| query_cache.results.get(&key).cloned())
+            .ok_or_else(|| {
+                NotificationError::InternalError(format!(
+                    "No result found for query {}",
+                    query.reference_name
+                ))
+            })?;
+        query_runs.push((query.clone(), Ok(cached_result), None));
+    }
+
+    Ok(query_runs)
+}
+
+fn query_limits(query: &NotificationQueryRow) -> QueryLimits {
+    // Zero or negative values mean no limit
+    let positive = |value: Option<i32>| value.filter(|v| *v > 0).map(|v| v as u32);
+    QueryLimits {
+        timeout_seconds: positive(query.timeout_seconds),
+        max_rows: positive(query.max_rows),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -97,7 +271,7 @@ mod tests {
             mock_notification_query_with_params, MockDataInserts,
         },
         test_db::setup_all,
-        NotificationConfigStatus,
+        NotificationConfigStatus, NotificationQueryRowRepository,
     };
     use util::uuid::uuid;
 
@@ -143,9 +317,14 @@ mod tests {
         };
 
         // Call the function being tested
-        let query_results =
-            get_notification_query_results(&context, all_params[0].clone(), &config, vec![])
-                .unwrap();
+        let query_results = get_notification_query_results(
+            &context,
+            all_params[0].clone(),
+            &config,
+            vec![],
+            &mut QueryCache::new(),
+        )
+        .unwrap();
 
         let query_results = match query_results {
             NotificationQueryResult::Success(results) => results,
@@ -216,9 +395,14 @@ mod tests {
         };
 
         // Call the function being tested
-        let query_results =
-            get_notification_query_results(&context, all_params[0].clone(), &config, vec![])
-                .unwrap();
+        let query_results = get_notification_query_results(
+            &context,
+            all_params[0].clone(),
+            &config,
+            vec![],
+            &mut QueryCache::new(),
+        )
+        .unwrap();
 
         let query_results = match query_results {
             NotificationQueryResult::Success(results) => results,
@@ -287,9 +471,14 @@ mod tests {
         };
 
         // Call the function being tested
-        let query_results =
-            get_notification_query_results(&context, all_params[0].clone(), &config, vec![])
-                .unwrap();
+        let query_results = get_notification_query_results(
+            &context,
+            all_params[0].clone(),
+            &config,
+            vec![],
+            &mut QueryCache::new(),
+        )
+        .unwrap();
 
         let query_results = match query_results {
             NotificationQueryResult::Success(results) => results,
@@ -377,9 +566,14 @@ mod tests {
         };
 
         // Call the function being tested
-        let query_results =
-            get_notification_query_results(&context, all_params[0].clone(), &config, vec![])
-                .unwrap();
+        let query_results = get_notification_query_results(
+            &context,
+            all_params[0].clone(),
+            &config,
+            vec![],
+            &mut QueryCache::new(),
+        )
+        .unwrap();
 
         let query_results = match query_results {
             NotificationQueryResult::Success(results) => results,
@@ -434,6 +628,7 @@ mod tests {
             all_params[0].clone(),
             &config,
             vec![mock_query.id.clone()],
+            &mut QueryCache::new(),
         )
         .unwrap();
 
@@ -451,4 +646,154 @@ mod tests {
             }
         }
     }
+
+    // Test that the row limit and timeout configured on a query are applied
+    #[tokio::test]
+    async fn test_get_notification_query_results_with_limits() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_get_notification_query_results_with_limits",
+            MockDataInserts::none(),
+        )
+        .await;
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        let repo = NotificationQueryRowRepository::new(&context.connection);
+        let limited_query = NotificationQueryRow {
+            id: "limited_query".to_string(),
+            reference_name: "limited".to_string(),
+            query: "SELECT generate_series(1, 10) as row_id".to_string(),
+            max_rows: Some(3),
+            ..Default::default()
+        };
+        repo.insert_one(&limited_query).unwrap();
+        let slow_query = NotificationQueryRow {
+            id: "slow_query".to_string(),
+            reference_name: "slow".to_string(),
+            query: "SELECT pg_sleep(3) as slept".to_string(),
+            timeout_seconds: Some(1),
+            ..Default::default()
+        };
+        repo.insert_one(&slow_query).unwrap();
+
+        let config = ScheduledNotificationPluginConfig {
+            notification_query_ids: vec![limited_query.id.clone(), slow_query.id.clone()],
+            ..Default::default()
+        };
+
+        let query_results = get_notification_query_results(
+            &context,
+            json!({}),
+            &config,
+            vec![],
+            &mut QueryCache::new(),
+        )
+        .unwrap();
+
+        let query_results = match query_results {
+            NotificationQueryResult::Success(results) => results,
+            NotificationQueryResult::Skipped(reason) => {
+                panic!("Query was skipped: {}", reason);
+            }
+        };
+
+        assert_eq!(
+            query_results.get("limited").unwrap(),
+            &json!([{"row_id": 1}, {"row_id": 2}, {"row_id": 3}])
+        );
+        // The slow query is cancelled, so returns no rows
+        assert_eq!(query_results.get("slow").unwrap(), &json!([]));
+    }
+
+    // Test that query results are reused across parameter sets when the rendered SQL is the same
+    #[tokio::test]
+    async fn test_get_notification_query_results_cached() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_get_notification_query_results_cached",
+            MockDataInserts::none(),
+        )
+        .await;
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        let repo = NotificationQueryRowRepository::new(&context.connection);
+        // random() lets us tell whether the query was run again
+        let no_param_query = NotificationQueryRow {
+            id: "no_param_query".to_string(),
+            reference_name: "no_param".to_string(),
+            query: "SELECT random() as value".to_string(),
+            ..Default::default()
+        };
+        repo.insert_one(&no_param_query).unwrap();
+        let param_query = NotificationQueryRow {
+            id: "param_query".to_string(),
+            reference_name: "param".to_string(),
+            query: "SELECT random() as value, '{{ store }}' as store".to_string(),
+            ..Default::default()
+        };
+        repo.insert_one(&param_query).unwrap();
+
+        let config = ScheduledNotificationPluginConfig {
+            notification_query_ids: vec![no_param_query.id.clone(), param_query.id.clone()],
+            ..Default::default()
+        };
+
+        let mut query_cache = QueryCache::new();
+        let mut get_results = |parameters: serde_json::Value| match get_notification_query_results(
+            &context,
+            parameters,
+            &config,
+            vec![],
+            &mut query_cache,
+        )
+        .unwrap()
+        {
+            NotificationQueryResult::Success(results) => results,
+            NotificationQueryResult::Skipped(reason) => {
+                panic!("Query was skipped: {}", reason);
+            }
+        };
+
+        let store_a = get_results(json!({"store": "A"}));
+        let store_b = get_results(json!({"store": "B"}));
+        let store_a_again = get_results(json!({"store": "A"}));
+
+        // The query without parameters is only run once
+        assert_eq!(store_a.get("no_param"), store_b.get("no_param"));
+        // The parameterised query is run for each distinct parameter value
+        assert_ne!(store_a.get("param"), store_b.get("param"));
+        assert_eq!(store_a.get("param"), store_a_again.get("param"));
+
+        assert_eq!(query_cache.len(), 3);
+        // Only the queries that were actually run are timed
+        assert_eq!(query_cache.take_query_timings().len(), 3);
+
+        // Queries that return an error are run again
+        let error_query = NotificationQueryRow {
+            id: "error_query".to_string(),
+            reference_name: "error".to_string(),
+            query: "SELECT * FROM table_that_does_not_exist".to_string(),
+            ..Default::default()
+        };
+        repo.insert_one(&error_query).unwrap();
+        let config = ScheduledNotificationPluginConfig {
+            notification_query_ids: vec![error_query.id.clone()],
+            ..Default::default()
+        };
+        let mut query_cache = QueryCache::new();
+        for _ in 0..2 {
+            get_notification_query_results(&context, json!({}), &config, vec![], &mut query_cache)
+                .unwrap();
+        }
+        assert!(query_cache.is_empty());
+        let query_timings = query_cache.take_query_timings();
+        assert_eq!(query_timings.len(), 2);
+        assert!(query_timings.iter().all(|timing| timing.error.is_some()));
+    }
 }
@@ -0,0 +1,413 @@
+use repository::{
+    NotificationConfigKind, NotificationEventRow, NotificationEventRowRepository, NotificationType,
+    RecipientRowRepository, UserAccountRowRepository,
+};
+use service::{
+    notification::enqueue::{
+        render_notification_events, NotificationContext, NotificationTarget, TemplateDefinition,
+    },
+    service_provider::ServiceContext,
+    SingleRecordError,
+};
+
+use crate::{
+    parse::ScheduledNotificationPluginConfig,
+    process::{get_parameter_sets, prepare_parameter_set, PreparedParameterSet},
+    query::QueryCache,
+    NotificationError,
+};
+
+pub struct RunNotificationConfigNow {
+    pub id: String,
+    /// Send to this recipient rather than the calling user (ignored for dry runs)
+    pub recipient_id: Option<String>,
+    /// Render the notifications for the configured recipients, without sending anything
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterSetResult {
+    pub parameters: serde_json::Value,
+    pub skipped_reason: Option<String>,
+    pub notifications: Vec<NotificationEventRow>,
+}
+
+/// Runs a scheduled notification config immediately, regardless of when it is next due.
+/// This doesn't update the last run or next due times, and ignores the only send when changed setting.
+pub fn run_notification_config_now(
+    ctx: &ServiceContext,
+    input: RunNotificationConfigNow,
+) -> Result<Vec<ParameterSetResult>, NotificationError> {
+    let scheduled_notification = ctx
+        .service_provider
+        .notification_config_service
+        .get_notification_config(ctx, input.id.clone())
+        .map_err(|e| match e {
+            SingleRecordError::NotFound(_) => NotificationError::BadUserInput(format!(
+                "Notification config {} does not exist",
+                input.id
+            )),
+            SingleRecordError::DatabaseError(e) => {
+                NotificationError::InternalError(format!("{:?}", e))
+            }
+        })?;
+
+    if scheduled_notification.kind != NotificationConfigKind::Scheduled {
+        return Err(NotificationError::BadUserInput(
+            "Only scheduled notifications can be run now".to_string(),
+        ));
+    }
+
+    let config =
+        ScheduledNotificationPluginConfig::from_string(&scheduled_notification.configuration_data)?;
+
+    let send_to = match input.dry_run {
+        true => None,
+        false => Some(get_run_now_target(ctx, input.recipient_id)?),
+    };
+
+    let mut query_cache = QueryCache::new();
+    let mut results = vec![];
+    for template_params in get_parameter_sets(ctx, &scheduled_notification)? {
+        let prepared_notifications = prepare_parameter_set(
+            ctx,
+            &scheduled_notification,
+            &config,
+            template_params,
+            send_to.as_ref(),
+            &mut query_cache,
+        )?;
+
+        for prepared in prepared_notifications {
+            let (parameters, notification_targets, template_data) = match prepared {
+                PreparedParameterSet::Ready {
+                    parameters,
+                    notification_targets,
+                    template_data,
+                } => (parameters, notification_targets, template_data),
+                PreparedParameterSet::Skipped { parameters, reason } => {
+                    results.push(ParameterSetResult {
+                        parameters,
+                        skipped_reason: Some(reason),
+                        notifications: vec![],
+                    });
+                    continue;
+                }
+            };
+
+            let notification = NotificationContext {
+                title_template: Some(TemplateDefinition::Template(
+                    config.subject_template.clone(),
+                )),
+                body_template: TemplateDefinition::Template(config.body_template.clone()),
+                template_data,
+                recipients: notification_targets,
+                dedup_key: None,
+                priority: config.priority.clone(),
+                channel_templates: config.channel_templates(),
+            };
+
+            let notifications = render_notification_events(
+                ctx,
+                &Some(scheduled_notification.id.clone()),
+                notification,
+            )
+            .map_err(|e| NotificationError::InvalidTemplate(format!("{:?}", e)))?;
+
+            if send_to.is_some() {
+                let repo = NotificationEventRowRepository::new(&ctx.connection);
+                for notification in &notifications {
+                    repo.insert_one(notification)
+                        .map_err(|e| NotificationError::InternalError(format!("{:?}", e)))?;
+                }
+                ctx.service_provider.notification_dispatch.notify();
+            }
+
+            results.push(ParameterSetResult {
+                parameters,
+                skipped_reason: None,
+                notifications,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+/// Use the specified recipient if there is one, otherwise send to the calling user's email address
+fn get_run_now_target(
+    ctx: &ServiceContext,
+    recipient_id: Option<String>,
+) -> Result<NotificationTarget, NotificationError> {
+    if let Some(recipient_id) = recipient_id {
+        let recipient = RecipientRowRepository::new(&ctx.connection)
+            .find_one_by_id(&recipient_id)
+            .map_err(|e| NotificationError::InternalError(format!("{:?}", e)))?;
+        return match recipient {
+            Some(recipient) => Ok(NotificationTarget::from(recipient)),
+            None => Err(NotificationError::BadUserInput(format!(
+                "Recipient {} does not exist",
+                recipient_id
+            ))),
+        };
+    }
+
+    let user = UserAccountRowRepository::new(&ctx.connection)
+        .find_one_by_id(&ctx.user_id)
+        .map_err(|e| NotificationError::InternalError(format!("{:?}", e)))?;
+
+    match user {
+        Some(user) => match user.email {
+            Some(email) if !email.is_empty() => Ok(NotificationTarget {
+                name: user.display_name,
+                to_address: email,
+                notification_type: NotificationType::Email,
+                ..Default::default()
+            }),
+            _ => Err(NotificationError::BadUserInput(
+                "Your user account doesn't have an email address, please select a recipient"
+                    .to_string(),
+            )),
+        },
+        None => Err(NotificationError::BadUserInput(
+            "Unable to find the current user, please select a recipient".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use repository::{
+        mock::{
+            mock_recipient_a, mock_recipient_list_with_recipient_members_a_and_b,
+            mock_user_account_a, MockDataInserts,
+        },
+        test_db::setup_all,
+        NotificationConfigRow, NotificationConfigRowRepository, NotificationConfigStatus,
+    };
+    use service::{service_provider::ServiceProvider, test_utils::get_test_settings};
+
+    use super::*;
+
+    fn scheduled_config_row() -> NotificationConfigRow {
+        let sch_config = ScheduledNotificationPluginConfig {
+            body_template: "Hello {{ recipient.name }}, store {{ store }}".to_string(),
+            subject_template: "Run now".to_string(),
+            schedule_frequency: "daily".to_string(),
+            ..Default::default()
+        };
+
+        NotificationConfigRow {
+            id: "scheduled_config_run_now".to_string(),
+            title: "Run now".to_string(),
+            kind: NotificationConfigKind::Scheduled,
+            configuration_data: serde_json::to_string(&sch_config).unwrap(),
+            status: NotificationConfigStatus::Disabled,
+            parameters: "[{\"store\":\"A\"},{\"store\":\"B\"}]".to_string(),
+            recipient_ids: "[]".to_string(),
+            recipient_list_ids: format!(
+                "[\"{}\"]",
+                mock_recipient_list_with_recipient_members_a_and_b().id
+            ),
+            sql_recipient_list_ids: "[]".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_notification_config_now_dry_run() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_run_notification_config_now_dry_run",
+            MockDataInserts::none()
+                .recipients()
+                .recipient_lists()
+                .recipient_list_members(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let ctx = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        let config_row = scheduled_config_row();
+        NotificationConfigRowRepository::new(&ctx.connection)
+            .insert_one(&config_row)
+            .unwrap();
+
+        let results = run_notification_config_now(
+            &ctx,
+            RunNotificationConfigNow {
+                id: config_row.id.clone(),
+                recipient_id: None,
+                dry_run: true,
+            },
+        )
+        .unwrap();
+
+        // One result per parameter set, with a notification for each recipient in the list
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].skipped_reason, None);
+        assert_eq!(results[0].notifications.len(), 2);
+        assert!(results[1].notifications[0].message.contains("store B"));
+
+        // Nothing should be queued for a dry run
+        let repo = NotificationEventRowRepository::new(&ctx.connection);
+        assert_eq!(repo.un_sent().unwrap().len(), 0);
+
+        // The schedule shouldn't be changed
+        let config = NotificationConfigRowRepository::new(&ctx.connection)
+            .find_one_by_id(&config_row.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.last_run_datetime, None);
+    }
+
+    #[tokio::test]
+    async fn test_run_notification_config_now_send() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_run_notification_config_now_send",
+            MockDataInserts::none()
+                .user_accounts()
+                .recipients()
+                .recipient_lists()
+                .recipient_list_members(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let ctx = ServiceContext::with_user(service_provider, mock_user_account_a().id).unwrap();
+
+        let config_row = scheduled_config_row();
+        NotificationConfigRowRepository::new(&ctx.connection)
+            .insert_one(&config_row)
+            .unwrap();
+
+        // Send to the calling user
+        let results = run_notification_config_now(
+            &ctx,
+            RunNotificationConfigNow {
+                id: config_row.id.clone(),
+                recipient_id: None,
+                dry_run: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].notifications.len(), 1);
+
+        let repo = NotificationEventRowRepository::new(&ctx.connection);
+        let events = repo.un_sent().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].to_address, mock_user_account_a().email.unwrap());
+
+        // Send to a specific recipient
+        run_notification_config_now(
+            &ctx,
+            RunNotificationConfigNow {
+                id: config_row.id.clone(),
+                recipient_id: Some(mock_recipient_a().id),
+                dry_run: false,
+            },
+        )
+        .unwrap();
+        let events = repo.un_sent().unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| e.to_address == mock_recipient_a().to_address)
+                .count(),
+            2
+        );
+
+        // Unknown recipient
+        let result = run_notification_config_now(
+            &ctx,
+            RunNotificationConfigNow {
+                id: config_row.id.clone(),
+                recipient_id: Some("not_a_recipient".to_string()),
+                dry_run: false,
+            },
+        );
+        assert!(matches!(result, Err(NotificationError::BadUserInput(_))));
+    }
+
+    // A config can be tried out before any recipients have been added to it
+    #[tokio::test]
+    async fn test_run_notification_config_now_without_recipients() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_run_notification_config_now_without_recipients",
+            MockDataInserts::none().user_accounts().recipients(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let ctx = ServiceContext::with_user(service_provider, mock_user_account_a().id).unwrap();
+
+        let config_row = NotificationConfigRow {
+            recipient_list_ids: "[]".to_string(),
+            ..scheduled_config_row()
+        };
+        NotificationConfigRowRepository::new(&ctx.connection)
+            .insert_one(&config_row)
+            .unwrap();
+
+        // Send to the calling user
+        let results = run_notification_config_now(
+            &ctx,
+            RunNotificationConfigNow {
+                id: config_row.id.clone(),
+                recipient_id: None,
+                dry_run: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].skipped_reason, None);
+        assert_eq!(
+            results[0].notifications[0].to_address,
+            mock_user_account_a().email.unwrap()
+        );
+
+        // Send to a specific recipient
+        let results = run_notification_config_now(
+            &ctx,
+            RunNotificationConfigNow {
+                id: config_row.id.clone(),
+                recipient_id: Some(mock_recipient_a().id),
+                dry_run: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(results[0].skipped_reason, None);
+        assert_eq!(
+            results[0].notifications[0].message,
+            format!("Hello {}, store A", mock_recipient_a().name)
+        );
+
+        let repo = NotificationEventRowRepository::new(&ctx.connection);
+        assert_eq!(repo.un_sent().unwrap().len(), 4);
+
+        // A dry run still shows there is no one to send to
+        let results = run_notification_config_now(
+            &ctx,
+            RunNotificationConfigNow {
+                id: config_row.id.clone(),
+                recipient_id: None,
+                dry_run: true,
+            },
+        )
+        .unwrap();
+        assert!(results[0].skipped_reason.is_some());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     auto_backup::auto_backup, configuration::get_or_create_token_secret, cors::cors_policy,
+    notification_dispatcher::notification_dispatcher, retention::retention_task,
     scheduled_tasks::scheduled_task_runner, serve_frontend::config_server_frontend,
     static_files::config_static_files,
 };
@@ -38,6 +39,8 @@ pub mod cors;
 pub mod environment;
 pub mod logging;
 pub mod middleware;
+mod notification_dispatcher;
+mod retention;
 mod scheduled_tasks;
 mod serve_frontend;
 pub mod static_files;
@@ -97,6 +100,20 @@ async fn run_server(
         auto_backup(auto_backup_context).await;
     });
 
+    let retention_context = ServiceContext::new(service_provider_data.clone().into_inner());
+    let retention_context = match retention_context {
+        Ok(retention_context) => retention_context,
+        Err(error) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Error unable to create retention task context: {:?}", error),
+            ));
+        }
+    };
+    let retention_handle = actix_web::rt::spawn(async move {
+        retention_task(retention_context).await;
+    });
+
     let scheduled_task_context = ServiceContext::new(service_provider_data.clone().into_inner());
     let scheduled_task_context = match scheduled_task_context {
         Ok(scheduled_task_context) => scheduled_task_context,
@@ -118,6 +135,21 @@ async fn run_server(
         scheduled_task_runner(scheduled_task_context, plugins).await;
     });
 
+    // Notifications are sent from their own thread, so they aren't held up by plugins processing scheduled tasks
+    let dispatcher_service_provider = service_provider_data.clone().into_inner();
+    let notification_dispatcher_arbiter = actix_rt::Arbiter::new();
+    notification_dispatcher_arbiter.spawn_fn(move || {
+        match ServiceContext::new(dispatcher_service_provider) {
+            Ok(dispatcher_context) => {
+                actix_web::rt::spawn(notification_dispatcher(dispatcher_context));
+            }
+            Err(error) => error!(
+                "Error unable to create notification dispatcher context: {:?}",
+                error
+            ),
+        }
+    });
+
     // Setup a channel to receive telegram messages, which we want to handle in recipient service
     let telegram_token = config_settings.clone().telegram.token;
     let telegram_update_handler_option = match telegram_token {
@@ -232,7 +264,9 @@ async fn run_server(
 
     server_handle.stop(true).await;
     scheduled_task_handle.abort();
+    notification_dispatcher_arbiter.stop();
     auto_backup_handle.abort();
+    retention_handle.abort();
     if let Some(telegram_update_handler) = telegram_update_handler_option {
         telegram_update_handler.abort();
     }
@@ -0,0 +1,66 @@
+use service::service_provider::ServiceContext;
+use std::time::Duration;
+
+/// Sends queued emails and notifications, separately from the scheduled tasks so that slow sends don't hold up plugins.
+/// Runs as soon as new notifications are queued, and also every `sweep_interval_seconds` to pick up retries.
+pub async fn notification_dispatcher(service_context: ServiceContext) {
+    let mut receiver = match service_context
+        .service_provider
+        .notification_dispatch
+        .take_receiver()
+    {
+        Some(receiver) => receiver,
+        None => {
+            log::error!("Notification dispatcher is already running");
+            return;
+        }
+    };
+
+    let sweep_interval = Duration::from_secs(
+        service_context
+            .service_provider
+            .settings
+            .dispatch
+            .sweep_interval_seconds
+            .max(1),
+    );
+    let mut interval = actix_web::rt::time::interval(sweep_interval);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                log::debug!("Checking for queued notifications");
+            }
+            _ = receiver.recv() => {
+                log::debug!("New notifications queued");
+            }
+        }
+
+        let send_emails = service_context
+            .service_provider
+            .email_service
+            .send_queued_emails(&service_context);
+        match send_emails {
+            Ok(num) => {
+                if num > 0 {
+                    log::info!("Sent {} queued emails", num);
+                }
+            }
+            Err(error) => log::error!("Error sending queued emails: {:?}", error),
+        };
+
+        let send_notifications = service_context
+            .service_provider
+            .notification_service
+            .send_queued_notifications(&service_context)
+            .await;
+        match send_notifications {
+            Ok(num) => {
+                if num > 0 {
+                    log::info!("Sent {} queued notifications", num);
+                }
+            }
+            Err(error) => log::error!("Error sending queued notifications: {:?}", error),
+        };
+    }
+}
@@ -0,0 +1,41 @@
+use chrono::{offset::Local, Utc};
+use cron::Schedule;
+use service::{retention::purge_old_records, service_provider::ServiceContext};
+use std::str::FromStr;
+use tokio::time::sleep;
+
+/// Deletes old notification events and audit logs, as configured in the `retention` settings
+pub async fn retention_task(service_context: ServiceContext) {
+    let settings = service_context.service_provider.settings.retention.clone();
+    if !settings.is_enabled() {
+        log::info!("Retention disabled, old notifications and audit logs are kept");
+        return;
+    }
+
+    let schedule = match Schedule::from_str(&settings.cron) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            log::error!(
+                "Error parsing retention cron string {}, defaulting to daily at 2am: {}",
+                settings.cron,
+                e
+            );
+            Schedule::from_str("0 0 2 * * *").unwrap()
+        }
+    };
+
+    for datetime in schedule.upcoming(Local) {
+        log::debug!("Next retention check at {}", datetime);
+        let duration = datetime.signed_duration_since(Local::now());
+        sleep(duration.to_std().unwrap_or_default()).await;
+
+        match purge_old_records(&service_context, &settings, Utc::now().naive_utc()) {
+            Ok(result) => log::info!(
+                "Deleted {} old notifications and {} old audit logs",
+                result.notification_events_deleted,
+                result.audit_logs_deleted
+            ),
+            Err(e) => log::error!("Error deleting old records: {:?}", e),
+        }
+    }
+}
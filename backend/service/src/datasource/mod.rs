@@ -1,7 +1,7 @@
 use crate::settings::Settings;
 use datasource::{
-    get_datasource_pool, pg_sql_query_as_json_rows, pg_sql_query_as_recipients, BasicRecipientRow,
-    DatasourcePool,
+    get_datasource_pool, pg_sql_query_as_json_rows, pg_sql_query_as_json_rows_with_limits,
+    pg_sql_query_as_recipients, BasicRecipientRow, DatasourcePool, QueryLimits,
 };
 use tera::{Context, Tera};
 
@@ -13,6 +13,12 @@ pub trait DatasourceServiceTrait: Send + Sync {
         sql_query: String,
         parameters: serde_json::Value,
     ) -> Result<QueryResult, DatasourceServiceError>;
+    fn run_sql_query_with_limits(
+        &self,
+        sql_query: String,
+        parameters: serde_json::Value,
+        limits: QueryLimits,
+    ) -> Result<QueryResult, DatasourceServiceError>;
     fn run_recipient_query(
         &self,
         sql_query: String,
@@ -29,6 +35,7 @@ pub struct QueryResult {
     pub results: String,
     pub query: String,
     pub query_error: Option<String>,
+    pub truncated: bool, // True if the query returned more rows than allowed
 }
 
 #[derive(Debug)]
@@ -76,6 +83,7 @@ impl DatasourceServiceTrait for DatasourceService {
             results: json,
             query: sql_query,
             query_error: query_error,
+            truncated: false,
         })
     }
     fn run_recipient_query(
@@ -100,6 +108,15 @@ impl DatasourceServiceTrait for DatasourceService {
         &self,
         sql_query: String,
         parameters: serde_json::Value,
+    ) -> Result<QueryResult, DatasourceServiceError> {
+        self.run_sql_query_with_limits(sql_query, parameters, QueryLimits::default())
+    }
+
+    fn run_sql_query_with_limits(
+        &self,
+        sql_query: String,
+        parameters: serde_json::Value,
+        limits: QueryLimits,
     ) -> Result<QueryResult, DatasourceServiceError> {
         let connection = &mut self.connection_pool.pool.get().map_err(|error| {
             DatasourceServiceError::InternalError(format!(
@@ -108,29 +125,16 @@ impl DatasourceServiceTrait for DatasourceService {
             ))
         })?;
 
-        // Pass params to template to get the full query
-        let tera_context = Context::from_value(parameters).map_err(|e| {
-            DatasourceServiceError::InternalError(format!(
-                "Failed to convert params to tera context: {}",
-                e.to_string()
-            ))
-        })?;
-
-        let full_query = Tera::one_off(&sql_query, &tera_context, false).map_err(|e| {
-            DatasourceServiceError::InternalError(format!(
-                "Failed to parse query as tera template: {}",
-                e.to_string()
-            ))
-        })?;
+        let full_query = render_sql_query(&sql_query, parameters)?;
 
         // Run query
-        let result = pg_sql_query_as_json_rows(connection, full_query.clone());
+        let result = pg_sql_query_as_json_rows_with_limits(connection, full_query.clone(), limits);
         let mut query_error = None;
-        let result = match result{
-            Ok(rows) => rows,
+        let (result, truncated) = match result {
+            Ok(result) => (result.rows, result.truncated),
             Err(e) => {
                 query_error = Some(format!("{:?}", e));
-                vec![]// return empty array of results if there's an error
+                (vec![], false) // return empty array of results if there's an error
             }
         };
 
@@ -145,7 +149,8 @@ impl DatasourceServiceTrait for DatasourceService {
         Ok(QueryResult {
             results: json,
             query: full_query,
-            query_error: query_error,
+            query_error,
+            truncated,
         })
     }
 
@@ -154,6 +159,26 @@ impl DatasourceServiceTrait for DatasourceService {
     }
 }
 
+/// Pass params to the query template to get the full query
+pub fn render_sql_query(
+    sql_query: &str,
+    parameters: serde_json::Value,
+) -> Result<String, DatasourceServiceError> {
+    let tera_context = Context::from_value(parameters).map_err(|e| {
+        DatasourceServiceError::InternalError(format!(
+            "Failed to convert params to tera context: {}",
+            e.to_string()
+        ))
+    })?;
+
+    Tera::one_off(sql_query, &tera_context, false).map_err(|e| {
+        DatasourceServiceError::InternalError(format!(
+            "Failed to parse query as tera template: {}",
+            e.to_string()
+        ))
+    })
+}
+
 #[cfg(test)]
 #[cfg(feature = "datasource-tests")]
 mod test {}
@@ -37,5 +37,7 @@ pub fn enqueue_email(
     repo.insert_one(&email_queue_row)
         .map_err(|e| EmailServiceError::DatabaseError(e))?;
 
+    ctx.service_provider.notification_dispatch.notify();
+
     Ok(())
 }
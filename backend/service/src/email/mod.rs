@@ -16,7 +16,7 @@ use crate::email::send::send_email;
 use crate::service_provider::ServiceContext;
 use crate::settings::Settings;
 
-use self::send::EmailSendError;
+use self::send::{EmailSendError, InlineImage};
 
 pub mod enqueue;
 pub mod send;
@@ -35,6 +35,7 @@ pub trait EmailServiceTrait: Send + Sync {
         subject: String,
         html_body: String,
         text_body: String,
+        inline_images: Vec<InlineImage>,
     ) -> Result<(), EmailSendError>;
 }
 
@@ -124,6 +125,7 @@ impl EmailServiceTrait for EmailService {
                 email_clone.subject,
                 email_clone.html_body,
                 email_clone.text_body,
+                vec![],
             );
 
             match result {
@@ -199,6 +201,7 @@ impl EmailServiceTrait for EmailService {
         subject: String,
         html_body: String,
         text_body: String,
+        inline_images: Vec<InlineImage>,
     ) -> Result<(), EmailSendError> {
         send_email(
             &self.mailer,
@@ -207,6 +210,7 @@ impl EmailServiceTrait for EmailService {
             subject,
             html_body,
             text_body,
+            inline_images,
         )
     }
 }
@@ -1,6 +1,6 @@
 use lettre::{
     address::AddressError,
-    message::{Mailbox, MultiPart},
+    message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart},
     Message, SmtpTransport, Transport,
 };
 
@@ -23,6 +23,13 @@ impl EmailSendError {
     }
 }
 
+/// A png image embedded in the html body of an email, referenced as `cid:{content_id}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineImage {
+    pub content_id: String,
+    pub png: Vec<u8>,
+}
+
 /**
     send_email takes a mailer (provided as a SmtpTransport), a from address (provided as a Mailbox),
     with a subject (provided as a string) and a body (provided as a string).
@@ -36,16 +43,34 @@ pub fn send_email(
     subject: String,
     html_body: String,
     text_body: String,
+    inline_images: Vec<InlineImage>,
 ) -> Result<(), EmailSendError> {
     let to: Mailbox = to
         .parse()
         .map_err(|e: AddressError| EmailSendError::AddressError(e.to_string()))?;
 
+    let body = if inline_images.is_empty() {
+        MultiPart::alternative_plain_html(text_body, html_body)
+    } else {
+        let png = ContentType::parse("image/png").expect("image/png is a valid content type");
+        let html_with_images = inline_images.into_iter().fold(
+            MultiPart::related().singlepart(SinglePart::html(html_body)),
+            |related, image| {
+                related.singlepart(
+                    Attachment::new_inline(image.content_id).body(image.png, png.clone()),
+                )
+            },
+        );
+        MultiPart::alternative()
+            .singlepart(SinglePart::plain(text_body))
+            .multipart(html_with_images)
+    };
+
     let message = Message::builder()
         .to(to)
         .from(from)
         .subject(subject)
-        .multipart(MultiPart::alternative_plain_html(text_body, html_body))
+        .multipart(body)
         .map_err(|e| EmailSendError::MessageBuildError(e))?;
 
     mailer
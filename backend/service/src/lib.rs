@@ -12,10 +12,13 @@ pub mod notification;
 pub mod notification_config;
 pub mod notification_event;
 pub mod notification_query;
+pub mod notification_run;
+pub mod notification_template;
 pub mod plugin;
 pub mod plugin_store;
 pub mod recipient;
 pub mod recipient_list;
+pub mod retention;
 pub mod service_provider;
 pub mod settings;
 pub mod sql_recipient_list;
@@ -0,0 +1,513 @@
+use std::{
+    collections::HashMap,
+    sync::{Once, OnceLock},
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use image::{codecs::png::PngEncoder, ColorType, ImageEncoder};
+use plotters::{prelude::*, style::register_font};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tera::{Function, Tera};
+
+// Bundled so charts render the same everywhere, without relying on system fonts
+static FONT_DATA: &[u8] = include_bytes!("fonts/DejaVuSans.ttf");
+static REGISTER_FONT: Once = Once::new();
+
+const CHART_FONT: &str = "sans-serif";
+// Charts are stored in the message as their definition, and only drawn when the message is sent
+const CHART_URI_PREFIX: &str = "chart:";
+
+const DEFAULT_WIDTH: u32 = 800;
+const DEFAULT_HEIGHT: u32 = 400;
+const MIN_SIZE: u32 = 200;
+const MAX_SIZE: u32 = 2000;
+
+const SERIES_COLOURS: [RGBColor; 6] = [
+    RGBColor(31, 119, 180),
+    RGBColor(255, 127, 14),
+    RGBColor(44, 160, 44),
+    RGBColor(214, 39, 40),
+    RGBColor(148, 103, 189),
+    RGBColor(140, 86, 75),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ChartKind {
+    Line,
+    Bar,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartSeries {
+    pub name: String,
+    pub values: Vec<Option<f64>>, // One per x label, None if the row has no value
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartDefinition {
+    pub kind: ChartKind,
+    pub title: Option<String>,
+    pub x_labels: Vec<String>,
+    pub series: Vec<ChartSeries>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ChartDefinition {
+    /// Builds a chart from the arguments passed to the `chart` tera function
+    pub fn from_args(args: &HashMap<String, Value>) -> Result<Self, String> {
+        let data = args
+            .get("data")
+            .and_then(|data| data.as_array())
+            .ok_or("chart requires a `data` argument, which should be a query result array")?;
+
+        let x_key = args.get("x").and_then(|x| x.as_str()).ok_or(
+            "chart requires an `x` argument, the name of the column to use for the x axis",
+        )?;
+
+        let y_keys = match args.get("y") {
+            Some(Value::String(y)) => vec![y.clone()],
+            Some(Value::Array(ys)) => ys
+                .iter()
+                .map(|y| {
+                    y.as_str()
+                        .map(|y| y.to_string())
+                        .ok_or("chart `y` values should be column names")
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => {
+                return Err(
+                    "chart requires a `y` argument, the name of a column (or a list of columns) to plot"
+                        .to_string(),
+                )
+            }
+        };
+
+        let kind = match args.get("kind").and_then(|kind| kind.as_str()) {
+            None | Some("line") => ChartKind::Line,
+            Some("bar") => ChartKind::Bar,
+            Some(other) => {
+                return Err(format!(
+                    "Unknown chart kind `{}`, expected `line` or `bar`",
+                    other
+                ))
+            }
+        };
+
+        let x_labels = data
+            .iter()
+            .map(|row| match row.get(x_key) {
+                Some(Value::String(label)) => label.clone(),
+                Some(Value::Null) | None => "".to_string(),
+                Some(other) => other.to_string(),
+            })
+            .collect();
+
+        let series = y_keys
+            .into_iter()
+            .map(|y_key| ChartSeries {
+                values: data
+                    .iter()
+                    .map(|row| row.get(&y_key).and_then(as_number))
+                    .collect(),
+                name: y_key,
+            })
+            .collect();
+
+        let size = |name: &str, default: u32| {
+            args.get(name)
+                .and_then(|size| size.as_u64())
+                .map(|size| size.clamp(MIN_SIZE as u64, MAX_SIZE as u64) as u32)
+                .unwrap_or(default)
+        };
+
+        Ok(ChartDefinition {
+            kind,
+            title: args
+                .get("title")
+                .and_then(|title| title.as_str())
+                .map(|title| title.to_string()),
+            x_labels,
+            series,
+            width: size("width", DEFAULT_WIDTH),
+            height: size("height", DEFAULT_HEIGHT),
+        })
+    }
+
+    fn y_range(&self) -> (f64, f64) {
+        let values = self
+            .series
+            .iter()
+            .flat_map(|series| series.values.iter().flatten());
+        let (mut min, mut max) = values.fold((f64::MAX, f64::MIN), |(min, max), value| {
+            (min.min(*value), max.max(*value))
+        });
+
+        if min > max {
+            // No values at all
+            return (0.0, 1.0);
+        }
+        if self.kind == ChartKind::Bar {
+            // Bars should always start from zero
+            min = min.min(0.0);
+            max = max.max(0.0);
+        }
+        if (max - min).abs() < f64::EPSILON {
+            return (min - 1.0, max + 1.0);
+        }
+        let padding = (max - min) * 0.05;
+        (if min == 0.0 { min } else { min - padding }, max + padding)
+    }
+}
+
+pub fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Draws the chart and returns it as a PNG image
+pub fn render_chart_png(chart: &ChartDefinition) -> Result<Vec<u8>, String> {
+    REGISTER_FONT.call_once(|| {
+        if register_font(CHART_FONT, FontStyle::Normal, FONT_DATA).is_err() {
+            log::error!("Unable to load the chart font");
+        }
+    });
+
+    let (width, height) = (
+        chart.width.clamp(MIN_SIZE, MAX_SIZE),
+        chart.height.clamp(MIN_SIZE, MAX_SIZE),
+    );
+    let mut buffer = vec![0u8; (width * height * 3) as usize];
+    draw_chart(chart, (width, height), &mut buffer)
+        .map_err(|e| format!("Unable to draw chart: {}", e))?;
+
+    let mut png = vec![];
+    PngEncoder::new(&mut png)
+        .write_image(&buffer, width, height, ColorType::Rgb8)
+        .map_err(|e| format!("Unable to encode chart as png: {}", e))?;
+    Ok(png)
+}
+
+fn draw_chart(
+    chart: &ChartDefinition,
+    size: (u32, u32),
+    buffer: &mut [u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let root = BitMapBackend::with_buffer(buffer, size).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let point_count = chart.x_labels.len();
+    let (y_min, y_max) = chart.y_range();
+
+    let mut builder = ChartBuilder::on(&root);
+    builder
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(60);
+    if let Some(title) = &chart.title {
+        builder.caption(title, (CHART_FONT, 22));
+    }
+    let mut chart_context =
+        builder.build_cartesian_2d(-0.5f64..(point_count as f64 - 0.5), y_min..y_max)?;
+
+    // The x axis is the row index, labelled with the value from the x column
+    let x_label_formatter = |x: &f64| {
+        let index = x.round();
+        if (x - index).abs() > 0.001 || index < 0.0 {
+            return "".to_string();
+        }
+        chart
+            .x_labels
+            .get(index as usize)
+            .cloned()
+            .unwrap_or_default()
+    };
+    chart_context
+        .configure_mesh()
+        .disable_x_mesh()
+        .max_light_lines(0)
+        .x_labels(point_count.clamp(1, 12))
+        .x_label_formatter(&x_label_formatter)
+        .label_style((CHART_FONT, 14))
+        .draw()?;
+
+    let series_count = chart.series.len().max(1);
+    for (series_index, series) in chart.series.iter().enumerate() {
+        let colour = SERIES_COLOURS[series_index % SERIES_COLOURS.len()];
+
+        let drawn_series = match chart.kind {
+            ChartKind::Line => {
+                let points = series
+                    .values
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, value)| value.map(|value| (index as f64, value)));
+                chart_context.draw_series(LineSeries::new(points, colour.stroke_width(2)))?
+            }
+            ChartKind::Bar => {
+                // Bars for each series sit side by side within the slot for each x value
+                let bar_width = 0.8 / series_count as f64;
+                let bars = series
+                    .values
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, value)| value.map(|value| (index, value)))
+                    .map(|(index, value)| {
+                        let left = index as f64 - 0.4 + bar_width * series_index as f64;
+                        let base = 0f64.max(y_min);
+                        Rectangle::new([(left, base), (left + bar_width, value)], colour.filled())
+                    });
+                chart_context.draw_series(bars)?
+            }
+        };
+
+        if chart.series.len() > 1 {
+            drawn_series
+                .label(series.name.clone())
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], colour.filled())
+                });
+        }
+    }
+
+    if chart.series.len() > 1 {
+        chart_context
+            .configure_series_labels()
+            .label_font((CHART_FONT, 14))
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+    }
+
+    root.present()?;
+    Ok(())
+}
+
+/// Adds a chart of a query result to the template, as a markdown image of the chart definition.
+/// The image itself is drawn when the message is sent, see `extract_chart_images`.
+///
+/// Usage: `{{ chart(data=temperatures, x="date", y="temperature", kind="line", title="Temperature") }}`
+/// `y` can also be a list of columns to draw several series on the same chart.
+/// An empty `data` array renders nothing.
+struct ChartFunction;
+
+impl Function for ChartFunction {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let chart = ChartDefinition::from_args(args).map_err(tera::Error::msg)?;
+        if chart.x_labels.is_empty() {
+            return Ok(Value::String("".to_string()));
+        }
+
+        let definition = serde_json::to_vec(&chart)
+            .map_err(|e| tera::Error::msg(format!("Unable to store chart: {}", e)))?;
+        let alt_text = chart
+            .title
+            .as_deref()
+            .unwrap_or("Chart")
+            .replace(['[', ']'], "");
+
+        Ok(Value::String(format!(
+            "![{}]({}{})",
+            alt_text,
+            CHART_URI_PREFIX,
+            BASE64.encode(definition)
+        )))
+    }
+
+    fn is_safe(&self) -> bool {
+        // The chart definition must not be html escaped
+        true
+    }
+}
+
+pub fn register_chart_function(tera: &mut Tera) {
+    tera.register_function("chart", ChartFunction);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartImage {
+    pub alt_text: String,
+    pub png: Vec<u8>,
+}
+
+fn chart_image_regex() -> &'static Regex {
+    static CHART_IMAGE_REGEX: OnceLock<Regex> = OnceLock::new();
+    CHART_IMAGE_REGEX.get_or_init(|| {
+        Regex::new(r"!\[([^\]]*)\]\(chart:([A-Za-z0-9+/=]+)\)")
+            .expect("Chart image regex should be valid")
+    })
+}
+
+fn decode_chart_definition(encoded: &str) -> Result<ChartDefinition, String> {
+    let definition = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("Unable to decode chart: {}", e))?;
+    serde_json::from_slice(&definition).map_err(|e| format!("Unable to read chart: {}", e))
+}
+
+/// Replaces each chart in a message with the result of `replace_with`, which is passed the chart index and what `prepare` returns for it.
+/// Charts that can't be prepared are logged and left in the message as they are.
+fn replace_charts<T>(
+    message: &str,
+    prepare: impl Fn(&str, ChartDefinition) -> Result<T, String>,
+    replace_with: impl Fn(usize, &T) -> String,
+) -> (String, Vec<T>) {
+    let mut charts = vec![];
+    let mut result = String::new();
+    let mut last_end = 0;
+
+    for captures in chart_image_regex().captures_iter(message) {
+        let whole_match = captures
+            .get(0)
+            .expect("Capture 0 is always the whole match");
+        let chart = match decode_chart_definition(&captures[2])
+            .and_then(|definition| prepare(&captures[1], definition))
+        {
+            Ok(chart) => chart,
+            Err(e) => {
+                log::error!("{}", e);
+                continue;
+            }
+        };
+
+        result.push_str(&message[last_end..whole_match.start()]);
+        result.push_str(&replace_with(charts.len(), &chart));
+        last_end = whole_match.end();
+        charts.push(chart);
+    }
+    result.push_str(&message[last_end..]);
+
+    (result, charts)
+}
+
+/// Finds the charts in a message and draws them, so they can be sent in the right way for each channel.
+/// Each chart is replaced in the message with the result of `replace_with`, which is passed the chart index and image.
+pub fn extract_chart_images(
+    message: &str,
+    replace_with: impl Fn(usize, &ChartImage) -> String,
+) -> (String, Vec<ChartImage>) {
+    replace_charts(
+        message,
+        |alt_text, definition| {
+            Ok(ChartImage {
+                alt_text: alt_text.to_string(),
+                png: render_chart_png(&definition)?,
+            })
+        },
+        replace_with,
+    )
+}
+
+/// Replaces each chart in a message with the result of `replace_with`, which is passed the chart's alt text.
+/// Unlike `extract_chart_images` the charts aren't drawn.
+pub fn replace_charts_with_text(message: &str, replace_with: impl Fn(&str) -> String) -> String {
+    replace_charts(
+        message,
+        |alt_text, _| Ok(alt_text.to_string()),
+        |_, alt_text| replace_with(alt_text),
+    )
+    .0
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use tera::Context;
+
+    use super::*;
+
+    fn temperature_data() -> Value {
+        json!([
+            {"date": "2024-03-01", "temperature": 4.5, "limit": 8},
+            {"date": "2024-03-02", "temperature": "5.1", "limit": 8},
+            {"date": "2024-03-03", "temperature": null, "limit": 8},
+            {"date": "2024-03-04", "temperature": 9.2, "limit": 8}
+        ])
+    }
+
+    #[test]
+    fn test_chart_definition_from_args() {
+        let args: HashMap<String, Value> = serde_json::from_value(json!({
+            "data": temperature_data(),
+            "x": "date",
+            "y": ["temperature", "limit"],
+            "kind": "bar",
+            "width": 5000
+        }))
+        .unwrap();
+
+        let chart = ChartDefinition::from_args(&args).unwrap();
+        assert_eq!(chart.kind, ChartKind::Bar);
+        assert_eq!(chart.x_labels[0], "2024-03-01");
+        assert_eq!(chart.series.len(), 2);
+        assert_eq!(
+            chart.series[0].values,
+            vec![Some(4.5), Some(5.1), None, Some(9.2)]
+        );
+        assert_eq!(chart.width, MAX_SIZE);
+        assert_eq!(chart.height, DEFAULT_HEIGHT);
+
+        // Missing arguments
+        let args: HashMap<String, Value> =
+            serde_json::from_value(json!({ "data": temperature_data(), "x": "date" })).unwrap();
+        assert!(ChartDefinition::from_args(&args).is_err());
+
+        let args: HashMap<String, Value> = serde_json::from_value(json!({
+            "data": temperature_data(),
+            "x": "date",
+            "y": "temperature",
+            "kind": "pie"
+        }))
+        .unwrap();
+        assert!(ChartDefinition::from_args(&args).is_err());
+    }
+
+    #[test]
+    fn test_chart_function_renders_png() {
+        let mut tera = Tera::default();
+        register_chart_function(&mut tera);
+        // .html templates are auto escaped, the chart should still be a valid image
+        tera.add_raw_template(
+            "body.html",
+            "Temperatures\n\n{{ chart(data=temperatures, x=\"date\", y=\"temperature\", title=\"Fridge [1]\") }}\n\nEnd",
+        )
+        .unwrap();
+
+        let context = Context::from_value(json!({ "temperatures": temperature_data() })).unwrap();
+        let message = tera.render("body.html", &context).unwrap();
+
+        let (text, images) = extract_chart_images(&message, |index, image| {
+            format!("[{} {}]", image.alt_text, index)
+        });
+        assert_eq!(text, "Temperatures\n\n[Fridge 1 0]\n\nEnd");
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].alt_text, "Fridge 1");
+        assert_eq!(&images[0].png[1..4], b"PNG");
+
+        // Only the chart definition is stored in the message, not the image
+        assert!(!message.contains("data:image/png"));
+        assert_eq!(
+            replace_charts_with_text(&message, |alt_text| format!("[{}]", alt_text)),
+            "Temperatures\n\n[Fridge 1]\n\nEnd"
+        );
+    }
+
+    #[test]
+    fn test_chart_function_empty_data() {
+        let mut tera = Tera::default();
+        register_chart_function(&mut tera);
+        tera.add_raw_template(
+            "body",
+            "{{ chart(data=[], x=\"date\", y=\"temperature\") }}",
+        )
+        .unwrap();
+
+        let message = tera.render("body", &Context::new()).unwrap();
+        assert_eq!(message, "");
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::Mutex;
+
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+/// Used to wake the notification dispatcher as soon as new notification events are queued,
+/// rather than waiting for its next sweep.
+pub struct NotificationDispatchTrigger {
+    sender: Sender<()>,
+    receiver: Mutex<Option<Receiver<()>>>,
+}
+
+impl NotificationDispatchTrigger {
+    pub fn new() -> Self {
+        // Only one pending wake up is needed, the dispatcher sends everything that is queued each time
+        let (sender, receiver) = channel(1);
+        NotificationDispatchTrigger {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    /// Let the dispatcher know there are notifications to send
+    pub fn notify(&self) {
+        // If the channel is full the dispatcher is already due to run, so there is nothing more to do
+        let _ = self.sender.try_send(());
+    }
+
+    /// The receiver can only be taken once, by the dispatcher
+    pub fn take_receiver(&self) -> Option<Receiver<()>> {
+        match self.receiver.lock() {
+            Ok(mut receiver) => receiver.take(),
+            Err(_) => None,
+        }
+    }
+}
+
+impl Default for NotificationDispatchTrigger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_notification_dispatch_trigger() {
+        let trigger = NotificationDispatchTrigger::new();
+        let mut receiver = trigger.take_receiver().unwrap();
+        assert!(trigger.take_receiver().is_none());
+
+        // Nothing queued yet
+        assert!(receiver.try_recv().is_err());
+
+        // Several notifies before the dispatcher runs only wake it once
+        trigger.notify();
+        trigger.notify();
+        assert!(receiver.try_recv().is_ok());
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use nanohtml2text::html2text;
+use tera::Context;
+
+use crate::email::send::InlineImage;
+use crate::notification_template::load::notification_tera;
+use crate::service_provider::ServiceContext;
+
+use super::chart;
+
+/// The template notification emails are wrapped in, it can be replaced by a stored template with the same name
+pub static EMAIL_LAYOUT_TEMPLATE: &str = "email/layout.html";
+
+/// Styles added to the html generated from markdown, as many email clients ignore `<style>` tags
+static ELEMENT_STYLES: [(&str, &str); 12] = [
+    (
+        "h1",
+        "margin: 0 0 16px; font-size: 22px; line-height: 130%;",
+    ),
+    (
+        "h2",
+        "margin: 16px 0 12px; font-size: 18px; line-height: 130%;",
+    ),
+    (
+        "h3",
+        "margin: 16px 0 8px; font-size: 16px; line-height: 130%;",
+    ),
+    ("p", "margin: 0 0 12px;"),
+    ("ul", "margin: 0 0 12px; padding-left: 24px;"),
+    ("ol", "margin: 0 0 12px; padding-left: 24px;"),
+    (
+        "blockquote",
+        "margin: 0 0 12px; padding-left: 12px; border-left: 4px solid #e4e4eb; color: #555770;",
+    ),
+    (
+        "pre",
+        "margin: 0 0 12px; padding: 8px; background-color: #f2f2f5; white-space: pre-wrap;",
+    ),
+    (
+        "table",
+        "margin: 0 0 12px; border-collapse: collapse; font-size: 13px;",
+    ),
+    (
+        "th",
+        "padding: 4px 8px; border: 1px solid #e4e4eb; background-color: #f2f2f5; text-align: left;",
+    ),
+    ("td", "padding: 4px 8px; border: 1px solid #e4e4eb;"),
+    ("img", "max-width: 100%;"),
+];
+
+#[derive(Debug)]
+pub struct NotificationEmail {
+    pub html_body: String,
+    pub text_body: String,
+    pub inline_images: Vec<InlineImage>,
+}
+
+/// Converts a notification's markdown message to the html and text parts of an email.
+/// The html is wrapped in the email layout, and any charts are attached as inline images.
+pub fn render_notification_email(
+    ctx: &ServiceContext,
+    title: &str,
+    message: &str,
+) -> NotificationEmail {
+    // Charts are attached as inline images, and referenced by their content id in the html
+    let (markdown, charts) = chart::extract_chart_images(message, |index, image| {
+        format!("![{}](cid:chart_{})", image.alt_text, index)
+    });
+    let text_markdown =
+        chart::replace_charts_with_text(message, |alt_text| format!("[{}]", alt_text));
+    let inline_images = charts
+        .into_iter()
+        .enumerate()
+        .map(|(index, image)| InlineImage {
+            content_id: format!("chart_{}", index),
+            png: image.png,
+        })
+        .collect();
+
+    let content = add_inline_styles(&markdown_to_html(&markdown));
+    let html_body = match render_layout(ctx, title, &content) {
+        Ok(html) => html,
+        Err(e) => {
+            // Better to send the notification without the layout than not at all
+            log::error!("Unable to render the notification email layout: {:?}", e);
+            content
+        }
+    };
+
+    NotificationEmail {
+        html_body,
+        text_body: html2text(&markdown_to_html(&text_markdown)),
+        inline_images,
+    }
+}
+
+fn markdown_to_html(markdown: &str) -> String {
+    let mut options = pulldown_cmark::Options::empty();
+    options.insert(pulldown_cmark::Options::ENABLE_TABLES);
+    options.insert(pulldown_cmark::Options::ENABLE_STRIKETHROUGH);
+    let parser = pulldown_cmark::Parser::new_ext(markdown, options);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    html
+}
+
+/// Adds the element styles to the tags generated by pulldown_cmark.
+/// Tags that already have attributes (other than images) are left as they are, e.g. aligned table cells.
+fn add_inline_styles(html: &str) -> String {
+    let mut html = html.to_string();
+    for (tag, style) in ELEMENT_STYLES.iter() {
+        let (from, to) = match *tag {
+            "img" => ("<img ".to_string(), format!("<img style=\"{}\" ", style)),
+            _ => (
+                format!("<{}>", tag),
+                format!("<{} style=\"{}\">", tag, style),
+            ),
+        };
+        html = html.replace(&from, &to);
+    }
+    html
+}
+
+fn render_layout(ctx: &ServiceContext, title: &str, content: &str) -> Result<String, tera::Error> {
+    let settings = &ctx.service_provider.settings;
+    let mut context = Context::new();
+    context.insert("title", title);
+    context.insert("content", content);
+    context.insert("app_url", &settings.server.app_url);
+    context.insert("logo_url", &settings.mail.logo_url);
+
+    notification_tera(ctx)?.render(EMAIL_LAYOUT_TEMPLATE, &context)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use repository::{mock::MockDataInserts, test_db::setup_all};
+
+    use crate::{
+        notification_template::create::CreateNotificationTemplate,
+        service_provider::{ServiceContext, ServiceProvider},
+        test_utils::get_test_settings,
+    };
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_render_notification_email() {
+        let (_, _, connection_manager, _) =
+            setup_all("test_render_notification_email", MockDataInserts::none()).await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        let message = "# Stock Report\n\nClinic A is **low** on stock\n\n| Item | Stock |\n|---|---|\n| Paracetamol | 3 |\n";
+        let email = render_notification_email(&context, "Stock <Report>", message);
+
+        // The message is wrapped in the layout, with inline styles
+        assert!(email
+            .html_body
+            .contains("<title>Stock &lt;Report&gt;</title>"));
+        assert!(email.html_body.contains(
+            r#"<h1 style="margin: 0 0 16px; font-size: 22px; line-height: 130%;">Stock Report</h1>"#
+        ));
+        assert!(email.html_body.contains("<strong>low</strong>"));
+        assert!(email.html_body.contains("<td style="));
+        assert!(email.html_body.contains(r#"href="http://localhost:8007""#));
+        assert!(email.inline_images.is_empty());
+
+        // The text part doesn't have any markdown or html
+        assert!(email.text_body.contains("Stock Report"));
+        assert!(email.text_body.contains("Clinic A is low on stock"));
+        assert!(email.text_body.contains("Paracetamol"));
+        assert!(!email.text_body.contains("**"));
+        assert!(!email.text_body.contains("<"));
+
+        // A stored layout is used instead of the built in one
+        context
+            .service_provider
+            .notification_template_service
+            .create_notification_template(
+                &context,
+                CreateNotificationTemplate {
+                    id: "layout".to_string(),
+                    name: EMAIL_LAYOUT_TEMPLATE.to_string(),
+                    description: None,
+                    template: "<div class=\"branded\">{{ content | safe }}</div>".to_string(),
+                },
+            )
+            .unwrap();
+        let email = render_notification_email(&context, "Stock Report", "Hello");
+        assert_eq!(
+            email.html_body,
+            r#"<div class="branded"><p style="margin: 0 0 12px;">Hello</p>
+</div>"#
+        );
+    }
+}
@@ -1,23 +1,29 @@
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use repository::{
     NotificationEventRow, NotificationEventRowRepository, NotificationEventStatus,
-    NotificationType, RecipientRow,
+    NotificationPriority, NotificationType, RecipientRow,
 };
 use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use tera::{Context, Error, Tera};
-use util::uuid::uuid;
+use util::{hash::sha256, uuid::uuid};
 
-use crate::service_provider::ServiceContext;
+use crate::{notification_template::load::notification_tera, service_provider::ServiceContext};
 
-use super::NotificationServiceError;
+use super::{locale::localised_template_name, NotificationServiceError};
 
 // This struct is intended to be able to be created by a plugin from a datasource, and defines what a template can expect from a recipient
 // Often it will be derived RecipientRow which is why we implement From<RecipientRow> for NotificationRecipient
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, PartialEq, Default)]
 pub struct NotificationTarget {
     pub name: String,
     pub to_address: String,
     pub notification_type: NotificationType,
+    /// Language tag e.g. `fr`, templates are translated if there is a version for this locale e.g. `coldchain/temperature.fr.md`
+    pub locale: Option<String>,
+    /// Any extra columns returned by a SQL recipient list, available in templates as `recipient.<column>`
+    #[serde(flatten)]
+    pub extra_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 impl From<RecipientRow> for NotificationTarget {
@@ -26,6 +32,8 @@ impl From<RecipientRow> for NotificationTarget {
             name: recipient.name,
             notification_type: recipient.notification_type.into(),
             to_address: recipient.to_address,
+            locale: recipient.locale,
+            ..Default::default()
         }
     }
 }
@@ -36,60 +44,153 @@ pub enum TemplateDefinition {
     Template(String),
 }
 
+/// Templates used instead of the notification's `title_template` or `body_template` for one type of recipient,
+/// e.g. a detailed email and a shorter telegram message
+#[derive(Debug, Default)]
+pub struct ChannelTemplates {
+    pub title_template: Option<TemplateDefinition>,
+    pub body_template: Option<TemplateDefinition>,
+}
+
 #[derive(Debug)]
 pub struct NotificationContext {
     pub title_template: Option<TemplateDefinition>,
     pub body_template: TemplateDefinition,
+    /// Any recipient types that don't have their own templates use `title_template` and `body_template`
+    pub channel_templates: HashMap<NotificationType, ChannelTemplates>,
     pub recipients: Vec<NotificationTarget>,
     pub template_data: serde_json::Value,
+    /// Notifications with the same key are only sent once to each recipient within the dedup window.
+    /// If not set, notifications with the same title and body are treated as the same.
+    pub dedup_key: Option<String>,
+    /// Critical notifications are sent even during the recipient's quiet hours
+    pub priority: NotificationPriority,
 }
 
+/// Returns the created events, including any that failed to render or were deduplicated
 pub fn create_notification_events(
     ctx: &ServiceContext,
     config_id: Option<String>,
     notification: NotificationContext,
-) -> Result<(), NotificationServiceError> {
+) -> Result<Vec<NotificationEventRow>, NotificationServiceError> {
     let repo = NotificationEventRowRepository::new(&ctx.connection);
+    let dedup_key = notification.dedup_key.clone();
+
+    let mut notification_event_rows = render_notification_events(ctx, &config_id, notification)
+        .map_err(|e| create_failed_event_row(e, &config_id, ctx))?;
+
+    let window_minutes = ctx.service_provider.settings.dedup.window_minutes;
+    let dedup_since = Utc::now().naive_utc() - Duration::minutes(window_minutes as i64);
+
+    for notification_event_row in notification_event_rows.iter_mut() {
+        let event_dedup_key = event_dedup_key(&dedup_key, notification_event_row);
+
+        if window_minutes > 0 && notification_event_row.status == NotificationEventStatus::Queued {
+            let duplicate_of = repo
+                .find_recent_by_dedup_key(&event_dedup_key, dedup_since)
+                .map_err(NotificationServiceError::DatabaseError)?;
+            if let Some(duplicate_of) = duplicate_of {
+                log::info!(
+                    "Not sending notification to {} as it is a duplicate of {}",
+                    notification_event_row.to_address,
+                    duplicate_of.id
+                );
+                notification_event_row.status = NotificationEventStatus::Deduplicated;
+                notification_event_row.error_message =
+                    Some(format!("Duplicate of notification {}", duplicate_of.id));
+            }
+        }
+
+        notification_event_row.dedup_key = Some(event_dedup_key);
+        repo.insert_one(notification_event_row)
+            .map_err(NotificationServiceError::DatabaseError)?;
+    }
+
+    ctx.service_provider.notification_dispatch.notify();
+
+    Ok(notification_event_rows)
+}
+
+/// Identifies notifications that are the same, so they aren't sent to a recipient more than once
+fn event_dedup_key(dedup_key: &Option<String>, row: &NotificationEventRow) -> String {
+    let content = match dedup_key {
+        Some(dedup_key) => dedup_key.clone(),
+        None => format!("{}\n{}", row.title.clone().unwrap_or_default(), row.message),
+    };
+    sha256(&format!(
+        "{:?}\n{}\n{}",
+        row.notification_type, row.to_address, content
+    ))
+}
+
+/// Renders the notification for each recipient, without saving anything to the database.
+/// If a template fails to render for a recipient, the row is returned with a `Failed` status.
+/// An error is only returned if the templates can't be compiled at all.
+pub fn render_notification_events(
+    ctx: &ServiceContext,
+    config_id: &Option<String>,
+    notification: NotificationContext,
+) -> Result<Vec<NotificationEventRow>, Error> {
+    let mut notification_event_rows = vec![];
 
     // Dedup recipients by to_address
     let mut recipients = notification.recipients.clone();
     recipients.sort_by(|a, b| a.to_address.cmp(&b.to_address));
     recipients.dedup_by(|a, b| (a.to_address == b.to_address));
 
-    // Create a tera instance for this notification
-    let mut tera = Tera::default();
-
-    // Add any configured templates to our new tera instance
-    tera.extend(ctx.service_provider.notification_service.tera())
-        .map_err(|e| create_failed_event_row(e, &config_id, ctx))?;
+    // Create a tera instance for this notification, with the file and database templates
+    let mut tera = notification_tera(ctx)?;
 
     let title_template_name = match &notification.title_template {
-        Some(TemplateDefinition::TemplateName(title_template_name)) => title_template_name.clone(),
-        Some(TemplateDefinition::Template(title_template)) => {
-            // Add the title template to the tera instance
-            tera.add_raw_template("title_template", &title_template)
-                .map_err(|e| create_failed_event_row(e, &config_id, ctx))?;
-            "title_template".to_string()
-        }
+        Some(title_template) => add_template(&mut tera, title_template, "title_template")?,
         None => "default/title.md".to_string(),
     };
+    let body_template_name = add_template(&mut tera, &notification.body_template, "body_template")?;
+
+    // The title and body template names to use for each type of recipient that has its own templates
+    let mut channel_template_names = HashMap::new();
+    for (notification_type, templates) in &notification.channel_templates {
+        let title = match &templates.title_template {
+            Some(title_template) => add_template(
+                &mut tera,
+                title_template,
+                &format!("title_template_{:?}", notification_type),
+            )?,
+            None => title_template_name.clone(),
+        };
+        let body = match &templates.body_template {
+            Some(body_template) => add_template(
+                &mut tera,
+                body_template,
+                &format!("body_template_{:?}", notification_type),
+            )?,
+            None => body_template_name.clone(),
+        };
+        channel_template_names.insert(notification_type.clone(), (title, body));
+    }
 
-    let body_template_name = match &notification.body_template {
-        TemplateDefinition::TemplateName(body_template_name) => body_template_name.clone(),
-        TemplateDefinition::Template(body_template) => {
-            // Add the body template to the tera instance
-            tera.add_raw_template("body_template", &body_template)
-                .map_err(|e| create_failed_event_row(e, &config_id, ctx))?;
-            "body_template".to_string()
-        }
-    };
+    // Used to find the translations of the templates for each recipient's locale
+    let template_names: HashSet<String> = tera
+        .get_template_names()
+        .map(|name| name.to_string())
+        .collect();
 
-    let mut tera_context = Context::from_value(notification.template_data)
-        .map_err(|e| create_failed_event_row(e, &config_id, ctx))?;
+    let mut tera_context = Context::from_value(notification.template_data)?;
 
     // Loop through recipients and create a notification for each
     for recipient in recipients {
         let notification_type = recipient.notification_type.clone();
+        let (recipient_title_template, recipient_body_template) = channel_template_names
+            .get(&notification_type)
+            .cloned()
+            .unwrap_or_else(|| (title_template_name.clone(), body_template_name.clone()));
+        let recipient_title_template = localised_template_name(
+            &template_names,
+            &recipient_title_template,
+            &recipient.locale,
+        );
+        let recipient_body_template =
+            localised_template_name(&template_names, &recipient_body_template, &recipient.locale);
 
         // Replace the recipient data in the template context
         tera_context.insert("recipient", &recipient);
@@ -105,6 +206,7 @@ pub fn create_notification_events(
             notification_config_id: config_id.clone(),
             notification_type,
             retry_at: None,
+            priority: notification.priority.clone(),
             context: match serde_json::to_string(&tera_context.clone().into_json()) {
                 Ok(context) => Some(context),
                 Err(e) => {
@@ -115,7 +217,7 @@ pub fn create_notification_events(
             ..Default::default()
         };
 
-        let base_row_with_title = match tera.render(&title_template_name, &tera_context) {
+        let base_row_with_title = match tera.render(&recipient_title_template, &tera_context) {
             Ok(title) => NotificationEventRow {
                 title: Some(title),
                 ..base_row
@@ -130,7 +232,7 @@ pub fn create_notification_events(
             }
         };
 
-        let notification_queue_row = match tera.render(&body_template_name, &tera_context) {
+        let notification_queue_row = match tera.render(&recipient_body_template, &tera_context) {
             Ok(body) => NotificationEventRow {
                 message: body,
                 ..base_row_with_title
@@ -145,13 +247,25 @@ pub fn create_notification_events(
             }
         };
 
-        repo.insert_one(&notification_queue_row)
-            .map_err(|e| NotificationServiceError::DatabaseError(e))?;
-
-        // TODO: trigger async notification send?
+        notification_event_rows.push(notification_queue_row);
     }
 
-    Ok(())
+    Ok(notification_event_rows)
+}
+
+/// Adds the template to tera if it is defined inline, returns the name to render it with
+fn add_template(
+    tera: &mut Tera,
+    template: &TemplateDefinition,
+    inline_template_name: &str,
+) -> Result<String, Error> {
+    match template {
+        TemplateDefinition::TemplateName(template_name) => Ok(template_name.clone()),
+        TemplateDefinition::Template(template) => {
+            tera.add_raw_template(inline_template_name, template)?;
+            Ok(inline_template_name.to_string())
+        }
+    }
 }
 
 fn create_failed_event_row(
@@ -184,15 +298,18 @@ fn create_failed_event_row(
 
 #[cfg(test)]
 mod test {
-    use std::sync::Arc;
+    use std::{collections::HashMap, sync::Arc};
 
     use repository::{
-        mock::MockDataInserts, test_db::setup_all, NotificationEventRowRepository, NotificationType,
+        mock::MockDataInserts, test_db::setup_all, NotificationEventFilter,
+        NotificationEventRepository, NotificationEventRowRepository, NotificationEventStatus,
+        NotificationPriority, NotificationType,
     };
 
     use crate::{
         notification::enqueue::{
-            create_notification_events, NotificationContext, NotificationTarget, TemplateDefinition,
+            create_notification_events, render_notification_events, ChannelTemplates,
+            NotificationContext, NotificationTarget, TemplateDefinition,
         },
         service_provider::{ServiceContext, ServiceProvider},
         test_utils::get_test_settings,
@@ -228,14 +345,19 @@ mod test {
                         name: "test".to_string(),
                         to_address: "test@example.com".to_string(),
                         notification_type: NotificationType::Email,
+                        ..Default::default()
                     },
                     NotificationTarget {
                         name: "test2".to_string(),
                         to_address: "test@example.com".to_string(),
                         notification_type: NotificationType::Email,
+                        ..Default::default()
                     },
                 ],
                 template_data: serde_json::json!({}),
+                dedup_key: None,
+                priority: NotificationPriority::Normal,
+                channel_templates: HashMap::new(),
             },
         );
 
@@ -281,14 +403,19 @@ mod test {
                         name: "telegram".to_string(),
                         to_address: "-12345".to_string(),
                         notification_type: NotificationType::Telegram,
+                        ..Default::default()
                     },
                     NotificationTarget {
                         name: "telegram2".to_string(),
                         to_address: "-12345".to_string(),
                         notification_type: NotificationType::Telegram,
+                        ..Default::default()
                     },
                 ],
                 template_data: serde_json::json!({}),
+                dedup_key: None,
+                priority: NotificationPriority::Normal,
+                channel_templates: HashMap::new(),
             },
         );
 
@@ -323,6 +450,9 @@ mod test {
                 body_template: TemplateDefinition::Template("{{bad_template}".to_string()),
                 recipients: vec![],
                 template_data: serde_json::json!({}),
+                dedup_key: None,
+                priority: NotificationPriority::Normal,
+                channel_templates: HashMap::new(),
             },
         );
 
@@ -340,4 +470,250 @@ mod test {
         );
         assert_ne!(notification_event_rows[0].error_message, None);
     }
+
+    #[actix_rt::test]
+    async fn test_create_notification_events_dedup() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_create_notification_events_dedup",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let connection = connection_manager.connection().unwrap();
+        let mut settings = get_test_settings("");
+        settings.dedup.window_minutes = 10;
+        let service_provider = Arc::new(ServiceProvider::new(connection_manager, settings));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        let notification =
+            |to_address: &str, body: &str, dedup_key: Option<&str>| NotificationContext {
+                title_template: None,
+                body_template: TemplateDefinition::Template(body.to_string()),
+                recipients: vec![NotificationTarget {
+                    name: "test".to_string(),
+                    to_address: to_address.to_string(),
+                    notification_type: NotificationType::Email,
+                    ..Default::default()
+                }],
+                template_data: serde_json::json!({}),
+                dedup_key: dedup_key.map(|key| key.to_string()),
+                priority: NotificationPriority::Normal,
+                channel_templates: HashMap::new(),
+            };
+
+        // e.g. the same alert from two configs
+        create_notification_events(
+            &context,
+            Some("config_a".to_string()),
+            notification("a@example.com", "Fridge is too hot", None),
+        )
+        .unwrap();
+        create_notification_events(
+            &context,
+            Some("config_b".to_string()),
+            notification("a@example.com", "Fridge is too hot", None),
+        )
+        .unwrap();
+        // Different recipient or message
+        create_notification_events(
+            &context,
+            None,
+            notification("b@example.com", "Fridge is too hot", None),
+        )
+        .unwrap();
+        create_notification_events(
+            &context,
+            None,
+            notification("a@example.com", "Fridge is too cold", None),
+        )
+        .unwrap();
+        // The same key, even though the message is different
+        create_notification_events(
+            &context,
+            None,
+            notification("c@example.com", "Fridge is 9 degrees", Some("fridge_1")),
+        )
+        .unwrap();
+        create_notification_events(
+            &context,
+            None,
+            notification("c@example.com", "Fridge is 10 degrees", Some("fridge_1")),
+        )
+        .unwrap();
+
+        let repo = NotificationEventRowRepository::new(&connection);
+        let un_sent = repo.un_sent().unwrap();
+        assert_eq!(un_sent.len(), 4);
+
+        let deduplicated: Vec<_> = NotificationEventRepository::new(&connection)
+            .query_by_filter(NotificationEventFilter::new())
+            .unwrap()
+            .into_iter()
+            .filter(|event| event.status == NotificationEventStatus::Deduplicated)
+            .collect();
+        assert_eq!(deduplicated.len(), 2);
+        let config_b = deduplicated
+            .iter()
+            .find(|event| event.notification_config_id == Some("config_b".to_string()))
+            .unwrap();
+        assert!(config_b
+            .error_message
+            .as_ref()
+            .unwrap()
+            .starts_with("Duplicate of notification"));
+    }
+
+    #[actix_rt::test]
+    async fn test_create_notification_events_channel_templates() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_create_notification_events_channel_templates",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let connection = connection_manager.connection().unwrap();
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        let recipient =
+            |to_address: &str, notification_type: NotificationType| NotificationTarget {
+                name: to_address.to_string(),
+                to_address: to_address.to_string(),
+                notification_type,
+                ..Default::default()
+            };
+        let mut channel_templates = HashMap::new();
+        channel_templates.insert(
+            NotificationType::Telegram,
+            ChannelTemplates {
+                title_template: None,
+                body_template: Some(TemplateDefinition::Template(
+                    "{{ stock }} units".to_string(),
+                )),
+            },
+        );
+
+        create_notification_events(
+            &context,
+            None,
+            NotificationContext {
+                title_template: Some(TemplateDefinition::Template("Stock report".to_string())),
+                body_template: TemplateDefinition::Template(
+                    "# Stock report\nThere are {{ stock }} units in stock".to_string(),
+                ),
+                channel_templates,
+                recipients: vec![
+                    recipient("a@example.com", NotificationType::Email),
+                    recipient("-12345", NotificationType::Telegram),
+                ],
+                template_data: serde_json::json!({ "stock": 42 }),
+                dedup_key: None,
+                priority: NotificationPriority::Normal,
+            },
+        )
+        .unwrap();
+
+        let events = NotificationEventRowRepository::new(&connection)
+            .un_sent()
+            .unwrap();
+        let email = events
+            .iter()
+            .find(|event| event.notification_type == NotificationType::Email)
+            .unwrap();
+        assert_eq!(email.message, "# Stock report\nThere are 42 units in stock");
+        let telegram = events
+            .iter()
+            .find(|event| event.notification_type == NotificationType::Telegram)
+            .unwrap();
+        assert_eq!(telegram.message, "42 units");
+        // No title template for telegram, so the generic one is used
+        assert_eq!(telegram.title, Some("Stock report".to_string()));
+    }
+
+    #[actix_rt::test]
+    async fn test_render_notification_events_locale() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_render_notification_events_locale",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        let recipient = |to_address: &str, locale: Option<&str>| NotificationTarget {
+            name: to_address.to_string(),
+            to_address: to_address.to_string(),
+            locale: locale.map(|locale| locale.to_string()),
+            ..Default::default()
+        };
+
+        let events = render_notification_events(
+            &context,
+            &None,
+            NotificationContext {
+                title_template: Some(TemplateDefinition::TemplateName(
+                    "coldchain/no_data_title.md".to_string(),
+                )),
+                body_template: TemplateDefinition::TemplateName("coldchain/no_data.md".to_string()),
+                channel_templates: HashMap::new(),
+                recipients: vec![
+                    recipient("en@example.com", None),
+                    recipient("fr@example.com", Some("fr-CA")),
+                    recipient("lo@example.com", Some("lo")),
+                ],
+                template_data: serde_json::json!({
+                    "store_name": "Store A",
+                    "location_name": "Fridge 1",
+                    "sensor_name": "Sensor 1",
+                    "last_data_time": "2023-07-17T17:04:00",
+                    "data_age": "3 hours",
+                    "data_age_seconds": 10800,
+                    "reminder_number": 0
+                }),
+                dedup_key: None,
+                priority: NotificationPriority::Normal,
+            },
+        )
+        .unwrap();
+
+        let event = |to_address: &str| {
+            events
+                .iter()
+                .find(|event| event.to_address == to_address)
+                .unwrap()
+        };
+
+        // No locale uses the default template
+        assert_eq!(
+            event("en@example.com").title,
+            Some("No data received for Sensor 1\n".to_string())
+        );
+        assert!(event("en@example.com")
+            .message
+            .contains("**Last data received**: 3 hours ago"));
+
+        // The French template is used for any French locale
+        let french = event("fr@example.com");
+        assert_eq!(
+            french.title,
+            Some("Aucune donnée reçue pour Sensor 1\n".to_string())
+        );
+        assert!(french.message.contains("**Date** : 17 juillet 2023"));
+        assert!(french
+            .message
+            .contains("**Dernières données reçues** : il y a 3 heures"));
+
+        // Falls back to the default template if there isn't a translation
+        assert_eq!(
+            event("lo@example.com").title,
+            Some("No data received for Sensor 1\n".to_string())
+        );
+    }
 }
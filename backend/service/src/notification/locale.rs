@@ -0,0 +1,317 @@
+use std::collections::HashSet;
+
+/// The languages that system generated text, e.g. durations and dates, can be shown in.
+/// Templates can be translated into any language, see `localised_template_name`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    French,
+    Lao,
+}
+
+struct Translations {
+    /// Singular and plural of each unit, largest first
+    units: [(&'static str, &'static str); 4],
+    ago: &'static str,
+    in_future: &'static str,
+    just_now: &'static str,
+    thousands_separator: &'static str,
+    decimal_point: &'static str,
+    months: [&'static str; 12],
+}
+
+const ENGLISH: Translations = Translations {
+    units: [
+        ("day", "days"),
+        ("hour", "hours"),
+        ("minute", "minutes"),
+        ("second", "seconds"),
+    ],
+    ago: "{} ago",
+    in_future: "in {}",
+    just_now: "just now",
+    thousands_separator: ",",
+    decimal_point: ".",
+    months: [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ],
+};
+
+const FRENCH: Translations = Translations {
+    units: [
+        ("jour", "jours"),
+        ("heure", "heures"),
+        ("minute", "minutes"),
+        ("seconde", "secondes"),
+    ],
+    ago: "il y a {}",
+    in_future: "dans {}",
+    just_now: "à l'instant",
+    thousands_separator: "\u{202f}",
+    decimal_point: ",",
+    months: [
+        "janvier",
+        "février",
+        "mars",
+        "avril",
+        "mai",
+        "juin",
+        "juillet",
+        "août",
+        "septembre",
+        "octobre",
+        "novembre",
+        "décembre",
+    ],
+};
+
+const LAO: Translations = Translations {
+    units: [
+        ("ວັນ", "ວັນ"),
+        ("ຊົ່ວໂມງ", "ຊົ່ວໂມງ"),
+        ("ນາທີ", "ນາທີ"),
+        ("ວິນາທີ", "ວິນາທີ"),
+    ],
+    ago: "{}ທີ່ຜ່ານມາ",
+    in_future: "ໃນອີກ {}",
+    just_now: "ຫາກໍ່ນີ້",
+    thousands_separator: ".",
+    decimal_point: ",",
+    months: [
+        "ມັງກອນ",
+        "ກຸມພາ",
+        "ມີນາ",
+        "ເມສາ",
+        "ພຶດສະພາ",
+        "ມິຖຸນາ",
+        "ກໍລະກົດ",
+        "ສິງຫາ",
+        "ກັນຍາ",
+        "ຕຸລາ",
+        "ພະຈິກ",
+        "ທັນວາ",
+    ],
+};
+
+impl Locale {
+    /// Uses the language of a language tag, e.g. `fr-CA` is French. Anything else is shown in English.
+    pub fn from_tag(tag: Option<&str>) -> Locale {
+        match tag.and_then(|tag| locale_tags(tag).pop()).as_deref() {
+            Some("fr") => Locale::French,
+            Some("lo") => Locale::Lao,
+            _ => Locale::English,
+        }
+    }
+
+    fn translations(&self) -> &'static Translations {
+        match self {
+            Locale::English => &ENGLISH,
+            Locale::French => &FRENCH,
+            Locale::Lao => &LAO,
+        }
+    }
+
+    /// A number of seconds as a duration that's easy to read, e.g. `3 hours`.
+    /// Only the largest unit is shown, so 5400 seconds is `1 hour`.
+    pub fn duration(&self, seconds: i64) -> String {
+        let seconds = seconds.abs();
+        let [days, hours, minutes, secs] = self.translations().units;
+        let (count, (singular, plural)) = match seconds {
+            86_400.. => (seconds / 86_400, days),
+            3_600.. => (seconds / 3_600, hours),
+            60.. => (seconds / 60, minutes),
+            _ => (seconds, secs),
+        };
+        match count {
+            1 => format!("1 {}", singular),
+            _ => format!("{} {}", count, plural),
+        }
+    }
+
+    /// How long ago something happened, e.g. `3 hours ago`. Negative seconds are in the future, e.g. `in 3 hours`.
+    pub fn time_ago(&self, seconds: i64) -> String {
+        let translations = self.translations();
+        match seconds {
+            -59..=59 => translations.just_now.to_string(),
+            60.. => translations.ago.replace("{}", &self.duration(seconds)),
+            _ => translations
+                .in_future
+                .replace("{}", &self.duration(seconds)),
+        }
+    }
+
+    pub fn thousands_separator(&self) -> &'static str {
+        self.translations().thousands_separator
+    }
+
+    pub fn decimal_point(&self) -> &'static str {
+        self.translations().decimal_point
+    }
+
+    /// `month` is 1 to 12
+    pub fn month_name(&self, month: u32) -> &'static str {
+        self.translations().months[(month.clamp(1, 12) - 1) as usize]
+    }
+}
+
+/// Checks the locale is a language tag, e.g. `fr` or `fr-CA`
+pub fn check_locale(locale: &str) -> Result<(), String> {
+    let invalid = || {
+        format!(
+            "Invalid locale {}, expected a language code e.g. fr or fr-CA",
+            locale
+        )
+    };
+
+    let mut parts = locale.split(['-', '_']);
+    let language = parts.next().unwrap_or_default();
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(invalid());
+    }
+    for part in parts {
+        if !(2..=8).contains(&part.len()) || !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(invalid());
+        }
+    }
+    Ok(())
+}
+
+/// The tags to look for templates with, most specific first, e.g. `fr-CA` then `fr`
+pub fn locale_tags(locale: &str) -> Vec<String> {
+    let locale = locale.trim().replace('_', "-");
+    let language = locale.split('-').next().unwrap_or_default().to_lowercase();
+
+    let mut tags = vec![];
+    if locale.contains('-') {
+        tags.push(locale.clone());
+    }
+    if !language.is_empty() {
+        tags.push(language);
+    }
+    tags
+}
+
+/// The name of the recipient's translation of a template if there is one, e.g. `coldchain/temperature.fr.md`,
+/// otherwise the template name
+pub fn localised_template_name(
+    template_names: &HashSet<String>,
+    template_name: &str,
+    locale: &Option<String>,
+) -> String {
+    let locale = match locale {
+        Some(locale) => locale,
+        None => return template_name.to_string(),
+    };
+
+    // The locale goes before the file extension, if the template name has one
+    let file_name_start = template_name.rfind('/').map(|i| i + 1).unwrap_or(0);
+    let (stem, extension) = match template_name[file_name_start..].rfind('.') {
+        Some(dot) => template_name.split_at(file_name_start + dot),
+        None => (template_name, ""),
+    };
+
+    locale_tags(locale)
+        .into_iter()
+        .map(|tag| format!("{}.{}{}", stem, tag, extension))
+        .find(|name| template_names.contains(name))
+        .unwrap_or_else(|| template_name.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_tag() {
+        assert_eq!(Locale::from_tag(Some("fr")), Locale::French);
+        assert_eq!(Locale::from_tag(Some("fr-CA")), Locale::French);
+        assert_eq!(Locale::from_tag(Some("FR_fr")), Locale::French);
+        assert_eq!(Locale::from_tag(Some("lo")), Locale::Lao);
+        assert_eq!(Locale::from_tag(Some("en-NZ")), Locale::English);
+        assert_eq!(Locale::from_tag(Some("de")), Locale::English);
+        assert_eq!(Locale::from_tag(None), Locale::English);
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(Locale::English.duration(1), "1 second");
+        assert_eq!(Locale::English.duration(5400), "1 hour");
+        assert_eq!(Locale::English.duration(-172_800), "2 days");
+        assert_eq!(Locale::French.duration(10_800), "3 heures");
+        assert_eq!(Locale::Lao.duration(120), "2 ນາທີ");
+
+        assert_eq!(Locale::English.time_ago(30), "just now");
+        assert_eq!(Locale::English.time_ago(10_800), "3 hours ago");
+        assert_eq!(Locale::English.time_ago(-7200), "in 2 hours");
+        assert_eq!(Locale::French.time_ago(10_800), "il y a 3 heures");
+        assert_eq!(Locale::French.time_ago(-60), "dans 1 minute");
+        assert_eq!(Locale::Lao.time_ago(172_800), "2 ວັນທີ່ຜ່ານມາ");
+    }
+
+    #[test]
+    fn test_check_locale() {
+        assert!(check_locale("fr").is_ok());
+        assert!(check_locale("fr-CA").is_ok());
+        assert!(check_locale("zh_Hant_TW").is_ok());
+        assert!(check_locale("french").is_err());
+        assert!(check_locale("fr-").is_err());
+        assert!(check_locale("f").is_err());
+        assert!(check_locale("fr CA").is_err());
+    }
+
+    #[test]
+    fn test_localised_template_name() {
+        let template_names: HashSet<String> = [
+            "coldchain/temperature.md",
+            "coldchain/temperature.fr.md",
+            "coldchain/temperature.fr-CA.md",
+            "signature.lo",
+        ]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+        let name = |template_name: &str, locale: Option<&str>| {
+            localised_template_name(
+                &template_names,
+                template_name,
+                &locale.map(|locale| locale.to_string()),
+            )
+        };
+
+        assert_eq!(
+            name("coldchain/temperature.md", Some("fr")),
+            "coldchain/temperature.fr.md"
+        );
+        assert_eq!(
+            name("coldchain/temperature.md", Some("fr_CA")),
+            "coldchain/temperature.fr-CA.md"
+        );
+        assert_eq!(
+            name("coldchain/temperature.md", Some("fr-BE")),
+            "coldchain/temperature.fr.md"
+        );
+        // Falls back to the default template
+        assert_eq!(
+            name("coldchain/temperature.md", Some("lo")),
+            "coldchain/temperature.md"
+        );
+        assert_eq!(
+            name("coldchain/temperature.md", None),
+            "coldchain/temperature.md"
+        );
+        // Templates without an extension
+        assert_eq!(name("signature", Some("lo")), "signature.lo");
+    }
+}
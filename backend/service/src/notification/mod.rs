@@ -1,20 +1,30 @@
+use crate::notification::email::{render_notification_email, NotificationEmail};
+use crate::notification::rate_limit::RateLimiter;
 use crate::service_provider::ServiceContext;
 use crate::settings::Settings;
 use async_trait::async_trait;
-use chrono::{ Utc, Duration };
+use chrono::Utc;
+use futures_util::{future::join_all, stream, StreamExt};
 use lettre::address::AddressError;
 use repository::{
-    NotificationEventRowRepository, NotificationEventStatus, NotificationType, RepositoryError,
+    NotificationEventRow, NotificationEventRowRepository, NotificationEventStatus,
+    NotificationPriority, NotificationType, RecipientRowRepository, RepositoryError,
 };
+use std::collections::HashMap;
 use serde_json::json;
-use telegram::TelegramError;
+use telegram::{TelegramClient, TelegramError, TemporaryErrorType};
 use tera::Tera;
 
+pub mod chart;
+pub mod dispatch;
+pub mod email;
 pub mod enqueue;
+pub mod locale;
+pub mod quiet_hours;
+pub mod rate_limit;
 pub mod renderer;
-
-pub static MAX_SEND_ATTEMPTS: i32 = 3;
-pub static RETRY_DELAY_MINUTES: i64 = 15; // Doubles each retry
+pub mod retry;
+pub mod template_helpers;
 
 // We use a trait for NotificationService to allow mocking in tests
 #[async_trait(?Send)]
@@ -37,6 +47,7 @@ pub trait NotificationServiceTrait: Send + Sync {
 
 pub struct NotificationService {
     pub tera: Tera,
+    pub rate_limiter: RateLimiter,
 }
 
 #[derive(Debug)]
@@ -67,10 +78,15 @@ impl NotificationService {
             Some(base_dir) => format!("{}/templates/**/*", base_dir),
             None => "templates/**/*".to_string(), // Assume base dir relative to current dir
         };
-        let tera = Tera::new(&template_path)
+        let mut tera = Tera::new(&template_path)
             .expect(format!("Unable to create tera with path {}", template_path).as_str());
+        chart::register_chart_function(&mut tera);
+        template_helpers::register_template_helpers(&mut tera);
 
-        NotificationService { tera }
+        NotificationService {
+            tera,
+            rate_limiter: RateLimiter::new(settings.rate_limit),
+        }
     }
 }
 
@@ -108,184 +124,489 @@ impl NotificationServiceTrait for NotificationService {
         let repo = NotificationEventRowRepository::new(&ctx.connection);
         let queued_notifications = repo.un_sent()?;
 
-        let mut error_count = 0;
-        let mut sent_count = 0;
-
-        for mut notification in queued_notifications {
-            match notification.notification_type {
-                NotificationType::Unknown => {
-                    // This should only happen with a misconfigured sql recipient list query.
-                    // If you get this error in the logs you need to fix the sql query.
-                    error_count += 1;
-                    log::error!(
-                        "Unknown Notification Type {} to {} !!!!!",
-                        notification.id,
-                        notification.to_address,
-                    );
-                    notification.error_message = Some(format!(
-                        "Unknown Notification Type for address {}",
-                        notification.to_address,
-                    ));
-                    notification.status = NotificationEventStatus::Failed;
+        // Each notification type is sent separately, so a slow SMTP server doesn't hold up telegram messages
+        let mut queued_by_type: HashMap<NotificationType, Vec<NotificationEventRow>> =
+            HashMap::new();
+        for notification in queued_notifications {
+            queued_by_type
+                .entry(notification.notification_type.clone())
+                .or_default()
+                .push(notification);
+        }
+
+        let dispatch_settings = &ctx.service_provider.settings.dispatch;
+        let sends = queued_by_type
+            .into_iter()
+            .map(|(notification_type, notifications)| {
+                stream::iter(notifications)
+                    .map(|notification| send_notification(ctx, &self.rate_limiter, notification))
+                    .buffer_unordered(dispatch_settings.concurrency(&notification_type))
+                    .collect::<Vec<_>>()
+            });
+        let results: Vec<Result<SendResult, NotificationServiceError>> =
+            join_all(sends).await.into_iter().flatten().collect();
+
+        let sent_count = results
+            .iter()
+            .filter(|r| matches!(r, Ok(SendResult::Sent)))
+            .count();
+        let deferred_count = results
+            .iter()
+            .filter(|r| matches!(r, Ok(SendResult::Deferred)))
+            .count();
+        let error_count = results.len() - sent_count - deferred_count;
+        log::debug!(
+            "Sent {} notifications, {} deferred, {} errors",
+            sent_count,
+            deferred_count,
+            error_count
+        );
+
+        // Everything else has still been attempted, but let the caller know if a result couldn't be saved
+        if let Some(Err(error)) = results.into_iter().find(|r| r.is_err()) {
+            return Err(error);
+        }
+
+        Ok(sent_count)
+    }
+}
+
+enum SendResult {
+    Sent,
+    /// Rate limited or in the recipient's quiet hours, will be sent later
+    Deferred,
+    Failed,
+}
+
+/// Sends a single notification event and records the result
+async fn send_notification(
+    ctx: &ServiceContext,
+    rate_limiter: &RateLimiter,
+    mut notification: NotificationEventRow,
+) -> Result<SendResult, NotificationServiceError> {
+    let repo = NotificationEventRowRepository::new(&ctx.connection);
+    let mut outcome = SendResult::Failed;
+
+    if notification.priority != NotificationPriority::Critical {
+        if let Some(quiet_until) = quiet_hours_end(ctx, &notification)? {
+            log::info!(
+                "Holding notification {} to {} until the end of quiet hours at {}",
+                notification.id,
+                notification.to_address,
+                quiet_until
+            );
+            notification.retry_at = Some(quiet_until);
+            notification.updated_at = Utc::now().naive_utc();
+            repo.update_one(&notification)?;
+            return Ok(SendResult::Deferred);
+        }
+    }
+
+    match notification.notification_type {
+        NotificationType::Unknown => {
+            // This should only happen with a misconfigured sql recipient list query.
+            // If you get this error in the logs you need to fix the sql query.
+            log::error!(
+                "Unknown Notification Type {} to {} !!!!!",
+                notification.id,
+                notification.to_address,
+            );
+            notification.error_message = Some(format!(
+                "Unknown Notification Type for address {}",
+                notification.to_address,
+            ));
+            notification.status = NotificationEventStatus::Failed;
+
+            repo.update_one(&notification)?;
+        }
+        NotificationType::Email => {
+            // Try to send via email
+            if let Err(wait) = rate_limiter.try_acquire(
+                &notification.notification_type,
+                &notification.to_address,
+                1,
+            ) {
+                defer_notification(&mut notification, wait);
+                repo.update_one(&notification)?;
+                return Ok(SendResult::Deferred);
+            }
+
+            let subject = notification
+                .title
+                .clone()
+                .unwrap_or("Notification".to_string());
+            let NotificationEmail {
+                html_body,
+                text_body,
+                inline_images,
+            } = render_notification_email(ctx, &subject, &notification.message);
+
+            // Sending an email blocks until the SMTP server responds, so it's done on a separate thread
+            let service_provider = ctx.service_provider.clone();
+            let to_address = notification.to_address.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                service_provider.email_service.send_email(
+                    to_address,
+                    subject,
+                    html_body,
+                    text_body,
+                    inline_images,
+                )
+            })
+            .await
+            .map_err(|e| {
+                NotificationServiceError::InternalError(format!("Email send task failed: {:?}", e))
+            })?;
 
+            match result {
+                Ok(_) => {
+                    // Successfully Sent
+                    notification.error_message = None;
+                    notification.status = NotificationEventStatus::Sent;
+                    notification.send_attempts += 1;
+                    notification.sent_at = Some(Utc::now().naive_utc());
+                    notification.updated_at = Utc::now().naive_utc();
                     repo.update_one(&notification)?;
+                    outcome = SendResult::Sent;
                 }
-                NotificationType::Email => {
-                    // Try to send via email
-                    let text_body = notification.message.clone();
-                    let parser = pulldown_cmark::Parser::new(&notification.message);
-                    let mut email_body = String::new();
-                    pulldown_cmark::html::push_html(&mut email_body, parser);
-
-                    let result = ctx.service_provider.email_service.send_email(
-                        notification.to_address.clone(),
-                        notification
-                            .title
-                            .clone()
-                            .unwrap_or("Notification".to_string()),
-                        email_body,
-                        text_body,
+                Err(send_error) => {
+                    record_failed_attempt(
+                        ctx,
+                        &mut notification,
+                        format!("{:?}", send_error),
+                        send_error.is_permanent(),
                     );
+                    repo.update_one(&notification)?;
+                }
+            }
+        }
+        NotificationType::Telegram => {
+            // Try to send via telegram
+            if let Some(telegram) = &ctx.service_provider.telegram {
+                // Charts are sent as photos after the text of the message
+                let (markdown, charts) =
+                    chart::extract_chart_images(&notification.message, |_, _| {
+                        "".to_string()
+                    });
+                let telegram_markdown_v2 =
+                    telegram::service::markdown::cmark_to_telegram_v2(&markdown);
+                let parts = if telegram_markdown_v2.trim().is_empty() {
+                    vec![]
+                } else {
+                    telegram::service::markdown::split_telegram_v2(
+                        &telegram_markdown_v2,
+                        telegram::MAX_MESSAGE_LENGTH,
+                    )
+                };
 
-                    match result {
-                        Ok(_) => {
-                            // Successfully Sent
-                            notification.error_message = None;
-                            notification.status = NotificationEventStatus::Sent;
-                            notification.send_attempts += 1;
-                            notification.sent_at = Some(Utc::now().naive_utc());
-                            notification.updated_at = Utc::now().naive_utc();
-                            repo.update_one(&notification)?;
-                            sent_count += 1;
-                        }
-                        Err(send_error) => {
-                            // Failed to send
-                            notification.updated_at = Utc::now().naive_utc();
-                            notification.send_attempts += 1;
-                            if notification.send_attempts >= MAX_SEND_ATTEMPTS {
-                                log::error!(
-                                    "Failed to send email {} to {} after {} attempts - {:?}",
-                                    notification.id,
-                                    notification.to_address,
-                                    MAX_SEND_ATTEMPTS,
-                                    send_error
-                                );
-                                notification.error_message = Some(format!(
-                                    "Failed to send email after {} attempts - {:?}",
-                                    MAX_SEND_ATTEMPTS, send_error
-                                ));
-                                notification.status = NotificationEventStatus::Failed;
-                            } else if send_error.is_permanent() {
-                                log::error!(
-                                    "Permanently failed to send email {} to {}",
-                                    notification.id,
-                                    notification.to_address,
-                                );
-                                notification.error_message = Some(format!("{:?}", send_error));
-                                notification.status = NotificationEventStatus::Failed;
-                            } else {
-                                log::error!(
-                                    "Temporarily unable to send email {} to {} - {:?}",
-                                    notification.id,
-                                    notification.to_address,
-                                    send_error
-                                );
-                                notification.error_message = Some(format!("{:?}", send_error));
-                                notification.status = NotificationEventStatus::Errored;
-                                notification.retry_at = Some(
-                                    Utc::now().naive_utc() +
-                                    Duration::minutes(
-                                        RETRY_DELAY_MINUTES *
-                                        i64::pow(2, notification.send_attempts as u32 - 1)
-                                    )
-                                )
-                            }
-                            error_count += 1;
-                            repo.update_one(&notification)?;
-                            continue;
-                        }
-                    }
+                // Each part of a long message and each chart is sent as a separate message,
+                // anything sent by a previous attempt isn't sent again
+                let cost = (parts.len() + charts.len())
+                    .saturating_sub(notification.parts_sent.max(0) as usize)
+                    as u32;
+                if let Err(wait) = rate_limiter.try_acquire(
+                    &notification.notification_type,
+                    &notification.to_address,
+                    cost,
+                ) {
+                    defer_notification(&mut notification, wait);
+                    repo.update_one(&notification)?;
+                    return Ok(SendResult::Deferred);
                 }
-                NotificationType::Telegram => {
-                    // Try to send via telegram
-                    if let Some(telegram) = &ctx.service_provider.telegram {
-                        let telegram_markdown_v2 =
-                            telegram::service::markdown::cmark_to_telegram_v2(
-                                &notification.message,
-                            );
-
-                        let result = telegram
-                            .send_markdown_message(&notification.to_address, &telegram_markdown_v2)
-                            .await;
-
-                        match result {
-                            Ok(_) => {
-                                log::info!("Sent telegram message to {}", notification.to_address);
-                                notification.error_message = None;
-                                notification.status = NotificationEventStatus::Sent;
-                                notification.send_attempts += 1;
-                                notification.sent_at = Some(Utc::now().naive_utc());
-                                notification.updated_at = Utc::now().naive_utc();
-                                repo.update_one(&notification)?;
-                                sent_count += 1;
-                            }
-                            Err(TelegramError::Fatal(e)) => {
-                                log::error!(
-                                    "Permanently fail to send telegram message to {}: {:?}",
-                                    notification.to_address,
-                                    e
-                                );
-                                notification.send_attempts += 1;
-                                notification.error_message = Some(format!("{:?}", e));
-                                notification.status = NotificationEventStatus::Failed;
-                                notification.updated_at = Utc::now().naive_utc();
-                                repo.update_one(&notification)?;
-                                error_count += 1;
-                            }
-                            Err(TelegramError::Temporary(e)) => {
-                                notification.send_attempts += 1;
-                                if notification.send_attempts >= MAX_SEND_ATTEMPTS {
-                                    log::error!(
-                                    "Failed to send telegram message {} to {} after {} attempts - {:?}",
-                                    notification.id,
-                                    notification.to_address,
-                                    MAX_SEND_ATTEMPTS,
-                                    e
-                                );
-                                    notification.error_message = Some(format!("{:?}", e));
-                                    notification.status = NotificationEventStatus::Failed;
-                                } else {
-                                    log::error!(
-                                    "Temporarily unable to send telegram message {} to {} - {:?}",
-                                    notification.id,
-                                    notification.to_address,
-                                    e
-                                );
-                                    notification.error_message = Some(format!("{:?}", e));
-                                    notification.status = NotificationEventStatus::Errored;
-                                }
-
-                                notification.updated_at = Utc::now().naive_utc();
-                                repo.update_one(&notification)?;
-                                error_count += 1;
-                            }
-                        }
-                    } else {
-                        log::error!(
-                            "Telegram not configured, you are missing telegram notifications!!!!"
-                        );
-                        notification.error_message = Some("Telegram Not Configured".to_string());
-                        notification.status = NotificationEventStatus::Errored;
+
+                let result =
+                    send_telegram_message_with_charts(telegram, &mut notification, parts, charts)
+                        .await;
+
+                match result {
+                    Ok(()) => {
+                        log::info!("Sent telegram message to {}", notification.to_address);
+                        notification.error_message = None;
+                        notification.status = NotificationEventStatus::Sent;
+                        notification.send_attempts += 1;
+                        notification.sent_at = Some(Utc::now().naive_utc());
                         notification.updated_at = Utc::now().naive_utc();
                         repo.update_one(&notification)?;
-                        error_count += 1;
+                        outcome = SendResult::Sent;
+                    }
+                    Err(TelegramError::Temporary(TemporaryErrorType::TooManyRequests {
+                        retry_after_seconds,
+                        ..
+                    })) => {
+                        let wait = std::time::Duration::from_secs(retry_after_seconds);
+                        rate_limiter.block(
+                            &notification.notification_type,
+                            &notification.to_address,
+                            wait,
+                        );
+                        defer_notification(&mut notification, wait);
+                        repo.update_one(&notification)?;
+                        outcome = SendResult::Deferred;
+                    }
+                    Err(TelegramError::Fatal(e)) => {
+                        record_failed_attempt(ctx, &mut notification, format!("{:?}", e), true);
+                        repo.update_one(&notification)?;
+                    }
+                    Err(TelegramError::Temporary(e)) => {
+                        record_failed_attempt(ctx, &mut notification, format!("{:?}", e), false);
+                        repo.update_one(&notification)?;
                     }
                 }
+            } else {
+                log::error!(
+                    "Telegram not configured, you are missing telegram notifications!!!!"
+                );
+                record_failed_attempt(
+                    ctx,
+                    &mut notification,
+                    "Telegram Not Configured".to_string(),
+                    false,
+                );
+                repo.update_one(&notification)?;
             }
         }
+    }
 
-        log::debug!("Sent {} notifications, {} errors", sent_count, error_count);
+    Ok(outcome)
+}
 
-        Ok(sent_count)
+/// If the notification's recipient is currently in their quiet hours, returns when the quiet hours end
+fn quiet_hours_end(
+    ctx: &ServiceContext,
+    notification: &NotificationEventRow,
+) -> Result<Option<chrono::NaiveDateTime>, RepositoryError> {
+    let recipient = RecipientRowRepository::new(&ctx.connection).find_one_by_to_address_and_type(
+        &notification.to_address,
+        notification.notification_type.clone(),
+    )?;
+    let Some(recipient) = recipient else {
+        return Ok(None);
+    };
+
+    match quiet_hours::QuietHours::from_recipient(&recipient) {
+        Ok(Some(quiet_hours)) => Ok(quiet_hours.ends_at(Utc::now())),
+        Ok(None) => Ok(None),
+        Err(e) => {
+            log::warn!("Ignoring quiet hours for recipient {}: {}", recipient.id, e);
+            Ok(None)
+        }
+    }
+}
+
+/// Puts a rate limited notification back in the queue, without counting it as a failed attempt
+fn defer_notification(notification: &mut NotificationEventRow, wait: std::time::Duration) {
+    let now = Utc::now().naive_utc();
+    let wait = chrono::Duration::from_std(wait).unwrap_or(chrono::Duration::seconds(1));
+    log::info!(
+        "Rate limited sending {:?} notification {} to {}, will retry at {}",
+        notification.notification_type,
+        notification.id,
+        notification.to_address,
+        now + wait
+    );
+    notification.retry_at = Some(now + wait);
+    notification.updated_at = now;
+}
+
+/// Records a failed attempt to send a notification.
+/// Unless the error is permanent, a retry is scheduled using the retry policy for the notification type,
+/// until there are no attempts left.
+fn record_failed_attempt(
+    ctx: &ServiceContext,
+    notification: &mut NotificationEventRow,
+    error: String,
+    permanent: bool,
+) {
+    let policy = ctx
+        .service_provider
+        .settings
+        .retry
+        .policy(&notification.notification_type);
+    let now = Utc::now().naive_utc();
+
+    notification.send_attempts += 1;
+    notification.updated_at = now;
+    notification.retry_at = None;
+
+    let retry_at = match permanent {
+        true => None,
+        false => retry::next_retry_at(&policy, notification.send_attempts, now),
+    };
+
+    match retry_at {
+        Some(retry_at) => {
+            log::error!(
+                "Temporarily unable to send {:?} notification {} to {}, retrying at {} - {}",
+                notification.notification_type,
+                notification.id,
+                notification.to_address,
+                retry_at,
+                error
+            );
+            notification.error_message = Some(error);
+            notification.status = NotificationEventStatus::Errored;
+            notification.retry_at = Some(retry_at);
+        }
+        None if permanent => {
+            log::error!(
+                "Permanently failed to send {:?} notification {} to {} - {}",
+                notification.notification_type,
+                notification.id,
+                notification.to_address,
+                error
+            );
+            notification.error_message = Some(error);
+            notification.status = NotificationEventStatus::Failed;
+        }
+        None => {
+            log::error!(
+                "Failed to send {:?} notification {} to {} after {} attempts - {}",
+                notification.notification_type,
+                notification.id,
+                notification.to_address,
+                notification.send_attempts,
+                error
+            );
+            notification.error_message = Some(format!(
+                "Failed to send after {} attempts - {}",
+                notification.send_attempts, error
+            ));
+            notification.status = NotificationEventStatus::Failed;
+        }
+    }
+}
+
+/// Sends the parts of the message then the charts, starting from the first one that hasn't been sent yet.
+/// `parts_sent` is updated as each one is sent, so if one fails a retry carries on from there.
+async fn send_telegram_message_with_charts(
+    telegram: &TelegramClient,
+    notification: &mut NotificationEventRow,
+    parts: Vec<String>,
+    charts: Vec<chart::ChartImage>,
+) -> Result<(), TelegramError> {
+    let already_sent = notification.parts_sent.max(0) as usize;
+    for part in parts.iter().skip(already_sent) {
+        let sent = telegram
+            .send_markdown_part(&notification.to_address, part)
+            .await?;
+        if let Some(error) = sent.plain_text_fallback {
+            notification.fallback_reason = Some(format!(
+                "Sent as plain text, as telegram couldn't parse the formatting - {}",
+                error
+            ));
+        }
+        notification.parts_sent += 1;
+    }
+
+    let charts_sent = already_sent.saturating_sub(parts.len());
+    for chart in charts.into_iter().skip(charts_sent) {
+        telegram
+            .send_photo(&notification.to_address, chart.png, Some(&chart.alt_text))
+            .await?;
+        notification.parts_sent += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    use repository::{NotificationEventRow, NotificationType};
+    use telegram::{TelegramClient, TelegramError, TemporaryErrorType};
+
+    use super::send_telegram_message_with_charts;
+
+    /// A telegram bot api that gives each of the responses in turn, and records the text of each message sent to it
+    fn mock_telegram_api(responses: Vec<&'static str>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let texts = Arc::new(Mutex::new(vec![]));
+
+        let recorded_texts = texts.clone();
+        std::thread::spawn(move || {
+            for (stream, response) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(length) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let text = String::from_utf8(body)
+                    .unwrap()
+                    .split('&')
+                    .find_map(|param| param.strip_prefix("text=").map(|text| text.to_string()))
+                    .unwrap_or_default();
+                recorded_texts.lock().unwrap().push(text);
+
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+
+        (url, texts)
+    }
+
+    #[actix_rt::test]
+    async fn test_send_telegram_message_resumes_after_a_part_fails() {
+        let sent = r#"{"ok":true,"result":{"message_id":1,"chat":{"id":-1234,"type":"group"}}}"#;
+        let too_many_requests = r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 5","parameters":{"retry_after":5}}"#;
+        let (url, texts) = mock_telegram_api(vec![sent, too_many_requests, sent, sent]);
+        let telegram = TelegramClient::with_base_url(url);
+
+        let parts = || {
+            vec![
+                "Part1".to_string(),
+                "Part2".to_string(),
+                "Part3".to_string(),
+            ]
+        };
+        let mut notification = NotificationEventRow {
+            to_address: "-1234".to_string(),
+            notification_type: NotificationType::Telegram,
+            ..Default::default()
+        };
+
+        // The second part is rate limited
+        let result =
+            send_telegram_message_with_charts(&telegram, &mut notification, parts(), vec![]).await;
+        assert!(matches!(
+            result,
+            Err(TelegramError::Temporary(
+                TemporaryErrorType::TooManyRequests {
+                    retry_after_seconds: 5,
+                    ..
+                }
+            ))
+        ));
+        assert_eq!(notification.parts_sent, 1);
+
+        // The retry carries on from the part that failed, rather than sending the first part again
+        send_telegram_message_with_charts(&telegram, &mut notification, parts(), vec![])
+            .await
+            .unwrap();
+        assert_eq!(notification.parts_sent, 3);
+        assert_eq!(
+            *texts.lock().unwrap(),
+            vec!["Part1", "Part2", "Part2", "Part3"]
+        );
     }
 }
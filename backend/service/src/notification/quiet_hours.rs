@@ -0,0 +1,260 @@
+use chrono::{DateTime, Duration, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use repository::RecipientRow;
+
+/// A recipient's quiet hours, when only critical notifications are sent to them
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub timezone: Tz,
+}
+
+pub fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time {}, expected HH:MM e.g. 22:00", time))
+}
+
+pub fn parse_timezone(timezone: &str) -> Result<Tz, String> {
+    timezone.trim().parse::<Tz>().map_err(|_| {
+        format!(
+            "Invalid timezone {}, expected a timezone name e.g. Pacific/Auckland",
+            timezone
+        )
+    })
+}
+
+impl QuietHours {
+    /// Returns `None` if quiet hours aren't set, the timezone defaults to UTC
+    pub fn parse(
+        start: &Option<String>,
+        end: &Option<String>,
+        timezone: &Option<String>,
+    ) -> Result<Option<QuietHours>, String> {
+        let timezone = match timezone {
+            Some(timezone) => parse_timezone(timezone)?,
+            None => Tz::UTC,
+        };
+
+        match (start, end) {
+            (None, None) => Ok(None),
+            (Some(start), Some(end)) => Ok(Some(QuietHours {
+                start: parse_time(start)?,
+                end: parse_time(end)?,
+                timezone,
+            })),
+            _ => Err("Quiet hours need both a start and an end time".to_string()),
+        }
+    }
+
+    pub fn from_recipient(recipient: &RecipientRow) -> Result<Option<QuietHours>, String> {
+        QuietHours::parse(
+            &recipient.quiet_hours_start,
+            &recipient.quiet_hours_end,
+            &recipient.timezone,
+        )
+    }
+
+    /// If `now` is within quiet hours, returns when they end (as UTC)
+    pub fn ends_at(&self, now: DateTime<Utc>) -> Option<NaiveDateTime> {
+        let local_now = now.with_timezone(&self.timezone);
+        let time = local_now.time();
+        let today = local_now.date_naive();
+
+        let end_date = if self.start < self.end {
+            // e.g. 13:00 to 14:00
+            if time < self.start || time >= self.end {
+                return None;
+            }
+            today
+        } else if self.start > self.end {
+            // Quiet hours go past midnight, e.g. 22:00 to 06:00
+            if time >= self.start {
+                today + Duration::days(1)
+            } else if time < self.end {
+                today
+            } else {
+                return None;
+            }
+        } else {
+            // Start and end are the same, so there aren't any quiet hours
+            return None;
+        };
+
+        let local_end = end_date.and_time(self.end);
+        // If the end time is skipped by a daylight saving change, use the first time after it
+        let end = self
+            .timezone
+            .from_local_datetime(&local_end)
+            .earliest()
+            .or_else(|| {
+                self.timezone
+                    .from_local_datetime(&(local_end + Duration::hours(1)))
+                    .earliest()
+            })?;
+
+        Some(end.with_timezone(&Utc).naive_utc())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use repository::{
+        mock::MockDataInserts, test_db::setup_all, NotificationEventRow,
+        NotificationEventRowRepository, NotificationEventStatus, NotificationPriority,
+        NotificationType, RecipientRowRepository,
+    };
+
+    use super::*;
+    use crate::{
+        service_provider::{ServiceContext, ServiceProvider},
+        test_utils::get_test_settings,
+    };
+
+    fn utc(datetime: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(datetime)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn quiet_hours(start: &str, end: &str, timezone: &str) -> QuietHours {
+        QuietHours::parse(
+            &Some(start.to_string()),
+            &Some(end.to_string()),
+            &Some(timezone.to_string()),
+        )
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_quiet_hours() {
+        assert_eq!(QuietHours::parse(&None, &None, &None), Ok(None));
+        assert!(QuietHours::parse(&Some("22:00".to_string()), &None, &None).is_err());
+        assert!(
+            QuietHours::parse(&Some("22:00".to_string()), &Some("6am".to_string()), &None).is_err()
+        );
+        assert!(QuietHours::parse(
+            &Some("22:00".to_string()),
+            &Some("06:00".to_string()),
+            &Some("Mars/Olympus_Mons".to_string())
+        )
+        .is_err());
+
+        let quiet_hours = quiet_hours("22:00", "06:00", "Pacific/Auckland");
+        assert_eq!(
+            quiet_hours.start,
+            NaiveTime::from_hms_opt(22, 0, 0).unwrap()
+        );
+        assert_eq!(quiet_hours.timezone, Tz::Pacific__Auckland);
+    }
+
+    #[test]
+    fn test_quiet_hours_ends_at() {
+        // Overnight, in UTC
+        let overnight = quiet_hours("22:00", "06:00", "UTC");
+        assert_eq!(overnight.ends_at(utc("2024-03-11T21:59:00Z")), None);
+        assert_eq!(
+            overnight.ends_at(utc("2024-03-11T22:00:00Z")),
+            Some(utc("2024-03-12T06:00:00Z").naive_utc())
+        );
+        assert_eq!(
+            overnight.ends_at(utc("2024-03-12T02:00:00Z")),
+            Some(utc("2024-03-12T06:00:00Z").naive_utc())
+        );
+        assert_eq!(overnight.ends_at(utc("2024-03-12T06:00:00Z")), None);
+
+        // During the day
+        let lunch = quiet_hours("12:00", "13:00", "UTC");
+        assert_eq!(
+            lunch.ends_at(utc("2024-03-11T12:30:00Z")),
+            Some(utc("2024-03-11T13:00:00Z").naive_utc())
+        );
+        assert_eq!(lunch.ends_at(utc("2024-03-11T13:30:00Z")), None);
+
+        // 2am in Auckland (NZDT, UTC+13) is 13:00 UTC the day before
+        let auckland = quiet_hours("22:00", "06:00", "Pacific/Auckland");
+        assert_eq!(
+            auckland.ends_at(utc("2024-03-11T13:00:00Z")),
+            Some(utc("2024-03-11T17:00:00Z").naive_utc())
+        );
+        assert_eq!(auckland.ends_at(utc("2024-03-11T00:00:00Z")), None);
+
+        // No quiet hours if start and end are the same
+        let none = quiet_hours("06:00", "06:00", "UTC");
+        assert_eq!(none.ends_at(utc("2024-03-11T06:00:00Z")), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_quiet_hours_hold_notifications() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_quiet_hours_hold_notifications",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let connection = connection_manager.connection().unwrap();
+        let mut settings = get_test_settings("");
+        settings.telegram.token = None;
+        let service_provider = Arc::new(ServiceProvider::new(connection_manager, settings));
+        let context = ServiceContext::as_server_admin(service_provider.clone()).unwrap();
+
+        // The recipient is in the middle of their quiet hours
+        let now = Utc::now();
+        RecipientRowRepository::new(&connection)
+            .insert_one(&RecipientRow {
+                id: "night_shift".to_string(),
+                name: "Night shift".to_string(),
+                notification_type: NotificationType::Telegram,
+                to_address: "-1234".to_string(),
+                quiet_hours_start: Some((now - Duration::hours(1)).format("%H:%M").to_string()),
+                quiet_hours_end: Some((now + Duration::hours(1)).format("%H:%M").to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let repo = NotificationEventRowRepository::new(&connection);
+        let event = |id: &str, priority: NotificationPriority| NotificationEventRow {
+            id: id.to_string(),
+            notification_type: NotificationType::Telegram,
+            to_address: "-1234".to_string(),
+            message: "Stock report".to_string(),
+            created_at: now.naive_utc(),
+            updated_at: now.naive_utc(),
+            priority,
+            ..Default::default()
+        };
+        repo.insert_one(&event("report", NotificationPriority::Normal))
+            .unwrap();
+        repo.insert_one(&event("alert", NotificationPriority::Critical))
+            .unwrap();
+
+        service_provider
+            .notification_service
+            .send_queued_notifications(&context)
+            .await
+            .unwrap();
+
+        let events = repo.errors().unwrap();
+        // The critical alert was attempted (but telegram isn't configured)
+        let alert = events.iter().find(|e| e.id == "alert").unwrap();
+        assert_eq!(alert.send_attempts, 1);
+
+        // The report is held until the end of quiet hours, without counting as an attempt
+        let report = repository::NotificationEventRepository::new(&connection)
+            .query_by_filter(
+                repository::NotificationEventFilter::new()
+                    .id(repository::EqualFilter::equal_to("report")),
+            )
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(report.status, NotificationEventStatus::Queued);
+        assert_eq!(report.send_attempts, 0);
+        let retry_at = report.retry_at.unwrap();
+        assert!(retry_at > (now + Duration::minutes(58)).naive_utc());
+        assert!(retry_at <= (now + Duration::hours(1)).naive_utc());
+    }
+}
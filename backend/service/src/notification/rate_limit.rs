@@ -0,0 +1,306 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use repository::NotificationType;
+
+use crate::settings::{RateLimit, RateLimitSettings};
+
+// Buckets that are full again are the same as new ones, so they are cleaned up once there are this many
+const MAX_BUCKETS_BEFORE_CLEANUP: usize = 1000;
+
+/// A token bucket, allows a burst of up to `capacity` sends which then refills at a steady rate
+struct Bucket {
+    tokens: f64,
+    capacity: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn new(limit: &RateLimit, now: Instant) -> Self {
+        let capacity = limit.max.max(1) as f64;
+        Bucket {
+            tokens: capacity,
+            capacity,
+            refill_per_second: capacity / limit.per_seconds.max(1) as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// How long until `cost` tokens are available
+    fn wait_time(&self, cost: f64) -> Duration {
+        let cost = cost.min(self.capacity);
+        if self.tokens >= cost {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((cost - self.tokens) / self.refill_per_second)
+    }
+
+    fn take(&mut self, cost: f64) {
+        self.tokens -= cost.min(self.capacity);
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= self.capacity
+    }
+}
+
+/// Identifies a bucket, `to_address` is `None` for the limit that applies to every destination
+#[derive(PartialEq, Eq, Hash)]
+struct BucketKey {
+    notification_type: NotificationType,
+    to_address: Option<String>,
+}
+
+#[derive(Default)]
+struct RateLimiterState {
+    buckets: HashMap<BucketKey, Bucket>,
+    blocked_until: HashMap<BucketKey, Instant>,
+}
+
+/// Rate limits outgoing notifications, for each notification type and each destination
+pub struct RateLimiter {
+    settings: RateLimitSettings,
+    state: Mutex<RateLimiterState>,
+}
+
+impl RateLimiter {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        RateLimiter {
+            settings,
+            state: Mutex::new(RateLimiterState::default()),
+        }
+    }
+
+    /// Uses up `cost` sends (e.g. a telegram message and its charts) from the limits for the notification type and destination.
+    /// If a limit has been reached nothing is used up, and the time to wait before trying again is returned.
+    pub fn try_acquire(
+        &self,
+        notification_type: &NotificationType,
+        to_address: &str,
+        cost: u32,
+    ) -> Result<(), Duration> {
+        self.try_acquire_at(notification_type, to_address, cost, Instant::now())
+    }
+
+    /// Stops sending to a destination for a while, e.g. when telegram asks us to slow down
+    pub fn block(
+        &self,
+        notification_type: &NotificationType,
+        to_address: &str,
+        duration: Duration,
+    ) {
+        self.block_at(notification_type, to_address, duration, Instant::now())
+    }
+
+    fn try_acquire_at(
+        &self,
+        notification_type: &NotificationType,
+        to_address: &str,
+        cost: u32,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let limits = self.settings.limits(notification_type);
+        let cost = cost.max(1) as f64;
+        let mut state = self.lock_state();
+
+        let global_key = BucketKey {
+            notification_type: notification_type.clone(),
+            to_address: None,
+        };
+        let destination_key = BucketKey {
+            notification_type: notification_type.clone(),
+            to_address: Some(to_address.to_string()),
+        };
+
+        if let Some(blocked_until) = state.blocked_until.get(&destination_key) {
+            if *blocked_until > now {
+                return Err(*blocked_until - now);
+            }
+            state.blocked_until.remove(&destination_key);
+        }
+
+        if state.buckets.len() > MAX_BUCKETS_BEFORE_CLEANUP {
+            state.buckets.retain(|_, bucket| {
+                bucket.refill(now);
+                !bucket.is_full()
+            });
+        }
+
+        let limited_buckets = vec![
+            (global_key, limits.global),
+            (destination_key, limits.per_destination),
+        ];
+        let mut wait = Duration::ZERO;
+        for (key, limit) in &limited_buckets {
+            if let Some(limit) = limit {
+                let bucket = state
+                    .buckets
+                    .entry(BucketKey {
+                        notification_type: key.notification_type.clone(),
+                        to_address: key.to_address.clone(),
+                    })
+                    .or_insert_with(|| Bucket::new(limit, now));
+                bucket.refill(now);
+                wait = wait.max(bucket.wait_time(cost));
+            }
+        }
+
+        if wait > Duration::ZERO {
+            return Err(wait);
+        }
+
+        for (key, limit) in limited_buckets {
+            if limit.is_some() {
+                if let Some(bucket) = state.buckets.get_mut(&key) {
+                    bucket.take(cost);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn block_at(
+        &self,
+        notification_type: &NotificationType,
+        to_address: &str,
+        duration: Duration,
+        now: Instant,
+    ) {
+        let key = BucketKey {
+            notification_type: notification_type.clone(),
+            to_address: Some(to_address.to_string()),
+        };
+        let mut state = self.lock_state();
+        let blocked_until = state.blocked_until.entry(key).or_insert(now);
+        *blocked_until = (*blocked_until).max(now + duration);
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, RateLimiterState> {
+        // The state is always left consistent, so it's still usable if another thread panicked
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::settings::ChannelRateLimits;
+
+    use super::*;
+
+    fn rate_limiter() -> RateLimiter {
+        RateLimiter::new(RateLimitSettings {
+            email: ChannelRateLimits {
+                global: Some(RateLimit {
+                    max: 3,
+                    per_seconds: 3,
+                }),
+                per_destination: None,
+            },
+            telegram: ChannelRateLimits {
+                global: Some(RateLimit {
+                    max: 30,
+                    per_seconds: 1,
+                }),
+                per_destination: Some(RateLimit {
+                    max: 2,
+                    per_seconds: 60,
+                }),
+            },
+        })
+    }
+
+    #[test]
+    fn test_global_limit() {
+        let limiter = rate_limiter();
+        let now = Instant::now();
+
+        for i in 0..3 {
+            let to_address = format!("user{}@example.com", i);
+            assert!(limiter
+                .try_acquire_at(&NotificationType::Email, &to_address, 1, now)
+                .is_ok());
+        }
+        assert_eq!(
+            limiter.try_acquire_at(&NotificationType::Email, "another@example.com", 1, now),
+            Err(Duration::from_secs(1))
+        );
+
+        // One more is allowed each second
+        let later = now + Duration::from_secs(1);
+        assert!(limiter
+            .try_acquire_at(&NotificationType::Email, "another@example.com", 1, later)
+            .is_ok());
+
+        // Other notification types have their own limits
+        assert!(limiter
+            .try_acquire_at(&NotificationType::Telegram, "chat", 1, now)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_per_destination_limit() {
+        let limiter = rate_limiter();
+        let now = Instant::now();
+
+        assert!(limiter
+            .try_acquire_at(&NotificationType::Telegram, "group", 1, now)
+            .is_ok());
+        assert!(limiter
+            .try_acquire_at(&NotificationType::Telegram, "group", 1, now)
+            .is_ok());
+        assert_eq!(
+            limiter.try_acquire_at(&NotificationType::Telegram, "group", 1, now),
+            Err(Duration::from_secs(30))
+        );
+
+        // A message with charts uses up more of the limit
+        assert!(limiter
+            .try_acquire_at(&NotificationType::Telegram, "other_group", 2, now)
+            .is_ok());
+        assert!(limiter
+            .try_acquire_at(&NotificationType::Telegram, "other_group", 1, now)
+            .is_err());
+    }
+
+    #[test]
+    fn test_block() {
+        let limiter = rate_limiter();
+        let now = Instant::now();
+
+        limiter.block_at(
+            &NotificationType::Telegram,
+            "group",
+            Duration::from_secs(5),
+            now,
+        );
+        assert_eq!(
+            limiter.try_acquire_at(&NotificationType::Telegram, "group", 1, now),
+            Err(Duration::from_secs(5))
+        );
+        assert!(limiter
+            .try_acquire_at(&NotificationType::Telegram, "other_group", 1, now)
+            .is_ok());
+        assert!(limiter
+            .try_acquire_at(
+                &NotificationType::Telegram,
+                "group",
+                1,
+                now + Duration::from_secs(5)
+            )
+            .is_ok());
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::{Duration, NaiveDateTime};
+use rand::Rng;
+
+use crate::settings::RetryPolicy;
+
+/// Returns when a notification should next be tried, after `send_attempts` failed attempts,
+/// or `None` if there are no attempts left.
+pub fn next_retry_at(
+    policy: &RetryPolicy,
+    send_attempts: i32,
+    now: NaiveDateTime,
+) -> Option<NaiveDateTime> {
+    if send_attempts >= policy.max_attempts {
+        return None;
+    }
+    let jitter_sample = rand::thread_rng().gen_range(-1.0..=1.0);
+    Some(now + retry_delay(policy, send_attempts, jitter_sample))
+}
+
+/// The delay after `send_attempts` failed attempts, `jitter_sample` is between -1 and 1
+fn retry_delay(policy: &RetryPolicy, send_attempts: i32, jitter_sample: f64) -> Duration {
+    let retries = (send_attempts - 1).max(0);
+    let backoff = policy.backoff_factor.max(1.0).powi(retries);
+    let jitter = 1.0 + policy.jitter.clamp(0.0, 1.0) * jitter_sample;
+    let delay_seconds = policy.base_delay_seconds as f64 * backoff * jitter;
+    Duration::milliseconds((delay_seconds * 1000.0) as i64)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 4,
+            base_delay_seconds: 60,
+            backoff_factor: 2.0,
+            jitter: 0.5,
+        };
+
+        // Without jitter the delay doubles each time
+        assert_eq!(retry_delay(&policy, 1, 0.0), Duration::seconds(60));
+        assert_eq!(retry_delay(&policy, 2, 0.0), Duration::seconds(120));
+        assert_eq!(retry_delay(&policy, 3, 0.0), Duration::seconds(240));
+
+        // Jitter varies the delay by up to 50%
+        assert_eq!(retry_delay(&policy, 1, 1.0), Duration::seconds(90));
+        assert_eq!(retry_delay(&policy, 1, -1.0), Duration::seconds(30));
+
+        // A constant delay
+        let policy = RetryPolicy {
+            backoff_factor: 1.0,
+            jitter: 0.0,
+            ..policy
+        };
+        assert_eq!(retry_delay(&policy, 3, 1.0), Duration::seconds(60));
+    }
+
+    #[test]
+    fn test_next_retry_at() {
+        let now = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay_seconds: 60,
+            backoff_factor: 2.0,
+            jitter: 0.1,
+        };
+
+        let retry_at = next_retry_at(&policy, 1, now).unwrap();
+        assert!(retry_at >= now + Duration::seconds(54));
+        assert!(retry_at <= now + Duration::seconds(66));
+
+        let retry_at = next_retry_at(&policy, 2, now).unwrap();
+        assert!(retry_at >= now + Duration::seconds(108));
+        assert!(retry_at <= now + Duration::seconds(132));
+
+        // No attempts left
+        assert_eq!(next_retry_at(&policy, 3, now), None);
+    }
+}
@@ -0,0 +1,504 @@
+use std::{collections::HashMap, fmt::Write};
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+use tera::Tera;
+
+use super::{chart::as_number, locale::Locale, quiet_hours::parse_timezone};
+
+const DEFAULT_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+const MAX_DECIMALS: u64 = 20;
+
+/// Registers the filters and functions available to all notification templates.
+/// Notification templates are rendered with a copy of the notification service's tera instance, so they get these too.
+pub fn register_template_helpers(tera: &mut Tera) {
+    tera.register_filter("markdown_table", markdown_table);
+    tera.register_filter("time_ago", time_ago);
+    tera.register_filter("humanise_duration", humanise_duration);
+    tera.register_filter("format_number", format_number);
+    tera.register_filter("format_date", format_date);
+    tera.register_filter("convert_temperature", convert_temperature);
+    tera.register_function("local_now", local_now);
+}
+
+/// Renders a query result array as a markdown table.
+///
+/// Usage: `{{ stock_levels | markdown_table(columns=["item", "stock"], headers=["Item", "Stock on hand"]) }}`
+/// Without `columns` every column of the first row is shown, in alphabetical order.
+/// An empty array renders nothing.
+fn markdown_table(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let rows = value
+        .as_array()
+        .ok_or_else(|| tera::Error::msg("markdown_table expects a query result array"))?;
+    let first_row = match rows.first() {
+        Some(first_row) => first_row,
+        None => return Ok(Value::String("".to_string())),
+    };
+
+    let columns = match args.get("columns") {
+        Some(columns) => string_list(columns, "columns")?,
+        None => first_row
+            .as_object()
+            .map(|row| row.keys().cloned().collect())
+            .unwrap_or_default(),
+    };
+    let headers = match args.get("headers") {
+        Some(headers) => string_list(headers, "headers")?,
+        None => columns.clone(),
+    };
+    if headers.len() != columns.len() {
+        return Err(tera::Error::msg(
+            "markdown_table `headers` should have one header for each column",
+        ));
+    }
+
+    let mut table = String::new();
+    table_row(&mut table, headers.iter().map(|header| cell(header)));
+    table_row(&mut table, columns.iter().map(|_| "---".to_string()));
+    for row in rows {
+        table_row(
+            &mut table,
+            columns.iter().map(|column| match row.get(column) {
+                Some(Value::String(value)) => cell(value),
+                Some(Value::Null) | None => "".to_string(),
+                Some(value) => cell(&value.to_string()),
+            }),
+        );
+    }
+
+    Ok(Value::String(table))
+}
+
+fn string_list(value: &Value, name: &str) -> tera::Result<Vec<String>> {
+    value
+        .as_array()
+        .and_then(|values| {
+            values
+                .iter()
+                .map(|value| value.as_str().map(|value| value.to_string()))
+                .collect()
+        })
+        .ok_or_else(|| tera::Error::msg(format!("`{}` should be a list of strings", name)))
+}
+
+/// Cell values can't contain pipes or new lines, as they would break the table
+fn cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn table_row(table: &mut String, cells: impl Iterator<Item = String>) {
+    table.push('|');
+    for cell in cells {
+        table.push(' ');
+        table.push_str(&cell);
+        table.push_str(" |");
+    }
+    table.push('\n');
+}
+
+/// How long ago a date or time was, e.g. `3 hours ago`, or `in 2 days` if it's in the future.
+///
+/// Usage: `{{ sensor.last_reading | time_ago(locale=recipient.locale) }}`
+/// Accepts dates and times as returned by postgres, e.g. `2024-03-01T10:00:00`, or a unix timestamp in seconds.
+/// Times without a timezone are assumed to be UTC.
+fn time_ago(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let datetime = parse_datetime(value).map_err(tera::Error::msg)?;
+    Ok(Value::String(time_ago_from(
+        datetime,
+        Utc::now(),
+        locale_arg(args),
+    )))
+}
+
+fn time_ago_from(datetime: DateTime<Utc>, now: DateTime<Utc>, locale: Locale) -> String {
+    locale.time_ago(now.signed_duration_since(datetime).num_seconds())
+}
+
+/// The `locale` argument, usually `recipient.locale`. English if it isn't set.
+fn locale_arg(args: &HashMap<String, Value>) -> Locale {
+    Locale::from_tag(args.get("locale").and_then(|locale| locale.as_str()))
+}
+
+fn parse_datetime(value: &Value) -> Result<DateTime<Utc>, String> {
+    let invalid = || format!("Invalid date or time {}", value);
+
+    let datetime = match value {
+        Value::Number(timestamp) => timestamp
+            .as_i64()
+            .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single()),
+        Value::String(datetime) => {
+            let datetime = datetime.trim();
+            DateTime::parse_from_rfc3339(datetime)
+                .map(|datetime| datetime.with_timezone(&Utc))
+                .ok()
+                .or_else(|| {
+                    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+                        .iter()
+                        .find_map(|format| NaiveDateTime::parse_from_str(datetime, format).ok())
+                        .or_else(|| {
+                            NaiveDate::parse_from_str(datetime, "%Y-%m-%d")
+                                .ok()
+                                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                        })
+                        .map(|datetime| Utc.from_utc_datetime(&datetime))
+                })
+        }
+        _ => None,
+    };
+
+    datetime.ok_or_else(invalid)
+}
+
+/// A number of seconds as a duration that's easy to read, e.g. `3 hours`.
+///
+/// Usage: `{{ seconds_since_last_reading | humanise_duration(locale=recipient.locale) }}`
+/// Only the largest unit is shown, so 5400 seconds is `1 hour`.
+fn humanise_duration(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let seconds = as_number(value).ok_or_else(|| {
+        tera::Error::msg(format!(
+            "humanise_duration expects a number of seconds, not {}",
+            value
+        ))
+    })?;
+    Ok(Value::String(locale_arg(args).duration(seconds as i64)))
+}
+
+/// Formats a number with thousands separators, e.g. `1234567.891` as `1,234,567.89`.
+///
+/// Usage: `{{ stock | format_number(decimals=2, locale=recipient.locale) }}`
+/// `decimals` defaults to 0, and is at most 20. The separators are the locale's, `separator` can be used to choose a different thousands separator.
+fn format_number(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let number = as_number(value).ok_or_else(|| {
+        tera::Error::msg(format!("format_number expects a number, not {}", value))
+    })?;
+    let decimals = args
+        .get("decimals")
+        .and_then(|decimals| decimals.as_u64())
+        .unwrap_or(0)
+        .min(MAX_DECIMALS) as usize;
+    let locale = locale_arg(args);
+    let separator = args
+        .get("separator")
+        .and_then(|separator| separator.as_str())
+        .unwrap_or_else(|| locale.thousands_separator());
+
+    let formatted = format!("{:.*}", decimals, number.abs());
+    let (whole, fraction) = match formatted.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (formatted.as_str(), None),
+    };
+
+    let mut result = String::new();
+    // Rounding can give -0, which should just be 0
+    if number < 0.0 && formatted.chars().any(|digit| ('1'..='9').contains(&digit)) {
+        result.push('-');
+    }
+    for (index, digit) in whole.chars().enumerate() {
+        if index > 0 && (whole.len() - index) % 3 == 0 {
+            result.push_str(separator);
+        }
+        result.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        result.push_str(locale.decimal_point());
+        result.push_str(fraction);
+    }
+
+    Ok(Value::String(result))
+}
+
+/// Formats a date in the locale's language, e.g. `1 March 2024` or `1 mars 2024`.
+///
+/// Usage: `{{ last_data_time | format_date(locale=recipient.locale, time=true) }}`
+/// Set `time` to also show the time, e.g. `1 March 2024 10:00`. Accepts the same dates and times as `time_ago`.
+fn format_date(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let datetime = parse_datetime(value).map_err(tera::Error::msg)?;
+    let locale = locale_arg(args);
+
+    let mut formatted = format!(
+        "{} {} {}",
+        datetime.day(),
+        locale.month_name(datetime.month()),
+        datetime.year()
+    );
+    if args.get("time").and_then(|time| time.as_bool()) == Some(true) {
+        formatted.push_str(&datetime.format(" %H:%M").to_string());
+    }
+
+    Ok(Value::String(formatted))
+}
+
+/// Converts a temperature between `C`, `F` and `K`.
+///
+/// Usage: `{{ temperature | convert_temperature(from="C", to="F") | round(precision=1) }}`
+/// `from` defaults to `C` and `to` defaults to `F`.
+fn convert_temperature(value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let temperature = as_number(value).ok_or_else(|| {
+        tera::Error::msg(format!(
+            "convert_temperature expects a number, not {}",
+            value
+        ))
+    })?;
+    let unit = |name: &str, default: &str| -> tera::Result<String> {
+        let unit = args
+            .get(name)
+            .and_then(|unit| unit.as_str())
+            .unwrap_or(default)
+            .trim_start_matches('°')
+            .to_uppercase();
+        match unit.as_str() {
+            "C" | "F" | "K" => Ok(unit),
+            _ => Err(tera::Error::msg(format!(
+                "Unknown temperature unit `{}`, expected `C`, `F` or `K`",
+                unit
+            ))),
+        }
+    };
+
+    let celsius = match unit("from", "C")?.as_str() {
+        "F" => (temperature - 32.0) * 5.0 / 9.0,
+        "K" => temperature - 273.15,
+        _ => temperature,
+    };
+    let converted = match unit("to", "F")?.as_str() {
+        "F" => celsius * 9.0 / 5.0 + 32.0,
+        "K" => celsius + 273.15,
+        _ => celsius,
+    };
+
+    Ok(Value::from(converted))
+}
+
+/// The current date and time in a timezone.
+///
+/// Usage: `{{ local_now(timezone="Pacific/Auckland", format="%d/%m/%Y %H:%M") }}`
+/// `timezone` defaults to UTC, and `format` (a chrono format string) to `%Y-%m-%d %H:%M`.
+fn local_now(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    format_local_time(Utc::now(), args).map(Value::String)
+}
+
+fn format_local_time(now: DateTime<Utc>, args: &HashMap<String, Value>) -> tera::Result<String> {
+    let timezone = match args.get("timezone").and_then(|timezone| timezone.as_str()) {
+        Some(timezone) => parse_timezone(timezone).map_err(tera::Error::msg)?,
+        None => chrono_tz::UTC,
+    };
+    let format = args
+        .get("format")
+        .and_then(|format| format.as_str())
+        .unwrap_or(DEFAULT_DATETIME_FORMAT);
+
+    // Writing rather than using to_string, as an invalid format would panic
+    let mut formatted = String::new();
+    write!(formatted, "{}", now.with_timezone(&timezone).format(format))
+        .map_err(|_| tera::Error::msg(format!("Invalid date format `{}`", format)))?;
+    Ok(formatted)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use tera::Context;
+
+    use super::*;
+
+    fn render(template: &str, context: Value) -> tera::Result<String> {
+        let mut tera = Tera::default();
+        register_template_helpers(&mut tera);
+        tera.render_str(template, &Context::from_value(context).unwrap())
+    }
+
+    fn args(args: Value) -> HashMap<String, Value> {
+        serde_json::from_value(args).unwrap()
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let stock = json!([
+            {"item": "Paracetamol", "stock": 1200, "notes": null},
+            {"item": "Amoxicillin | 250mg", "stock": 0, "notes": "Ordered\nyesterday"}
+        ]);
+
+        assert_eq!(
+            render(
+                r#"{{ stock | markdown_table(columns=["item", "stock", "notes"], headers=["Item", "Stock", "Notes"]) }}"#,
+                json!({ "stock": stock })
+            )
+            .unwrap(),
+            "| Item | Stock | Notes |\n| --- | --- | --- |\n| Paracetamol | 1200 |  |\n| Amoxicillin \\| 250mg | 0 | Ordered yesterday |\n"
+        );
+
+        // Columns default to all of them
+        assert_eq!(
+            render(
+                "{{ stock | markdown_table }}",
+                json!({ "stock": [{"stock": 3, "item": "Paracetamol"}] })
+            )
+            .unwrap(),
+            "| item | stock |\n| --- | --- |\n| Paracetamol | 3 |\n"
+        );
+
+        assert_eq!(
+            render("{{ stock | markdown_table }}", json!({ "stock": [] })).unwrap(),
+            ""
+        );
+        assert!(render(
+            r#"{{ stock | markdown_table(columns=["item"], headers=["Item", "Stock"]) }}"#,
+            json!({ "stock": stock })
+        )
+        .is_err());
+        assert!(render("{{ stock | markdown_table }}", json!({ "stock": 3 })).is_err());
+    }
+
+    #[test]
+    fn test_time_ago() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let ago =
+            |value: Value| time_ago_from(parse_datetime(&value).unwrap(), now, Locale::English);
+
+        assert_eq!(ago(json!("2024-03-01T11:59:30")), "just now");
+        assert_eq!(ago(json!("2024-03-01T08:59:59.500")), "3 hours ago");
+        assert_eq!(ago(json!("2024-03-01 11:59:00")), "1 minute ago");
+        assert_eq!(ago(json!("2024-02-28")), "2 days ago");
+        assert_eq!(ago(json!("2024-03-02T00:00:00+13:00")), "1 hour ago");
+        assert_eq!(ago(json!("2024-03-01T14:00:00Z")), "in 2 hours");
+        assert_eq!(ago(json!(now.timestamp() - 600)), "10 minutes ago");
+        assert_eq!(
+            time_ago_from(now - chrono::Duration::hours(3), now, Locale::French),
+            "il y a 3 heures"
+        );
+
+        assert!(parse_datetime(&json!("yesterday")).is_err());
+        assert!(render("{{ value | time_ago }}", json!({ "value": true })).is_err());
+        assert!(
+            render("{{ value | time_ago }}", json!({ "value": "2024-03-01" }))
+                .unwrap()
+                .ends_with(" ago")
+        );
+    }
+
+    #[test]
+    fn test_humanise_duration() {
+        let humanise = |seconds: Value| {
+            render(
+                "{{ seconds | humanise_duration }}",
+                json!({ "seconds": seconds }),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(humanise(json!(1)), "1 second");
+        assert_eq!(humanise(json!(45)), "45 seconds");
+        assert_eq!(humanise(json!(5400)), "1 hour");
+        assert_eq!(humanise(json!(10800.5)), "3 hours");
+        assert_eq!(humanise(json!("172800")), "2 days");
+        assert_eq!(humanise(json!(-120)), "2 minutes");
+        assert!(render(
+            "{{ seconds | humanise_duration }}",
+            json!({ "seconds": "soon" })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_format_number() {
+        let format = |number: Value, args: &str| {
+            render(
+                &format!("{{{{ number | format_number({}) }}}}", args),
+                json!({ "number": number }),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(format(json!(0), ""), "0");
+        assert_eq!(format(json!(999), ""), "999");
+        assert_eq!(format(json!(1000), ""), "1,000");
+        assert_eq!(format(json!(1234567.891), "decimals=2"), "1,234,567.89");
+        assert_eq!(format(json!(-1234567), ""), "-1,234,567");
+        assert_eq!(format(json!("12345.6"), "separator=\" \""), "12 346");
+        assert_eq!(format(json!(-0.4), ""), "0");
+        assert_eq!(
+            format(json!(1.5), "decimals=1000000000"),
+            "1.50000000000000000000"
+        );
+        assert_eq!(
+            format(json!(1234567.891), "decimals=2, locale=\"fr\""),
+            "1\u{202f}234\u{202f}567,89"
+        );
+        assert_eq!(
+            format(json!(1234.5), "decimals=1, locale=\"lo\""),
+            "1.234,5"
+        );
+        assert!(render("{{ number | format_number }}", json!({ "number": "lots" })).is_err());
+    }
+
+    #[test]
+    fn test_format_date() {
+        let format = |args: &str| {
+            render(
+                &format!("{{{{ date | format_date({}) }}}}", args),
+                json!({ "date": "2023-07-17T17:04:00" }),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(format(""), "17 July 2023");
+        assert_eq!(format("time=true"), "17 July 2023 17:04");
+        assert_eq!(format("locale=\"fr-CA\""), "17 juillet 2023");
+        assert_eq!(format("locale=\"lo\", time=true"), "17 ກໍລະກົດ 2023 17:04");
+        // No locale, e.g. a recipient that hasn't set one
+        assert_eq!(
+            render(
+                "{{ date | format_date(locale=recipient.locale) }}",
+                json!({ "date": "2023-07-17", "recipient": { "locale": null } })
+            )
+            .unwrap(),
+            "17 July 2023"
+        );
+    }
+
+    #[test]
+    fn test_convert_temperature() {
+        let convert = |temperature: Value, args: Value| {
+            convert_temperature(&temperature, &super::test::args(args))
+                .unwrap()
+                .as_f64()
+                .unwrap()
+        };
+
+        assert!((convert(json!(100), json!({})) - 212.0).abs() < 1e-9);
+        assert!((convert(json!(-40), json!({ "to": "F" })) + 40.0).abs() < 1e-9);
+        assert!((convert(json!("50"), json!({ "from": "F", "to": "C" })) - 10.0).abs() < 1e-9);
+        assert!((convert(json!(0), json!({ "to": "°k" })) - 273.15).abs() < 1e-9);
+        assert!(convert_temperature(&json!(0), &args(json!({ "to": "X" }))).is_err());
+
+        assert_eq!(
+            render(
+                "{{ temperature | convert_temperature | round(precision=1) }}",
+                json!({ "temperature": 4.5 })
+            )
+            .unwrap(),
+            "40.1"
+        );
+    }
+
+    #[test]
+    fn test_local_now() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            format_local_time(now, &args(json!({}))).unwrap(),
+            "2024-03-01 12:00"
+        );
+        assert_eq!(
+            format_local_time(
+                now,
+                &args(json!({ "timezone": "Pacific/Auckland", "format": "%d/%m/%Y %H:%M %Z" }))
+            )
+            .unwrap(),
+            "02/03/2024 01:00 NZDT"
+        );
+        assert!(format_local_time(now, &args(json!({ "timezone": "Moon/Base" }))).is_err());
+        assert!(format_local_time(now, &args(json!({ "format": "%Q" }))).is_err());
+        assert!(render("{{ local_now(timezone=\"UTC\") }}", json!({})).is_ok());
+    }
+}
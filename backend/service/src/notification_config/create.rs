@@ -7,6 +7,7 @@ use repository::{
 use crate::{audit_log::audit_log_entry, service_provider::ServiceContext};
 
 use super::{
+    parameter_schema::validate_parameters,
     query::{get_notification_config, NotificationConfig},
     validate::check_notification_config_does_not_exist,
     ModifyNotificationConfigError,
@@ -17,6 +18,8 @@ pub struct CreateNotificationConfig {
     pub id: String,
     pub title: String,
     pub kind: NotificationConfigKind,
+    /// JSON array of parameter definitions, see `parameter_schema::ParameterDefinition`
+    pub parameter_schema: Option<String>,
 }
 
 pub fn create_notification_config(
@@ -28,6 +31,7 @@ pub fn create_notification_config(
         .transaction_sync(|connection| {
             validate(&new_config, connection)?;
             let new_config_row = generate(new_config.clone())?;
+            validate_parameters(connection, &new_config_row)?;
 
             NotificationConfigRowRepository::new(connection).insert_one(&new_config_row)?;
 
@@ -57,7 +61,12 @@ pub fn validate(
 }
 
 pub fn generate(
-    CreateNotificationConfig { id, title, kind }: CreateNotificationConfig,
+    CreateNotificationConfig {
+        id,
+        title,
+        kind,
+        parameter_schema,
+    }: CreateNotificationConfig,
 ) -> Result<NotificationConfigRow, ModifyNotificationConfigError> {
     Ok(NotificationConfigRow {
         id,
@@ -72,5 +81,6 @@ pub fn generate(
         sql_recipient_list_ids: "[]".to_string(),
         last_run_datetime: None,
         next_due_datetime: None,
+        parameter_schema: parameter_schema.unwrap_or_else(|| "[]".to_string()),
     })
 }
@@ -0,0 +1,512 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate};
+use repository::{
+    EqualFilter, NotificationConfigKind, NotificationConfigRow, NotificationQueryFilter,
+    NotificationQueryRepository, StorageConnection,
+};
+use serde::{Deserialize, Serialize};
+use tera::{
+    ast::{Expr, ExprVal, Node},
+    Tera,
+};
+
+use super::ModifyNotificationConfigError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterType {
+    String,
+    Number,
+    Date, // YYYY-MM-DD or an RFC 3339 date time
+    Enum,
+}
+
+/// Describes a parameter that can be set for a notification config, e.g.
+/// `{"name": "store", "type": "enum", "required": true, "options": ["Store A", "Store B"]}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParameterDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub parameter_type: ParameterType,
+    #[serde(default)]
+    pub required: bool,
+    /// The allowed values for an `enum` parameter
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+impl ParameterDefinition {
+    fn validate_value(&self, value: &serde_json::Value) -> Result<(), String> {
+        let is_valid = match self.parameter_type {
+            ParameterType::String => value.is_string() || value.is_number(),
+            ParameterType::Number => match value {
+                serde_json::Value::Number(_) => true,
+                serde_json::Value::String(value) => value.trim().parse::<f64>().is_ok(),
+                _ => false,
+            },
+            ParameterType::Date => match value.as_str() {
+                Some(value) => {
+                    NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+                        || DateTime::parse_from_rfc3339(value).is_ok()
+                }
+                None => false,
+            },
+            ParameterType::Enum => match value.as_str() {
+                Some(value) => self.options.iter().any(|option| option == value),
+                None => false,
+            },
+        };
+
+        match is_valid {
+            true => Ok(()),
+            false => Err(match self.parameter_type {
+                ParameterType::Enum => format!(
+                    "Parameter `{}` should be one of {}, got {}",
+                    self.name,
+                    self.options.join(", "),
+                    value
+                ),
+                parameter_type => format!(
+                    "Parameter `{}` should be a {:?}, got {}",
+                    self.name, parameter_type, value
+                ),
+            }),
+        }
+    }
+}
+
+pub fn parse_parameter_schema(schema: &str) -> Result<Vec<ParameterDefinition>, String> {
+    if schema.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let schema: Vec<ParameterDefinition> = serde_json::from_str(schema)
+        .map_err(|e| format!("Unable to parse parameter schema: {}", e))?;
+
+    let mut names = HashSet::new();
+    for definition in &schema {
+        if definition.name.trim().is_empty() {
+            return Err("Parameter names can't be empty".to_string());
+        }
+        if !names.insert(definition.name.as_str()) {
+            return Err(format!("Parameter `{}` is defined twice", definition.name));
+        }
+        if definition.parameter_type == ParameterType::Enum && definition.options.is_empty() {
+            return Err(format!(
+                "Enum parameter `{}` needs at least one option",
+                definition.name
+            ));
+        }
+    }
+
+    Ok(schema)
+}
+
+/// Checks a parameter set against the schema, returning a description of each problem found.
+/// If there is no schema, any parameters are allowed.
+pub fn validate_parameter_set(
+    schema: &[ParameterDefinition],
+    parameters: &HashMap<String, serde_json::Value>,
+) -> Vec<String> {
+    if schema.is_empty() {
+        return vec![];
+    }
+
+    let mut errors = vec![];
+    for definition in schema {
+        match parameters.get(&definition.name) {
+            None | Some(serde_json::Value::Null) => {
+                if definition.required {
+                    errors.push(format!("Parameter `{}` is required", definition.name));
+                }
+            }
+            Some(serde_json::Value::String(value)) if value.is_empty() => {
+                if definition.required {
+                    errors.push(format!("Parameter `{}` is required", definition.name));
+                }
+            }
+            Some(value) => {
+                if let Err(error) = definition.validate_value(value) {
+                    errors.push(error);
+                }
+            }
+        }
+    }
+
+    // Unknown parameters are most likely a typo
+    let mut unknown_parameters: Vec<&String> = parameters
+        .keys()
+        .filter(|name| !schema.iter().any(|definition| &definition.name == *name))
+        .collect();
+    unknown_parameters.sort();
+    for name in unknown_parameters {
+        errors.push(format!("Unknown parameter `{}`", name));
+    }
+
+    errors
+}
+
+/// The parts of a scheduled notification's configuration data that can use parameters
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ScheduledConfigurationData {
+    #[serde(default)]
+    subject_template: String,
+    #[serde(default)]
+    body_template: String,
+    #[serde(default)]
+    email_body_template: Option<String>,
+    #[serde(default)]
+    telegram_body_template: Option<String>,
+    #[serde(default)]
+    send_condition: Option<String>,
+    #[serde(default)]
+    notification_query_ids: Vec<String>,
+}
+
+/// Validates the parameter schema and parameters for a notification config before it is saved.
+/// If there is a schema, the templates and queries of a scheduled notification are also checked to
+/// make sure they only use variables the schema (or a query) provides.
+pub fn validate_parameters(
+    connection: &StorageConnection,
+    config: &NotificationConfigRow,
+) -> Result<(), ModifyNotificationConfigError> {
+    let schema = parse_parameter_schema(&config.parameter_schema)
+        .map_err(ModifyNotificationConfigError::BadUserInput)?;
+    if schema.is_empty() {
+        return Ok(());
+    }
+
+    let mut errors = vec![];
+
+    // New configs are created with an empty object rather than an array
+    let parameter_sets: Vec<HashMap<String, serde_json::Value>> = match config.parameters.trim() {
+        "" | "{}" => vec![],
+        parameters => serde_json::from_str(parameters).map_err(|e| {
+            ModifyNotificationConfigError::BadUserInput(format!(
+                "Unable to parse parameters (expecting an array of objects): {}",
+                e
+            ))
+        })?,
+    };
+    for (index, parameters) in parameter_sets.iter().enumerate() {
+        for error in validate_parameter_set(&schema, parameters) {
+            errors.push(format!("Parameter set {}: {}", index + 1, error));
+        }
+    }
+
+    if config.kind == NotificationConfigKind::Scheduled {
+        errors.extend(check_scheduled_variables(connection, config, &schema)?);
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(ModifyNotificationConfigError::BadUserInput(
+            errors.join("\n"),
+        )),
+    }
+}
+
+fn check_scheduled_variables(
+    connection: &StorageConnection,
+    config: &NotificationConfigRow,
+    schema: &[ParameterDefinition],
+) -> Result<Vec<String>, ModifyNotificationConfigError> {
+    let configuration_data: ScheduledConfigurationData =
+        serde_json::from_str(&config.configuration_data).unwrap_or_default();
+
+    let queries = NotificationQueryRepository::new(connection).query_by_filter(
+        NotificationQueryFilter::new().id(EqualFilter::equal_any(
+            configuration_data.notification_query_ids.clone(),
+        )),
+    )?;
+
+    // The recipient is always available, and is added to the query parameters when personalising per recipient
+    let mut query_variables: HashSet<String> = schema
+        .iter()
+        .map(|definition| definition.name.clone())
+        .collect();
+    query_variables.insert("recipient".to_string());
+
+    let mut errors = vec![];
+    for query in &queries {
+        errors.extend(check_variables(
+            &format!("Query {}", query.reference_name),
+            &query.query,
+            &query_variables,
+        ));
+    }
+
+    // Templates can also use the query results
+    let mut template_variables = query_variables;
+    template_variables.extend(queries.into_iter().map(|query| query.reference_name));
+
+    errors.extend(check_variables(
+        "Subject template",
+        &configuration_data.subject_template,
+        &template_variables,
+    ));
+    errors.extend(check_variables(
+        "Body template",
+        &configuration_data.body_template,
+        &template_variables,
+    ));
+    for (source, channel_template) in [
+        (
+            "Email body template",
+            &configuration_data.email_body_template,
+        ),
+        (
+            "Telegram body template",
+            &configuration_data.telegram_body_template,
+        ),
+    ] {
+        if let Some(channel_template) = channel_template {
+            errors.extend(check_variables(
+                source,
+                channel_template,
+                &template_variables,
+            ));
+        }
+    }
+    if let Some(send_condition) = &configuration_data.send_condition {
+        let send_condition = send_condition.trim();
+        let expression = send_condition
+            .strip_prefix("{{")
+            .and_then(|condition| condition.strip_suffix("}}"))
+            .unwrap_or(send_condition);
+        if !expression.trim().is_empty() {
+            errors.extend(check_variables(
+                "Send condition",
+                &format!("{{{{ {} }}}}", expression),
+                &template_variables,
+            ));
+        }
+    }
+
+    Ok(errors)
+}
+
+fn check_variables(source: &str, template: &str, known_variables: &HashSet<String>) -> Vec<String> {
+    match template_variables(template) {
+        Ok(variables) => variables
+            .into_iter()
+            .filter(|variable| !known_variables.contains(variable))
+            .map(|variable| {
+                format!(
+                    "{} uses `{}`, which isn't a parameter or query",
+                    source, variable
+                )
+            })
+            .collect(),
+        Err(e) => vec![format!("{} could not be parsed: {}", source, e)],
+    }
+}
+
+/// Finds the top level variables used by a template, ignoring any it defines itself e.g. in a for loop
+pub fn template_variables(template: &str) -> Result<BTreeSet<String>, tera::Error> {
+    let mut tera = Tera::default();
+    tera.add_raw_template("template", template)?;
+
+    let mut used = BTreeSet::new();
+    let mut defined = HashSet::new();
+    defined.insert("loop".to_string());
+    defined.insert("__tera_context".to_string());
+    collect_nodes(&tera.get_template("template")?.ast, &mut used, &mut defined);
+
+    Ok(used
+        .into_iter()
+        .filter(|variable| !defined.contains(variable))
+        .collect())
+}
+
+fn collect_nodes(nodes: &[Node], used: &mut BTreeSet<String>, defined: &mut HashSet<String>) {
+    for node in nodes {
+        match node {
+            Node::VariableBlock(_, expr) => collect_expr(expr, used),
+            Node::Set(_, set) => {
+                defined.insert(set.key.clone());
+                collect_expr(&set.value, used);
+            }
+            Node::FilterSection(_, section, _) => {
+                section
+                    .filter
+                    .args
+                    .values()
+                    .for_each(|arg| collect_expr(arg, used));
+                collect_nodes(&section.body, used, defined);
+            }
+            Node::Block(_, block, _) => collect_nodes(&block.body, used, defined),
+            Node::Forloop(_, forloop, _) => {
+                defined.insert(forloop.value.clone());
+                if let Some(key) = &forloop.key {
+                    defined.insert(key.clone());
+                }
+                collect_expr(&forloop.container, used);
+                collect_nodes(&forloop.body, used, defined);
+                if let Some(empty_body) = &forloop.empty_body {
+                    collect_nodes(empty_body, used, defined);
+                }
+            }
+            Node::If(if_node, _) => {
+                for (_, condition, body) in &if_node.conditions {
+                    collect_expr(condition, used);
+                    collect_nodes(body, used, defined);
+                }
+                if let Some((_, body)) = &if_node.otherwise {
+                    collect_nodes(body, used, defined);
+                }
+            }
+            Node::MacroDefinition(_, definition, _) => {
+                defined.extend(definition.args.keys().cloned());
+                collect_nodes(&definition.body, used, defined);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_expr(expr: &Expr, used: &mut BTreeSet<String>) {
+    collect_expr_val(&expr.val, used);
+    for filter in &expr.filters {
+        filter.args.values().for_each(|arg| collect_expr(arg, used));
+    }
+}
+
+fn collect_expr_val(val: &ExprVal, used: &mut BTreeSet<String>) {
+    match val {
+        ExprVal::Ident(ident) => {
+            used.insert(root_variable(ident));
+        }
+        ExprVal::Math(math) => {
+            collect_expr(&math.lhs, used);
+            collect_expr(&math.rhs, used);
+        }
+        ExprVal::Logic(logic) => {
+            collect_expr(&logic.lhs, used);
+            collect_expr(&logic.rhs, used);
+        }
+        ExprVal::Test(test) => {
+            // `is defined` is used to check for optional variables
+            if test.name != "defined" && test.name != "undefined" {
+                used.insert(root_variable(&test.ident));
+            }
+            test.args.iter().for_each(|arg| collect_expr(arg, used));
+        }
+        ExprVal::MacroCall(call) => call.args.values().for_each(|arg| collect_expr(arg, used)),
+        ExprVal::FunctionCall(call) => call.args.values().for_each(|arg| collect_expr(arg, used)),
+        ExprVal::Array(items) => items.iter().for_each(|item| collect_expr(item, used)),
+        ExprVal::StringConcat(concat) => concat
+            .values
+            .iter()
+            .for_each(|value| collect_expr_val(value, used)),
+        ExprVal::In(in_expr) => {
+            collect_expr(&in_expr.lhs, used);
+            collect_expr(&in_expr.rhs, used);
+        }
+        ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => {}
+    }
+}
+
+/// `stores.0.name` and `stores[0].name` both use the `stores` variable
+fn root_variable(ident: &str) -> String {
+    ident.split(['.', '[']).next().unwrap_or(ident).to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Vec<ParameterDefinition> {
+        parse_parameter_schema(
+            r#"[
+                {"name": "store", "type": "enum", "required": true, "options": ["A", "B"]},
+                {"name": "days", "type": "number"},
+                {"name": "from", "type": "date"},
+                {"name": "region", "type": "string", "required": true}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn parameters(value: serde_json::Value) -> HashMap<String, serde_json::Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_parameter_schema() {
+        assert_eq!(schema().len(), 4);
+        assert_eq!(parse_parameter_schema(""), Ok(vec![]));
+
+        assert!(parse_parameter_schema(r#"[{"name": "a", "type": "colour"}]"#).is_err());
+        assert!(parse_parameter_schema(r#"[{"name": "a", "type": "enum"}]"#).is_err());
+        assert!(parse_parameter_schema(
+            r#"[{"name": "a", "type": "string"}, {"name": "a", "type": "number"}]"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_validate_parameter_set() {
+        let schema = schema();
+
+        let valid =
+            parameters(json!({"store": "A", "days": "7", "from": "2024-03-01", "region": "North"}));
+        assert_eq!(
+            validate_parameter_set(&schema, &valid),
+            Vec::<String>::new()
+        );
+
+        // Numbers from a SQL parameter query don't need to be strings
+        let valid = parameters(json!({"store": "B", "days": 7, "region": "North"}));
+        assert_eq!(
+            validate_parameter_set(&schema, &valid),
+            Vec::<String>::new()
+        );
+
+        let invalid = parameters(
+            json!({"store": "C", "days": "a week", "from": "yesterday", "regoin": "North"}),
+        );
+        assert_eq!(
+            validate_parameter_set(&schema, &invalid),
+            vec![
+                "Parameter `store` should be one of A, B, got \"C\"".to_string(),
+                "Parameter `days` should be a Number, got \"a week\"".to_string(),
+                "Parameter `from` should be a Date, got \"yesterday\"".to_string(),
+                "Parameter `region` is required".to_string(),
+                "Unknown parameter `regoin`".to_string(),
+            ]
+        );
+
+        // Without a schema anything goes
+        assert!(validate_parameter_set(&[], &invalid).is_empty());
+    }
+
+    #[test]
+    fn test_template_variables() {
+        let template = r#"{% set total = 0 %}
+{{ recipient.name }}, stock for {{ store | upper }}:
+{% for row in stock_levels %}{{ loop.index }}. {{ row.item }} {{ total }}{% endfor %}
+{% if low_stock[0] and days > 7 %}{{ chart(data=low_stock, x="item", y="days") }}{% endif %}
+{% if optional is defined %}{{ optional }}{% endif %}"#;
+
+        let variables: Vec<String> = template_variables(template).unwrap().into_iter().collect();
+        assert_eq!(
+            variables,
+            vec![
+                "days",
+                "low_stock",
+                "optional",
+                "recipient",
+                "stock_levels",
+                "store"
+            ]
+        );
+
+        assert!(template_variables("{{ unclosed").is_err());
+    }
+}
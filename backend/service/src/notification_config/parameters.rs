@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use crate::service_provider::ServiceContext;
 use crate::notification::NotificationServiceError;
 use super::query::NotificationConfig;
+use super::parameter_schema::{parse_parameter_schema, validate_parameter_set};
 use repository::NotificationQueryRowRepository;
 
 pub fn get_notification_parameters(
@@ -34,6 +35,23 @@ pub fn get_notification_parameters(
                 e, params_string
             ))
         })?;
+
+    // Parameters saved on the config are validated when it is saved, but the parameter query could return anything
+    let schema = parse_parameter_schema(&notification_config.parameter_schema)
+        .map_err(NotificationServiceError::InternalError)?;
+    let mut errors = vec![];
+    for (index, params) in sql_params.iter().enumerate() {
+        for error in validate_parameter_set(&schema, params) {
+            errors.push(format!("Parameter query row {}: {}", index + 1, error));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(NotificationServiceError::InternalError(format!(
+            "Parameter query results don't match the parameter schema: {}",
+            errors.join(", ")
+        )));
+    }
+
     all_params.extend(sql_params);
 
     return Ok(all_params);
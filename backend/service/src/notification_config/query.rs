@@ -28,6 +28,7 @@ pub struct NotificationConfig {
     pub sql_recipient_list_ids: Vec<String>,
     pub last_run_datetime: Option<NaiveDateTime>,
     pub next_due_datetime: Option<NaiveDateTime>,
+    pub parameter_schema: String,
 }
 
 impl From<NotificationConfigRow> for NotificationConfig {
@@ -45,6 +46,7 @@ impl From<NotificationConfigRow> for NotificationConfig {
             sql_recipient_list_ids,
             last_run_datetime,
             next_due_datetime,
+            parameter_schema,
         }: NotificationConfigRow,
     ) -> Self {
         NotificationConfig {
@@ -61,6 +63,7 @@ impl From<NotificationConfigRow> for NotificationConfig {
                 .unwrap_or_default(),
             last_run_datetime,
             next_due_datetime,
+            parameter_schema,
         }
     }
 }
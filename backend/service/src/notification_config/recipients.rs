@@ -59,13 +59,18 @@ pub fn get_notification_targets(
                 let sql_recipients: Vec<NotificationTarget> = sql_recipients
                     .rows
                     .into_iter()
-                    .map(|row| NotificationTarget {
-                        name: row.name,
-                        to_address: row.to_address,
-                        notification_type: repository::NotificationType::from_str(
-                            &row.notification_type,
-                        )
-                        .unwrap_or_default(), // Default to an email address if the notification type is invalid, probably won't work but doesn't hurt to try something
+                    .map(|row| {
+                        let mut extra_fields = sql_recipient_extra_fields(row.data);
+                        NotificationTarget {
+                            name: row.name,
+                            to_address: row.to_address,
+                            notification_type: repository::NotificationType::from_str(
+                                &row.notification_type,
+                            )
+                            .unwrap_or_default(), // Default to an email address if the notification type is invalid, probably won't work but doesn't hurt to try something
+                            locale: sql_recipient_locale(&mut extra_fields),
+                            extra_fields,
+                        }
                     })
                     .collect();
                 notification_targets.extend(sql_recipients);
@@ -82,6 +87,33 @@ pub fn get_notification_targets(
     Ok(notification_targets)
 }
 
+/// Any columns other than the standard recipient ones, e.g. a store id the recipient should see data for
+fn sql_recipient_extra_fields(
+    data: serde_json::Value,
+) -> serde_json::Map<String, serde_json::Value> {
+    match data {
+        serde_json::Value::Object(mut fields) => {
+            for standard_field in ["id", "name", "to_address", "notification_type"] {
+                fields.remove(standard_field);
+            }
+            fields
+        }
+        _ => serde_json::Map::new(),
+    }
+}
+
+/// A `locale` column sets the recipient's locale, rather than being an extra field
+fn sql_recipient_locale(
+    extra_fields: &mut serde_json::Map<String, serde_json::Value>,
+) -> Option<String> {
+    match extra_fields.remove("locale") {
+        Some(serde_json::Value::String(locale)) if !locale.trim().is_empty() => {
+            Some(locale.trim().to_string())
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -225,6 +257,7 @@ mod tests {
             name: String::from("recipient1@example.com"),
             to_address: String::from("recipient1@example.com"),
             notification_type: NotificationType::Email,
+            ..Default::default()
         };
 
         // Call the function being tested
@@ -256,6 +289,7 @@ mod tests {
             name: String::from("name_no_param"),
             to_address: String::from("name_no_param@example.com"),
             notification_type: NotificationType::Email,
+            ..Default::default()
         };
 
         // Call the function being tested
@@ -266,4 +300,36 @@ mod tests {
         assert_eq!(notification_targets.len(), 1);
         assert!(notification_targets.contains(&expected_notification_target));
     }
+
+    #[test]
+    fn test_sql_recipient_extra_fields() {
+        let data = serde_json::json!({
+            "id": "id1",
+            "name": "Name One",
+            "notification_type": "EMAIL",
+            "to_address": "name1@example.com",
+            "store_id": "store1",
+            "locale": "fr"
+        });
+
+        let mut extra_fields = sql_recipient_extra_fields(data);
+        assert_eq!(extra_fields.len(), 2);
+        assert_eq!(extra_fields["store_id"], "store1");
+
+        let locale = sql_recipient_locale(&mut extra_fields);
+        assert_eq!(locale, Some("fr".to_string()));
+        assert_eq!(extra_fields.len(), 1);
+
+        // The extra fields are available alongside the standard ones
+        let target = NotificationTarget {
+            name: "Name One".to_string(),
+            locale,
+            extra_fields,
+            ..Default::default()
+        };
+        let target = serde_json::to_value(target).unwrap();
+        assert_eq!(target["name"], "Name One");
+        assert_eq!(target["locale"], "fr");
+        assert_eq!(target["store_id"], "store1");
+    }
 }
@@ -35,6 +35,7 @@ mod notification_config_create_test {
                     id: mock_coldchain_notification_config_a().id.clone(),
                     title: "some title".to_string(),
                     kind: NotificationConfigKind::ColdChain,
+                    parameter_schema: None,
                 },
             ),
             Err(ModifyNotificationConfigError::NotificationConfigAlreadyExists)
@@ -65,6 +66,7 @@ mod notification_config_create_test {
                 id: new_notification_config_id.clone(),
                 title: "new_notification_config".to_string(),
                 kind: NotificationConfigKind::ColdChain,
+                parameter_schema: None,
             },
         );
 
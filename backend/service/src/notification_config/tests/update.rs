@@ -1,15 +1,19 @@
 #[cfg(test)]
 mod notification_config_update_tests {
     use crate::notification_config::{
-        update::UpdateNotificationConfig, ModifyNotificationConfigError,
+        create::CreateNotificationConfig, update::UpdateNotificationConfig,
+        ModifyNotificationConfigError,
     };
     use crate::service_provider::{ServiceContext, ServiceProvider};
     use crate::test_utils::get_test_settings;
-    use repository::NotificationConfigStatus;
     use repository::{
-        mock::{mock_coldchain_notification_config_a, MockDataInserts},
+        mock::{
+            mock_coldchain_notification_config_a, mock_notification_query_with_params,
+            MockDataInserts,
+        },
         test_db::setup_all,
     };
+    use repository::{NotificationConfigKind, NotificationConfigStatus};
     use std::sync::Arc;
 
     #[actix_rt::test]
@@ -114,4 +118,114 @@ mod notification_config_update_tests {
             NotificationConfigStatus::Enabled
         );
     }
+
+    #[actix_rt::test]
+    async fn notification_config_service_update_parameter_schema() {
+        let (_, _, connection_manager, _) = setup_all(
+            "notification_config_service_update_parameter_schema",
+            MockDataInserts::none().notification_queries(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+        let service = &context.service_provider.notification_config_service;
+
+        service
+            .create_notification_config(
+                &context,
+                CreateNotificationConfig {
+                    id: "scheduled_config".to_string(),
+                    title: "Scheduled".to_string(),
+                    kind: NotificationConfigKind::Scheduled,
+                    parameter_schema: Some(
+                        r#"[{"name": "sensor_limit", "type": "number", "required": true},
+                            {"name": "latest_temperature", "type": "number"}]"#
+                            .to_string(),
+                    ),
+                },
+            )
+            .unwrap();
+
+        // The parameters must match the schema
+        let result = service.update_notification_config(
+            &context,
+            UpdateNotificationConfig {
+                id: "scheduled_config".to_string(),
+                parameters: Some(r#"[{"sensor_limit": "8"}, {"sensr_limit": "8"}]"#.to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            result,
+            Err(ModifyNotificationConfigError::BadUserInput(
+                "Parameter set 2: Parameter `sensor_limit` is required\nParameter set 2: Unknown parameter `sensr_limit`"
+                    .to_string()
+            ))
+        );
+
+        // Templates and queries can only use parameters from the schema, or query results
+        let configuration_data = serde_json::json!({
+            "subjectTemplate": "Limit {{ sensor_limit }}",
+            "bodyTemplate": "{{ query1.0.is_above_limit }} {{ store }}",
+            "notificationQueryIds": [mock_notification_query_with_params().id],
+        });
+        let result = service.update_notification_config(
+            &context,
+            UpdateNotificationConfig {
+                id: "scheduled_config".to_string(),
+                parameters: Some(r#"[{"sensor_limit": "8"}]"#.to_string()),
+                configuration_data: Some(configuration_data.to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            result,
+            Err(ModifyNotificationConfigError::BadUserInput(
+                "Body template uses `store`, which isn't a parameter or query".to_string()
+            ))
+        );
+
+        // Adding the parameter to the schema fixes it
+        let result = service.update_notification_config(
+            &context,
+            UpdateNotificationConfig {
+                id: "scheduled_config".to_string(),
+                parameters: Some(r#"[{"sensor_limit": "8", "store": "A"}]"#.to_string()),
+                configuration_data: Some(configuration_data.to_string()),
+                parameter_schema: Some(
+                    r#"[{"name": "sensor_limit", "type": "number", "required": true},
+                        {"name": "latest_temperature", "type": "number"},
+                        {"name": "store", "type": "string"}]"#
+                        .to_string(),
+                ),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok());
+
+        // The templates for each type of recipient are checked too
+        let configuration_data = serde_json::json!({
+            "subjectTemplate": "Limit {{ sensor_limit }}",
+            "bodyTemplate": "{{ store }}",
+            "telegramBodyTemplate": "*{{ stor }}*",
+        });
+        let result = service.update_notification_config(
+            &context,
+            UpdateNotificationConfig {
+                id: "scheduled_config".to_string(),
+                configuration_data: Some(configuration_data.to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            result,
+            Err(ModifyNotificationConfigError::BadUserInput(
+                "Telegram body template uses `stor`, which isn't a parameter or query".to_string()
+            ))
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use super::{
+    parameter_schema::validate_parameters,
     query::{get_notification_config, NotificationConfig},
     validate::check_notification_config_exists,
     ModifyNotificationConfigError,
@@ -22,6 +23,7 @@ pub struct UpdateNotificationConfig {
     pub recipient_list_ids: Option<Vec<String>>,
     pub sql_recipient_list_ids: Option<Vec<String>>,
     pub next_due_datetime: Option<chrono::NaiveDateTime>,
+    pub parameter_schema: Option<String>,
 }
 
 pub fn update_notification_config(
@@ -34,6 +36,7 @@ pub fn update_notification_config(
             let notification_config_row = validate(connection, &updated_notification_config)?;
             let updated_notification_config_row =
                 generate(updated_notification_config.clone(), notification_config_row)?;
+            validate_parameters(connection, &updated_notification_config_row)?;
             NotificationConfigRowRepository::new(connection)
                 .update_one(&updated_notification_config_row)?;
 
@@ -77,6 +80,7 @@ pub fn generate(
         recipient_list_ids,
         sql_recipient_list_ids,
         next_due_datetime,
+        parameter_schema,
     }: UpdateNotificationConfig,
     current_notification_config_row: NotificationConfigRow,
 ) -> Result<NotificationConfigRow, ModifyNotificationConfigError> {
@@ -96,6 +100,10 @@ pub fn generate(
         new_notification_config_row.parameters = parameters;
     }
 
+    if let Some(parameter_schema) = parameter_schema {
+        new_notification_config_row.parameter_schema = parameter_schema;
+    }
+
     if let Some(recipient_ids) = recipient_ids {
         let recipient_json = serde_json::to_string(&recipient_ids).map_err(|_| {
             ModifyNotificationConfigError::BadUserInput(
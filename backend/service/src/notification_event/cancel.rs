@@ -0,0 +1,56 @@
+use super::{select_notification_events, ModifyNotificationEventError};
+use crate::{audit_log::audit_log_entry, service_provider::ServiceContext};
+use chrono::Utc;
+use repository::{
+    LogType, NotificationEvent, NotificationEventFilter, NotificationEventRow,
+    NotificationEventRowRepository, NotificationEventStatus,
+};
+
+/// Only notifications that are waiting to be sent (or re-tried) can be cancelled
+const CANCELLABLE_STATUSES: [NotificationEventStatus; 2] = [
+    NotificationEventStatus::Queued,
+    NotificationEventStatus::Errored,
+];
+
+/// Stops the selected notifications from being sent.
+/// Returns the notifications that were cancelled.
+pub fn cancel_notification_events(
+    ctx: &ServiceContext,
+    filter: NotificationEventFilter,
+) -> Result<Vec<NotificationEvent>, ModifyNotificationEventError> {
+    let now = Utc::now().naive_utc();
+    let cancelled_events = ctx
+        .connection
+        .transaction_sync(|connection| -> Result<_, ModifyNotificationEventError> {
+            let repo = NotificationEventRowRepository::new(connection);
+            let events: Vec<NotificationEventRow> =
+                select_notification_events(connection, &filter)?
+                    .into_iter()
+                    .filter(|event| CANCELLABLE_STATUSES.contains(&event.status))
+                    .map(|event| NotificationEventRow {
+                        status: NotificationEventStatus::Cancelled,
+                        retry_at: None,
+                        updated_at: now,
+                        ..event
+                    })
+                    .collect();
+
+            for event in &events {
+                repo.update_one(event)?;
+            }
+            Ok(events)
+        })
+        .map_err(|error| error.to_inner_error())?;
+
+    // Audit logging
+    for event in &cancelled_events {
+        audit_log_entry(
+            ctx,
+            LogType::NotificationEventCancelled,
+            Some(event.id.clone()),
+            now,
+        )?;
+    }
+
+    Ok(cancelled_events)
+}
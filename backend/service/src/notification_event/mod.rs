@@ -1,13 +1,22 @@
+pub mod cancel;
 pub mod query;
+pub mod rerender;
+pub mod resend;
 mod tests;
 
-use self::query::{get_notification_event, get_notification_events};
+use self::{
+    cancel::cancel_notification_events,
+    query::{get_notification_event, get_notification_events},
+    rerender::{rerender_notification_events, RerenderNotificationEvents},
+    resend::resend_notification_events,
+};
 
 use super::{ListError, ListResult};
 use crate::{service_provider::ServiceContext, SingleRecordError};
 
 use repository::{
-    NotificationEvent, NotificationEventFilter, NotificationEventSort, PaginationOption,
+    NotificationEvent, NotificationEventFilter, NotificationEventRepository, NotificationEventSort,
+    Pagination, PaginationOption, RepositoryError, StorageConnection,
 };
 
 pub trait NotificationEventServiceTrait: Sync + Send {
@@ -28,7 +37,62 @@ pub trait NotificationEventServiceTrait: Sync + Send {
     ) -> Result<NotificationEvent, SingleRecordError> {
         get_notification_event(ctx, notification_event_id)
     }
+
+    fn resend_notification_events(
+        &self,
+        ctx: &ServiceContext,
+        filter: NotificationEventFilter,
+    ) -> Result<Vec<NotificationEvent>, ModifyNotificationEventError> {
+        resend_notification_events(ctx, filter)
+    }
+
+    fn cancel_notification_events(
+        &self,
+        ctx: &ServiceContext,
+        filter: NotificationEventFilter,
+    ) -> Result<Vec<NotificationEvent>, ModifyNotificationEventError> {
+        cancel_notification_events(ctx, filter)
+    }
+
+    fn rerender_notification_events(
+        &self,
+        ctx: &ServiceContext,
+        input: RerenderNotificationEvents,
+    ) -> Result<Vec<NotificationEvent>, ModifyNotificationEventError> {
+        rerender_notification_events(ctx, input)
+    }
 }
 
 pub struct NotificationEventService {}
 impl NotificationEventServiceTrait for NotificationEventService {}
+
+#[derive(Debug, PartialEq)]
+pub enum ModifyNotificationEventError {
+    DatabaseError(RepositoryError),
+    BadUserInput(String),
+}
+
+impl From<RepositoryError> for ModifyNotificationEventError {
+    fn from(err: RepositoryError) -> Self {
+        ModifyNotificationEventError::DatabaseError(err)
+    }
+}
+
+/// Changing notifications needs a filter, so everything isn't changed by mistake
+fn select_notification_events(
+    connection: &StorageConnection,
+    filter: &NotificationEventFilter,
+) -> Result<Vec<NotificationEvent>, ModifyNotificationEventError> {
+    if filter == &NotificationEventFilter::default() {
+        return Err(ModifyNotificationEventError::BadUserInput(
+            "No notification events selected".to_string(),
+        ));
+    }
+
+    let events = NotificationEventRepository::new(connection).query(
+        Pagination::all(),
+        Some(filter.clone()),
+        None,
+    )?;
+    Ok(events)
+}
@@ -0,0 +1,161 @@
+use super::{select_notification_events, ModifyNotificationEventError};
+use crate::{
+    audit_log::audit_log_entry, notification_template::load::notification_tera,
+    service_provider::ServiceContext,
+};
+use chrono::Utc;
+use repository::{
+    LogType, NotificationEvent, NotificationEventFilter, NotificationEventRow,
+    NotificationEventRowRepository, NotificationEventStatus,
+};
+use tera::{Context, Tera};
+
+/// Notifications that have already been sent (or won't be because they were duplicates) aren't re-rendered
+const RERENDERABLE_STATUSES: [NotificationEventStatus; 4] = [
+    NotificationEventStatus::Queued,
+    NotificationEventStatus::Errored,
+    NotificationEventStatus::Failed,
+    NotificationEventStatus::Cancelled,
+];
+
+pub struct RerenderNotificationEvents {
+    pub filter: NotificationEventFilter,
+    /// If not set, the existing title is kept
+    pub title_template: Option<String>,
+    pub body_template: String,
+    /// Return the re-rendered notifications without saving or sending them
+    pub preview: bool,
+}
+
+/// Renders the selected notifications again with corrected templates, using the template context stored on each notification.
+/// The plugin isn't run again, so the notifications have the same data as when they were first created.
+///
+/// Unless previewing, the notifications are re-queued to be sent. If any of them fail to render nothing is changed,
+/// use `preview` to see the errors.
+pub fn rerender_notification_events(
+    ctx: &ServiceContext,
+    input: RerenderNotificationEvents,
+) -> Result<Vec<NotificationEvent>, ModifyNotificationEventError> {
+    let tera = corrected_tera(ctx, &input.title_template, &input.body_template)?;
+
+    let now = Utc::now().naive_utc();
+    let rendered_events: Vec<NotificationEventRow> =
+        select_notification_events(&ctx.connection, &input.filter)?
+            .into_iter()
+            .filter(|event| RERENDERABLE_STATUSES.contains(&event.status))
+            .map(|event| {
+                let event = match rerender_event(&tera, input.title_template.is_some(), &event) {
+                    Ok((title, message)) => NotificationEventRow {
+                        title,
+                        message,
+                        status: NotificationEventStatus::Queued,
+                        send_attempts: 0,
+                        retry_at: None,
+                        error_message: None,
+                        fallback_reason: None,
+                        parts_sent: 0,
+                        ..event
+                    },
+                    Err(error_message) => NotificationEventRow {
+                        status: NotificationEventStatus::Failed,
+                        error_message: Some(error_message),
+                        ..event
+                    },
+                };
+                NotificationEventRow {
+                    updated_at: now,
+                    ..event
+                }
+            })
+            .collect();
+
+    if input.preview {
+        return Ok(rendered_events);
+    }
+
+    if let Some(failed) = rendered_events
+        .iter()
+        .find(|event| event.status == NotificationEventStatus::Failed)
+    {
+        return Err(ModifyNotificationEventError::BadUserInput(format!(
+            "Notification {} failed to render: {}",
+            failed.id,
+            failed.error_message.clone().unwrap_or_default()
+        )));
+    }
+
+    ctx.connection
+        .transaction_sync(|connection| -> Result<_, ModifyNotificationEventError> {
+            let repo = NotificationEventRowRepository::new(connection);
+            for event in &rendered_events {
+                repo.update_one(event)?;
+            }
+            Ok(())
+        })
+        .map_err(|error| error.to_inner_error())?;
+
+    // Audit logging
+    for event in &rendered_events {
+        audit_log_entry(
+            ctx,
+            LogType::NotificationEventRerendered,
+            Some(event.id.clone()),
+            now,
+        )?;
+    }
+
+    if !rendered_events.is_empty() {
+        ctx.service_provider.notification_dispatch.notify();
+    }
+
+    Ok(rendered_events)
+}
+
+/// Creates a tera instance with the configured templates (so they can still be included), plus the corrected templates
+fn corrected_tera(
+    ctx: &ServiceContext,
+    title_template: &Option<String>,
+    body_template: &str,
+) -> Result<Tera, ModifyNotificationEventError> {
+    let invalid_template = |e: tera::Error| {
+        ModifyNotificationEventError::BadUserInput(format!("Invalid template: {:?}", e))
+    };
+
+    let mut tera = notification_tera(ctx).map_err(invalid_template)?;
+    if let Some(title_template) = title_template {
+        tera.add_raw_template("title_template", title_template)
+            .map_err(invalid_template)?;
+    }
+    tera.add_raw_template("body_template", body_template)
+        .map_err(invalid_template)?;
+
+    Ok(tera)
+}
+
+/// Returns the new title and message, or why they couldn't be rendered
+fn rerender_event(
+    tera: &Tera,
+    has_title_template: bool,
+    event: &NotificationEventRow,
+) -> Result<(Option<String>, String), String> {
+    let context = event
+        .context
+        .as_ref()
+        .ok_or_else(|| "The notification doesn't have a stored template context".to_string())?;
+    let context = serde_json::from_str(context)
+        .map_err(|e| format!("Unable to parse the stored template context: {}", e))?;
+    let context = Context::from_value(context).map_err(|e| format!("{:?}", e))?;
+
+    let title = match has_title_template {
+        true => Some(
+            tera.render("title_template", &context)
+                .map_err(|e| format!("{:?}", e))?,
+        ),
+        false => event.title.clone(),
+    };
+    let message = tera
+        .render("body_template", &context)
+        .map_err(|e| format!("{:?}", e))?;
+
+    Ok((title, message))
+}
@@ -0,0 +1,65 @@
+use super::{select_notification_events, ModifyNotificationEventError};
+use crate::{audit_log::audit_log_entry, service_provider::ServiceContext};
+use chrono::Utc;
+use repository::{
+    LogType, NotificationEvent, NotificationEventFilter, NotificationEventRow,
+    NotificationEventRowRepository, NotificationEventStatus,
+};
+
+/// Only notifications that weren't sent can be resent, anything already sent or still queued is left as it is
+const RESENDABLE_STATUSES: [NotificationEventStatus; 3] = [
+    NotificationEventStatus::Failed,
+    NotificationEventStatus::Errored,
+    NotificationEventStatus::Cancelled,
+];
+
+/// Queues the selected notifications to be sent again, as if they were new.
+/// Returns the notifications that were re-queued.
+pub fn resend_notification_events(
+    ctx: &ServiceContext,
+    filter: NotificationEventFilter,
+) -> Result<Vec<NotificationEvent>, ModifyNotificationEventError> {
+    let now = Utc::now().naive_utc();
+    let resent_events = ctx
+        .connection
+        .transaction_sync(|connection| -> Result<_, ModifyNotificationEventError> {
+            let repo = NotificationEventRowRepository::new(connection);
+            let events: Vec<NotificationEventRow> =
+                select_notification_events(connection, &filter)?
+                    .into_iter()
+                    .filter(|event| RESENDABLE_STATUSES.contains(&event.status))
+                    .map(|event| NotificationEventRow {
+                        status: NotificationEventStatus::Queued,
+                        send_attempts: 0,
+                        retry_at: None,
+                        error_message: None,
+                        fallback_reason: None,
+                        parts_sent: 0,
+                        updated_at: now,
+                        ..event
+                    })
+                    .collect();
+
+            for event in &events {
+                repo.update_one(event)?;
+            }
+            Ok(events)
+        })
+        .map_err(|error| error.to_inner_error())?;
+
+    // Audit logging
+    for event in &resent_events {
+        audit_log_entry(
+            ctx,
+            LogType::NotificationEventResent,
+            Some(event.id.clone()),
+            now,
+        )?;
+    }
+
+    if !resent_events.is_empty() {
+        ctx.service_provider.notification_dispatch.notify();
+    }
+
+    Ok(resent_events)
+}
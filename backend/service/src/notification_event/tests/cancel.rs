@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod notification_event_cancel_test {
+    use std::sync::Arc;
+
+    use repository::{
+        mock::MockDataInserts, test_db::setup_all, EqualFilter, NotificationEventFilter,
+        NotificationEventRow, NotificationEventRowRepository, NotificationEventStatus,
+    };
+
+    use crate::service_provider::{ServiceContext, ServiceProvider};
+    use crate::test_utils::get_test_settings;
+
+    #[actix_rt::test]
+    async fn cancel_notification_events() {
+        let (_, _, connection_manager, _) =
+            setup_all("cancel_notification_events", MockDataInserts::none()).await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+        let service = &context.service_provider.notification_event_service;
+        let repo = NotificationEventRowRepository::new(&context.connection);
+
+        for (id, status) in [
+            ("queued", NotificationEventStatus::Queued),
+            ("errored", NotificationEventStatus::Errored),
+            ("sent", NotificationEventStatus::Sent),
+        ] {
+            repo.insert_one(&NotificationEventRow {
+                id: id.to_string(),
+                status,
+                ..Default::default()
+            })
+            .unwrap();
+        }
+
+        let cancelled = service
+            .cancel_notification_events(
+                &context,
+                NotificationEventFilter::new().id(EqualFilter::equal_any(vec![
+                    "queued".to_string(),
+                    "errored".to_string(),
+                    "sent".to_string(),
+                ])),
+            )
+            .unwrap();
+        assert_eq!(cancelled.len(), 2);
+
+        for id in ["queued", "errored"] {
+            let event = repo.find_one_by_id(id).unwrap().unwrap();
+            assert_eq!(event.status, NotificationEventStatus::Cancelled);
+        }
+        // Already sent, so can't be cancelled
+        let sent = repo.find_one_by_id("sent").unwrap().unwrap();
+        assert_eq!(sent.status, NotificationEventStatus::Sent);
+
+        // Cancelled notifications aren't sent
+        assert!(repo.un_sent().unwrap().is_empty());
+    }
+}
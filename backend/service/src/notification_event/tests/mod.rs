@@ -1,2 +1,8 @@
 #[cfg(test)]
+mod cancel;
+#[cfg(test)]
 mod query;
+#[cfg(test)]
+mod rerender;
+#[cfg(test)]
+mod resend;
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod notification_event_rerender_test {
+    use std::sync::Arc;
+
+    use repository::{
+        mock::MockDataInserts, test_db::setup_all, AuditLogFilter, AuditLogRepository, EqualFilter,
+        NotificationEventFilter, NotificationEventRow, NotificationEventRowRepository,
+        NotificationEventStatus,
+    };
+
+    use crate::notification_event::rerender::RerenderNotificationEvents;
+    use crate::notification_event::ModifyNotificationEventError;
+    use crate::service_provider::{ServiceContext, ServiceProvider};
+    use crate::test_utils::get_test_settings;
+
+    #[actix_rt::test]
+    async fn rerender_notification_events() {
+        let (_, _, connection_manager, _) =
+            setup_all("rerender_notification_events", MockDataInserts::none()).await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+        let service = &context.service_provider.notification_event_service;
+        let repo = NotificationEventRowRepository::new(&context.connection);
+
+        let event = |id: &str, status: NotificationEventStatus, context: Option<&str>| {
+            NotificationEventRow {
+                id: id.to_string(),
+                notification_config_id: Some("stock_report".to_string()),
+                title: Some("Stock report".to_string()),
+                status,
+                send_attempts: 1,
+                error_message: Some("Variable `stock_levels` not found".to_string()),
+                context: context.map(|context| context.to_string()),
+                ..Default::default()
+            }
+        };
+        let stored_context = r#"{"stock": 42, "recipient": {"name": "Clinic A"}}"#;
+        repo.insert_one(&event(
+            "failed",
+            NotificationEventStatus::Failed,
+            Some(stored_context),
+        ))
+        .unwrap();
+        repo.insert_one(&event("no_context", NotificationEventStatus::Failed, None))
+            .unwrap();
+        repo.insert_one(&event(
+            "sent",
+            NotificationEventStatus::Sent,
+            Some(stored_context),
+        ))
+        .unwrap();
+
+        let input =
+            |ids: Vec<&str>, body_template: &str, preview: bool| RerenderNotificationEvents {
+                filter: NotificationEventFilter::new().id(EqualFilter::equal_any(
+                    ids.into_iter().map(|id| id.to_string()).collect(),
+                )),
+                title_template: None,
+                body_template: body_template.to_string(),
+                preview,
+            };
+        let all = vec!["failed", "no_context", "sent"];
+
+        // Template doesn't compile
+        assert!(matches!(
+            service.rerender_notification_events(&context, input(all.clone(), "{{ stock", true)),
+            Err(ModifyNotificationEventError::BadUserInput(_))
+        ));
+
+        // Preview shows what each notification would look like, without changing anything
+        let template = "{{ recipient.name }} has {{ stock }} units";
+        let preview = service
+            .rerender_notification_events(&context, input(all.clone(), template, true))
+            .unwrap();
+        assert_eq!(preview.len(), 2);
+        let failed = preview.iter().find(|e| e.id == "failed").unwrap();
+        assert_eq!(failed.message, "Clinic A has 42 units");
+        assert_eq!(failed.title, Some("Stock report".to_string()));
+        assert_eq!(failed.status, NotificationEventStatus::Queued);
+        let no_context = preview.iter().find(|e| e.id == "no_context").unwrap();
+        assert_eq!(no_context.status, NotificationEventStatus::Failed);
+        assert_eq!(
+            repo.find_one_by_id("failed").unwrap().unwrap().status,
+            NotificationEventStatus::Failed
+        );
+
+        // Nothing is saved if any of the notifications fail to render
+        assert!(matches!(
+            service.rerender_notification_events(&context, input(all, template, false)),
+            Err(ModifyNotificationEventError::BadUserInput(_))
+        ));
+        assert_eq!(
+            repo.find_one_by_id("failed").unwrap().unwrap().status,
+            NotificationEventStatus::Failed
+        );
+
+        // Re-rendered and re-queued
+        let rerendered = service
+            .rerender_notification_events(&context, input(vec!["failed"], template, false))
+            .unwrap();
+        assert_eq!(rerendered.len(), 1);
+        let failed = repo.find_one_by_id("failed").unwrap().unwrap();
+        assert_eq!(failed.message, "Clinic A has 42 units");
+        assert_eq!(failed.status, NotificationEventStatus::Queued);
+        assert_eq!(failed.send_attempts, 0);
+        assert_eq!(failed.error_message, None);
+
+        let logs = AuditLogRepository::new(&context.connection)
+            .query_by_filter(AuditLogFilter::new().record_id(EqualFilter::equal_to("failed")))
+            .unwrap();
+        assert_eq!(logs.len(), 1);
+    }
+}
@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod notification_event_resend_test {
+    use std::sync::Arc;
+
+    use repository::{
+        mock::MockDataInserts, test_db::setup_all, AuditLogFilter, AuditLogRepository, EqualFilter,
+        NotificationEventFilter, NotificationEventRow, NotificationEventRowRepository,
+        NotificationEventStatus,
+    };
+
+    use crate::notification_event::ModifyNotificationEventError;
+    use crate::service_provider::{ServiceContext, ServiceProvider};
+    use crate::test_utils::get_test_settings;
+
+    #[actix_rt::test]
+    async fn resend_notification_events() {
+        let (_, _, connection_manager, _) =
+            setup_all("resend_notification_events", MockDataInserts::none()).await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+        let service = &context.service_provider.notification_event_service;
+        let repo = NotificationEventRowRepository::new(&context.connection);
+
+        let event = |id: &str, status: NotificationEventStatus| NotificationEventRow {
+            id: id.to_string(),
+            notification_config_id: Some("smtp_down".to_string()),
+            status,
+            send_attempts: 3,
+            error_message: Some("Connection refused".to_string()),
+            fallback_reason: Some("Sent as plain text".to_string()),
+            ..Default::default()
+        };
+        repo.insert_one(&event("failed", NotificationEventStatus::Failed))
+            .unwrap();
+        repo.insert_one(&event("errored", NotificationEventStatus::Errored))
+            .unwrap();
+        repo.insert_one(&event("sent", NotificationEventStatus::Sent))
+            .unwrap();
+
+        // Nothing selected
+        assert_eq!(
+            service.resend_notification_events(&context, NotificationEventFilter::new()),
+            Err(ModifyNotificationEventError::BadUserInput(
+                "No notification events selected".to_string()
+            ))
+        );
+
+        // By id
+        let resent = service
+            .resend_notification_events(
+                &context,
+                NotificationEventFilter::new().id(EqualFilter::equal_to("failed")),
+            )
+            .unwrap();
+        assert_eq!(resent.len(), 1);
+        let failed = repo.find_one_by_id("failed").unwrap().unwrap();
+        assert_eq!(failed.status, NotificationEventStatus::Queued);
+        assert_eq!(failed.send_attempts, 0);
+        assert_eq!(failed.error_message, None);
+        assert_eq!(failed.fallback_reason, None);
+        assert_eq!(failed.retry_at, None);
+
+        // By filter, notifications that have already been sent (or re-queued) are left as they are
+        let filter = NotificationEventFilter {
+            notification_config_id: Some(EqualFilter::equal_to("smtp_down")),
+            ..Default::default()
+        };
+        let resent = service
+            .resend_notification_events(&context, filter)
+            .unwrap();
+        assert_eq!(resent.len(), 1);
+        assert_eq!(resent[0].id, "errored");
+        let sent = repo.find_one_by_id("sent").unwrap().unwrap();
+        assert_eq!(sent.status, NotificationEventStatus::Sent);
+        assert_eq!(sent.send_attempts, 3);
+
+        // Audit log
+        let logs = AuditLogRepository::new(&context.connection)
+            .query_by_filter(AuditLogFilter::new().record_id(EqualFilter::equal_to("errored")))
+            .unwrap();
+        assert_eq!(logs.len(), 1);
+    }
+}
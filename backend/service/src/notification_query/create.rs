@@ -21,6 +21,10 @@ pub struct CreateNotificationQuery {
     pub id: String,
     pub name: String,
     pub reference_name: String,
+    /// 0 or None for no timeout
+    pub timeout_seconds: Option<i32>,
+    /// 0 or None for no row limit
+    pub max_rows: Option<i32>,
 }
 
 pub fn create_notification_query(
@@ -63,6 +67,18 @@ pub fn validate(
         return Err(ModifyNotificationQueryError::InvalidNotificationQueryName);
     }
 
+    if new_notification_query.timeout_seconds.unwrap_or(0) < 0 {
+        return Err(ModifyNotificationQueryError::BadUserInput(
+            "Timeout can't be negative".to_string(),
+        ));
+    }
+
+    if new_notification_query.max_rows.unwrap_or(0) < 0 {
+        return Err(ModifyNotificationQueryError::BadUserInput(
+            "Max rows can't be negative".to_string(),
+        ));
+    }
+
     if !check_notification_query_does_not_exist(&new_notification_query.id, connection)? {
         return Err(ModifyNotificationQueryError::NotificationQueryAlreadyExists);
     }
@@ -91,6 +107,8 @@ pub fn generate(
         id,
         name,
         reference_name,
+        timeout_seconds,
+        max_rows,
     }: CreateNotificationQuery,
 ) -> Result<NotificationQueryRow, ModifyNotificationQueryError> {
     Ok(NotificationQueryRow {
@@ -102,5 +120,7 @@ pub fn generate(
         required_parameters: "[]".to_string(),
         created_at: Utc::now().naive_utc(),
         updated_at: Utc::now().naive_utc(),
+        timeout_seconds: timeout_seconds.filter(|timeout_seconds| *timeout_seconds != 0),
+        max_rows: max_rows.filter(|max_rows| *max_rows != 0),
     })
 }
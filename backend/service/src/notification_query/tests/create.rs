@@ -142,5 +142,45 @@ mod notification_query_create_test {
 
         // NotificationQuery now exists
         assert_eq!(result.name, "new_notification_query");
+        assert_eq!(result.timeout_seconds, None);
+        assert_eq!(result.max_rows, None);
+
+        // Created with a timeout and row limit
+        let new_notification_query_id = uuid();
+        service
+            .create_notification_query(
+                &context,
+                CreateNotificationQuery {
+                    id: new_notification_query_id.clone(),
+                    name: "limited_notification_query".to_string(),
+                    reference_name: "limited_notification_query".to_string(),
+                    timeout_seconds: Some(30),
+                    max_rows: Some(1000),
+                },
+            )
+            .unwrap();
+
+        let result = notification_query_row_repository
+            .find_one_by_id(&new_notification_query_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.timeout_seconds, Some(30));
+        assert_eq!(result.max_rows, Some(1000));
+
+        // Negative limits aren't allowed
+        let result = service.create_notification_query(
+            &context,
+            CreateNotificationQuery {
+                id: uuid(),
+                name: "negative_notification_query".to_string(),
+                reference_name: "negative_notification_query".to_string(),
+                timeout_seconds: Some(-1),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            result,
+            Err(ModifyNotificationQueryError::BadUserInput(_))
+        ));
     }
 }
@@ -195,5 +195,58 @@ mod notification_query_update_tests {
             updated_notification_query.description,
             "A nice new description".to_string()
         );
+
+        // Update timeout and max rows
+        let updated_notification_query = context
+            .service_provider
+            .notification_query_service
+            .update_notification_query(
+                &context,
+                UpdateNotificationQuery {
+                    id: "id1".to_string(),
+                    timeout_seconds: Some(30),
+                    max_rows: Some(1000),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(updated_notification_query.timeout_seconds, Some(30));
+        assert_eq!(updated_notification_query.max_rows, Some(1000));
+
+        // Setting to 0 removes the limits
+        let updated_notification_query = context
+            .service_provider
+            .notification_query_service
+            .update_notification_query(
+                &context,
+                UpdateNotificationQuery {
+                    id: "id1".to_string(),
+                    timeout_seconds: Some(0),
+                    max_rows: Some(0),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(updated_notification_query.timeout_seconds, None);
+        assert_eq!(updated_notification_query.max_rows, None);
+
+        // Negative limits aren't allowed
+        let result = context
+            .service_provider
+            .notification_query_service
+            .update_notification_query(
+                &context,
+                UpdateNotificationQuery {
+                    id: "id1".to_string(),
+                    max_rows: Some(-1),
+                    ..Default::default()
+                },
+            );
+        assert!(matches!(
+            result,
+            Err(ModifyNotificationQueryError::BadUserInput(_))
+        ));
     }
 }
@@ -22,6 +22,10 @@ pub struct UpdateNotificationQuery {
     pub description: Option<String>,
     pub query: Option<String>,
     pub required_parameters: Option<Vec<String>>,
+    /// Set to 0 to remove the timeout
+    pub timeout_seconds: Option<i32>,
+    /// Set to 0 to remove the row limit
+    pub max_rows: Option<i32>,
 }
 
 pub fn update_notification_query(
@@ -66,6 +70,18 @@ pub fn validate(
         }
     }
 
+    if new_notification_query.timeout_seconds.unwrap_or(0) < 0 {
+        return Err(ModifyNotificationQueryError::BadUserInput(
+            "Timeout can't be negative".to_string(),
+        ));
+    }
+
+    if new_notification_query.max_rows.unwrap_or(0) < 0 {
+        return Err(ModifyNotificationQueryError::BadUserInput(
+            "Max rows can't be negative".to_string(),
+        ));
+    }
+
     let notification_query_row =
         match check_notification_query_exists(&new_notification_query.id, connection)? {
             Some(notification_query_row) => notification_query_row,
@@ -99,6 +115,8 @@ pub fn generate(
         description,
         query,
         required_parameters,
+        timeout_seconds,
+        max_rows,
     }: UpdateNotificationQuery,
     current_notification_query_row: NotificationQueryRow,
 ) -> Result<NotificationQueryRow, ModifyNotificationQueryError> {
@@ -132,6 +150,18 @@ pub fn generate(
 
         new_notification_query_row.required_parameters = json_parameters;
     }
+    if let Some(timeout_seconds) = timeout_seconds {
+        new_notification_query_row.timeout_seconds = match timeout_seconds {
+            0 => None,
+            timeout_seconds => Some(timeout_seconds),
+        };
+    }
+    if let Some(max_rows) = max_rows {
+        new_notification_query_row.max_rows = match max_rows {
+            0 => None,
+            max_rows => Some(max_rows),
+        };
+    }
 
     Ok(new_notification_query_row)
 }
@@ -0,0 +1,35 @@
+pub mod query;
+mod record;
+mod tests;
+
+pub use self::record::*;
+
+use self::query::{get_notification_run, get_notification_runs};
+
+use super::{ListError, ListResult};
+use crate::{service_provider::ServiceContext, SingleRecordError};
+
+use repository::{NotificationRun, NotificationRunFilter, NotificationRunSort, PaginationOption};
+
+pub trait NotificationRunServiceTrait: Sync + Send {
+    fn get_notification_runs(
+        &self,
+        ctx: &ServiceContext,
+        pagination: Option<PaginationOption>,
+        filter: Option<NotificationRunFilter>,
+        sort: Option<NotificationRunSort>,
+    ) -> Result<ListResult<NotificationRun>, ListError> {
+        get_notification_runs(ctx, pagination, filter, sort)
+    }
+
+    fn get_notification_run(
+        &self,
+        ctx: &ServiceContext,
+        notification_run_id: String,
+    ) -> Result<NotificationRun, SingleRecordError> {
+        get_notification_run(ctx, notification_run_id)
+    }
+}
+
+pub struct NotificationRunService {}
+impl NotificationRunServiceTrait for NotificationRunService {}
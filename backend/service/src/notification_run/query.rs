@@ -0,0 +1,44 @@
+use repository::{
+    EqualFilter, NotificationRun, NotificationRunFilter, NotificationRunRepository,
+    NotificationRunSort, PaginationOption,
+};
+use util::number_conversions::i64_to_u32;
+
+use crate::{
+    get_default_pagination, service_provider::ServiceContext, ListError, ListResult,
+    SingleRecordError,
+};
+
+pub const MAX_LIMIT: u32 = 1000;
+pub const MIN_LIMIT: u32 = 1;
+
+pub fn get_notification_runs(
+    ctx: &ServiceContext,
+    pagination: Option<PaginationOption>,
+    filter: Option<NotificationRunFilter>,
+    sort: Option<NotificationRunSort>,
+) -> Result<ListResult<NotificationRun>, ListError> {
+    let pagination = get_default_pagination(pagination, MAX_LIMIT, MIN_LIMIT)?;
+    let repository = NotificationRunRepository::new(&ctx.connection);
+
+    Ok(ListResult {
+        rows: repository.query(pagination, filter.clone(), sort)?,
+        count: i64_to_u32(repository.count(filter)?),
+    })
+}
+
+pub fn get_notification_run(
+    ctx: &ServiceContext,
+    id: String,
+) -> Result<NotificationRun, SingleRecordError> {
+    let repository = NotificationRunRepository::new(&ctx.connection);
+
+    let mut result =
+        repository.query_by_filter(NotificationRunFilter::new().id(EqualFilter::equal_to(&id)))?;
+
+    if let Some(record) = result.pop() {
+        Ok(record)
+    } else {
+        Err(SingleRecordError::NotFound(id))
+    }
+}
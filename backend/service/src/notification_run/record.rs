@@ -0,0 +1,76 @@
+use chrono::{NaiveDateTime, Utc};
+use repository::{
+    NotificationConfigKind, NotificationRunRow, NotificationRunRowRepository,
+    NotificationRunStatus, RepositoryError,
+};
+use serde::{Deserialize, Serialize};
+use util::uuid::uuid;
+
+use crate::service_provider::ServiceContext;
+
+/// How long a notification query took to run for a parameter set
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryTiming {
+    pub query: String, // The query's reference name
+    pub parameters: serde_json::Value,
+    pub duration_ms: i64,
+    pub error: Option<String>,
+}
+
+/// The outcome of processing a notification config, used to update the run record
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NotificationRunOutcome {
+    pub parameter_sets_evaluated: usize,
+    pub notifications_created: usize,
+    pub skipped_reasons: Vec<String>,
+    pub query_timings: Vec<QueryTiming>,
+    pub error_message: Option<String>,
+}
+
+/// Records that processing of a notification config has started.
+/// The run stays in the `Running` status until `finish_notification_run` is called.
+pub fn start_notification_run(
+    ctx: &ServiceContext,
+    notification_config_id: &str,
+    kind: NotificationConfigKind,
+    started_at: NaiveDateTime,
+) -> Result<NotificationRunRow, RepositoryError> {
+    let run = NotificationRunRow {
+        id: uuid(),
+        notification_config_id: notification_config_id.to_string(),
+        kind,
+        status: NotificationRunStatus::Running,
+        started_at,
+        finished_at: None,
+        skipped_reasons: "[]".to_string(),
+        query_timings: "[]".to_string(),
+        ..Default::default()
+    };
+    NotificationRunRowRepository::new(&ctx.connection).insert_one(&run)?;
+    Ok(run)
+}
+
+pub fn finish_notification_run(
+    ctx: &ServiceContext,
+    run: NotificationRunRow,
+    outcome: NotificationRunOutcome,
+) -> Result<NotificationRunRow, RepositoryError> {
+    let run = NotificationRunRow {
+        status: match outcome.error_message {
+            Some(_) => NotificationRunStatus::Errored,
+            None => NotificationRunStatus::Completed,
+        },
+        finished_at: Some(Utc::now().naive_utc()),
+        parameter_sets_evaluated: outcome.parameter_sets_evaluated as i32,
+        notifications_created: outcome.notifications_created as i32,
+        skipped_count: outcome.skipped_reasons.len() as i32,
+        skipped_reasons: serde_json::to_string(&outcome.skipped_reasons)
+            .unwrap_or_else(|_| "[]".to_string()),
+        query_timings: serde_json::to_string(&outcome.query_timings)
+            .unwrap_or_else(|_| "[]".to_string()),
+        error_message: outcome.error_message,
+        ..run
+    };
+    NotificationRunRowRepository::new(&ctx.connection).update_one(&run)?;
+    Ok(run)
+}
@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod notification_run_query_test {
+    use std::sync::Arc;
+
+    use chrono::{Duration, NaiveDate};
+    use repository::{
+        mock::MockDataInserts, test_db::setup_all, EqualFilter, NotificationConfigKind,
+        NotificationRunFilter, NotificationRunRow, NotificationRunRowRepository,
+        NotificationRunStatus,
+    };
+
+    use crate::notification_run::{
+        finish_notification_run, start_notification_run, NotificationRunOutcome, QueryTiming,
+    };
+    use crate::service_provider::ServiceContext;
+    use crate::test_utils::get_test_settings;
+    use crate::{service_provider::ServiceProvider, SingleRecordError};
+
+    #[actix_rt::test]
+    async fn notification_run_service_single_record() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_notification_run_single_record",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::new(service_provider).unwrap();
+        let service = &context.service_provider.notification_run_service;
+
+        assert_eq!(
+            service.get_notification_run(&context, "invalid_id".to_owned()),
+            Err(SingleRecordError::NotFound("invalid_id".to_owned()))
+        );
+
+        let repo = NotificationRunRowRepository::new(&context.connection);
+        let id = "some-id".to_string();
+        repo.insert_one(&NotificationRunRow {
+            id: id.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let db_notification_run = service.get_notification_run(&context, id.clone()).unwrap();
+        assert_eq!(db_notification_run.id, id);
+    }
+
+    #[actix_rt::test]
+    async fn notification_run_service_filter_by_config() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_notification_run_filter_by_config",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::new(service_provider).unwrap();
+        let service = &context.service_provider.notification_run_service;
+
+        let repo = NotificationRunRowRepository::new(&context.connection);
+        let started_at = NaiveDate::from_ymd_opt(2024, 3, 5)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        for (id, config_id, days) in [
+            ("run_a_1", "config_a", 0),
+            ("run_b_1", "config_b", 1),
+            ("run_a_2", "config_a", 2),
+        ] {
+            repo.insert_one(&NotificationRunRow {
+                id: id.to_string(),
+                notification_config_id: config_id.to_string(),
+                started_at: started_at + Duration::days(days),
+                ..Default::default()
+            })
+            .unwrap();
+        }
+
+        let runs = service
+            .get_notification_runs(
+                &context,
+                None,
+                Some(
+                    NotificationRunFilter::new()
+                        .notification_config_id(EqualFilter::equal_to("config_a")),
+                ),
+                None,
+            )
+            .unwrap();
+
+        // Most recent run first
+        assert_eq!(runs.count, 2);
+        assert_eq!(runs.rows[0].id, "run_a_2");
+        assert_eq!(runs.rows[1].id, "run_a_1");
+    }
+
+    #[actix_rt::test]
+    async fn notification_run_start_and_finish() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_notification_run_start_and_finish",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::new(service_provider).unwrap();
+        let service = &context.service_provider.notification_run_service;
+
+        let now = chrono::Utc::now().naive_utc();
+        let run = start_notification_run(
+            &context,
+            "config_id",
+            NotificationConfigKind::Scheduled,
+            now,
+        )
+        .unwrap();
+
+        let db_run = service.get_notification_run(&context, run.id.clone()).unwrap();
+        assert_eq!(db_run.status, NotificationRunStatus::Running);
+        assert_eq!(db_run.finished_at, None);
+
+        finish_notification_run(
+            &context,
+            run.clone(),
+            NotificationRunOutcome {
+                parameter_sets_evaluated: 3,
+                notifications_created: 1,
+                skipped_reasons: vec!["No recipients".to_string(), "Unchanged".to_string()],
+                query_timings: vec![QueryTiming {
+                    query: "stock".to_string(),
+                    parameters: serde_json::json!({"store": "A"}),
+                    duration_ms: 12,
+                    error: None,
+                }],
+                error_message: None,
+            },
+        )
+        .unwrap();
+
+        let db_run = service.get_notification_run(&context, run.id.clone()).unwrap();
+        assert_eq!(db_run.status, NotificationRunStatus::Completed);
+        assert!(db_run.finished_at.is_some());
+        assert_eq!(db_run.parameter_sets_evaluated, 3);
+        assert_eq!(db_run.skipped_count, 2);
+        assert_eq!(
+            db_run.skipped_reasons,
+            "[\"No recipients\",\"Unchanged\"]".to_string()
+        );
+        let timings: Vec<QueryTiming> = serde_json::from_str(&db_run.query_timings).unwrap();
+        assert_eq!(timings[0].query, "stock");
+
+        // An error marks the run as errored
+        let db_run = finish_notification_run(
+            &context,
+            run,
+            NotificationRunOutcome {
+                error_message: Some("Failed".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(db_run.status, NotificationRunStatus::Errored);
+    }
+}
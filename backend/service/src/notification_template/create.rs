@@ -0,0 +1,121 @@
+use super::{
+    query::get_notification_template,
+    validate::{
+        check_notification_template_does_not_exist, check_notification_template_name_is_unique,
+        check_notification_template_name_is_valid, check_template_compiles,
+    },
+    ModifyNotificationTemplateError,
+};
+use crate::{audit_log::audit_log_entry, service_provider::ServiceContext};
+
+use chrono::Utc;
+use repository::{
+    LogType, NotificationTemplate, NotificationTemplateRow, NotificationTemplateRowRepository,
+    NotificationTemplateVersionRow, NotificationTemplateVersionRowRepository, StorageConnection,
+};
+use util::uuid::uuid;
+
+#[derive(Clone, Default)]
+pub struct CreateNotificationTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub template: String,
+}
+
+pub fn create_notification_template(
+    ctx: &ServiceContext,
+    new_notification_template: CreateNotificationTemplate,
+) -> Result<NotificationTemplate, ModifyNotificationTemplateError> {
+    let notification_template = ctx
+        .connection
+        .transaction_sync(|connection| {
+            validate(&new_notification_template, connection)?;
+            check_template_compiles(
+                ctx,
+                connection,
+                &new_notification_template.id,
+                &new_notification_template.name,
+                &new_notification_template.template,
+            )?;
+            let new_notification_template_row = generate(new_notification_template.clone());
+            NotificationTemplateRowRepository::new(connection)
+                .insert_one(&new_notification_template_row)?;
+            NotificationTemplateVersionRowRepository::new(connection)
+                .insert_one(&new_version(ctx, &new_notification_template_row))?;
+
+            get_notification_template(ctx, new_notification_template_row.id)
+                .map_err(ModifyNotificationTemplateError::from)
+        })
+        .map_err(|error| error.to_inner_error())?;
+
+    // Audit logging
+    audit_log_entry(
+        ctx,
+        LogType::NotificationTemplateCreated,
+        Some(new_notification_template.id),
+        Utc::now().naive_utc(),
+    )?;
+
+    Ok(notification_template)
+}
+
+pub fn validate(
+    new_notification_template: &CreateNotificationTemplate,
+    connection: &StorageConnection,
+) -> Result<(), ModifyNotificationTemplateError> {
+    if !check_notification_template_name_is_valid(&new_notification_template.name) {
+        return Err(ModifyNotificationTemplateError::InvalidNotificationTemplateName);
+    }
+
+    if !check_notification_template_does_not_exist(&new_notification_template.id, connection)? {
+        return Err(ModifyNotificationTemplateError::NotificationTemplateAlreadyExists);
+    }
+
+    if !check_notification_template_name_is_unique(
+        &new_notification_template.id,
+        Some(new_notification_template.name.clone()),
+        connection,
+    )? {
+        return Err(ModifyNotificationTemplateError::NameAlreadyExists);
+    }
+
+    Ok(())
+}
+
+pub fn generate(
+    CreateNotificationTemplate {
+        id,
+        name,
+        description,
+        template,
+    }: CreateNotificationTemplate,
+) -> NotificationTemplateRow {
+    NotificationTemplateRow {
+        id,
+        name: name.trim().to_string(),
+        description: description.unwrap_or_default(),
+        template,
+        version: 1,
+        created_at: Utc::now().naive_utc(),
+        updated_at: Utc::now().naive_utc(),
+    }
+}
+
+/// A copy of the template as it is now, to keep in the version history
+pub fn new_version(
+    ctx: &ServiceContext,
+    notification_template: &NotificationTemplateRow,
+) -> NotificationTemplateVersionRow {
+    NotificationTemplateVersionRow {
+        id: uuid(),
+        notification_template_id: notification_template.id.clone(),
+        version: notification_template.version,
+        template: notification_template.template.clone(),
+        created_at: notification_template.updated_at,
+        user_id: match ctx.user_id.as_str() {
+            "" => None,
+            user_id => Some(user_id.to_string()),
+        },
+    }
+}
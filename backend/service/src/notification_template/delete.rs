@@ -0,0 +1,61 @@
+use super::{load::compile_templates, validate::check_notification_template_exists};
+use crate::service_provider::ServiceContext;
+use repository::{
+    NotificationTemplateRow, NotificationTemplateRowRepository,
+    NotificationTemplateVersionRowRepository, RepositoryError, StorageConnection,
+};
+
+#[derive(PartialEq, Debug)]
+pub enum DeleteNotificationTemplateError {
+    NotificationTemplateDoesNotExist,
+    TemplateInUse(String),
+    DatabaseError(RepositoryError),
+}
+
+/// Deletes the template and its version history. If it replaced a file template, the file template is used again.
+/// A template can't be deleted if another template extends or imports it.
+pub fn delete_notification_template(
+    ctx: &ServiceContext,
+    notification_template_id: &str,
+) -> Result<String, DeleteNotificationTemplateError> {
+    let notification_template = ctx
+        .connection
+        .transaction_sync(|connection| -> Result<_, DeleteNotificationTemplateError> {
+            let notification_template_row = validate(ctx, connection, notification_template_id)?;
+
+            NotificationTemplateVersionRowRepository::new(connection)
+                .delete_by_template_id(notification_template_id)?;
+            NotificationTemplateRowRepository::new(connection).delete(notification_template_id)?;
+
+            Ok(notification_template_row)
+        })
+        .map_err(|error| error.to_inner_error())?;
+
+    Ok(notification_template.id)
+}
+
+pub fn validate(
+    ctx: &ServiceContext,
+    connection: &StorageConnection,
+    notification_template_id: &str,
+) -> Result<NotificationTemplateRow, DeleteNotificationTemplateError> {
+    let notification_template_row =
+        match check_notification_template_exists(notification_template_id, connection)? {
+            Some(notification_template_row) => notification_template_row,
+            None => return Err(DeleteNotificationTemplateError::NotificationTemplateDoesNotExist),
+        };
+
+    // The other templates still need to compile without this one
+    let mut templates = NotificationTemplateRowRepository::new(connection).find_all()?;
+    templates.retain(|row| row.id != notification_template_id);
+    compile_templates(ctx, &templates)
+        .map_err(|e| DeleteNotificationTemplateError::TemplateInUse(format!("{:?}", e)))?;
+
+    Ok(notification_template_row)
+}
+
+impl From<RepositoryError> for DeleteNotificationTemplateError {
+    fn from(error: RepositoryError) -> Self {
+        DeleteNotificationTemplateError::DatabaseError(error)
+    }
+}
@@ -0,0 +1,197 @@
+use repository::{NotificationTemplateRow, NotificationTemplateRowRepository};
+use tera::Tera;
+
+use crate::service_provider::ServiceContext;
+
+/// Creates a tera instance with the file templates from `templates/**/*`, and the templates stored in the database.
+/// Database templates replace file templates with the same name, e.g. `coldchain/temperature.md`.
+/// A stored template that doesn't compile is logged and left out, so it doesn't stop other notifications being sent.
+pub fn notification_tera(ctx: &ServiceContext) -> Result<Tera, tera::Error> {
+    let templates = NotificationTemplateRowRepository::new(&ctx.connection)
+        .find_all()
+        .map_err(|e| tera::Error::msg(format!("Unable to load notification templates: {:?}", e)))?;
+
+    let mut tera = file_tera(ctx)?;
+    add_stored_templates(&mut tera, &templates);
+
+    Ok(tera)
+}
+
+/// Creates a tera instance with the file templates and the given stored templates, failing if any of them don't compile,
+/// e.g. because a template extends or imports one that isn't there.
+pub fn compile_templates(
+    ctx: &ServiceContext,
+    templates: &[NotificationTemplateRow],
+) -> Result<Tera, tera::Error> {
+    let mut tera = file_tera(ctx)?;
+    tera.add_raw_templates(
+        templates
+            .iter()
+            .map(|template| (template.name.as_str(), template.template.as_str())),
+    )?;
+
+    Ok(tera)
+}
+
+fn file_tera(ctx: &ServiceContext) -> Result<Tera, tera::Error> {
+    let mut tera = Tera::default();
+    tera.extend(ctx.service_provider.notification_service.tera())?;
+    Ok(tera)
+}
+
+fn add_stored_templates(tera: &mut Tera, templates: &[NotificationTemplateRow]) {
+    let mut all_templates = tera.clone();
+    if all_templates
+        .add_raw_templates(
+            templates
+                .iter()
+                .map(|template| (template.name.as_str(), template.template.as_str())),
+        )
+        .is_ok()
+    {
+        *tera = all_templates;
+        return;
+    }
+
+    // A template can extend or import one that comes after it, so keep adding the templates that compile until no more can be added
+    let mut remaining: Vec<&NotificationTemplateRow> = templates.iter().collect();
+    loop {
+        let remaining_count = remaining.len();
+        remaining.retain(|template| {
+            let mut with_template = tera.clone();
+            match with_template.add_raw_template(&template.name, &template.template) {
+                Ok(()) => {
+                    *tera = with_template;
+                    false
+                }
+                Err(_) => true,
+            }
+        });
+        if remaining.len() == remaining_count {
+            break;
+        }
+    }
+
+    for template in remaining {
+        let error = tera
+            .clone()
+            .add_raw_template(&template.name, &template.template)
+            .err();
+        log::error!(
+            "Notification template {} doesn't compile, so it won't be used: {:?}",
+            template.name,
+            error
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use repository::{
+        mock::MockDataInserts, test_db::setup_all, NotificationPriority, NotificationTemplateRow,
+        NotificationTemplateRowRepository, NotificationType,
+    };
+
+    use crate::{
+        notification::enqueue::{
+            render_notification_events, NotificationContext, NotificationTarget, TemplateDefinition,
+        },
+        notification_template::create::CreateNotificationTemplate,
+        service_provider::{ServiceContext, ServiceProvider},
+        test_utils::get_test_settings,
+    };
+
+    #[actix_rt::test]
+    async fn test_database_templates_replace_file_templates() {
+        let (_, _, connection_manager, _) = setup_all(
+            "test_database_templates_replace_file_templates",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        let render = |body_template: TemplateDefinition| {
+            render_notification_events(
+                &context,
+                &None,
+                NotificationContext {
+                    title_template: None,
+                    body_template,
+                    recipients: vec![NotificationTarget {
+                        name: "Clinic A".to_string(),
+                        to_address: "-1234".to_string(),
+                        notification_type: NotificationType::Telegram,
+                        ..Default::default()
+                    }],
+                    template_data: serde_json::json!({}),
+                    dedup_key: None,
+                    priority: NotificationPriority::Normal,
+                    channel_templates: HashMap::new(),
+                },
+            )
+            .unwrap()
+            .pop()
+            .unwrap()
+            .message
+        };
+        let file_template =
+            || TemplateDefinition::TemplateName("test_message/telegram.html".to_string());
+
+        assert!(render(file_template()).contains("This is a test message from notify"));
+
+        let service = &context.service_provider.notification_template_service;
+        for (name, template) in [
+            ("test_message/telegram.html", "Hello {{ recipient.name }}"),
+            ("signature.md", "Sent by notify"),
+        ] {
+            service
+                .create_notification_template(
+                    &context,
+                    CreateNotificationTemplate {
+                        id: name.to_string(),
+                        name: name.to_string(),
+                        template: template.to_string(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+
+        // The database template is used instead of the file
+        assert_eq!(render(file_template()), "Hello Clinic A");
+
+        // Templates can be included by name, e.g. from a scheduled notification's template
+        assert_eq!(
+            render(TemplateDefinition::Template(
+                "Report\n{% include \"signature.md\" %}".to_string()
+            )),
+            "Report\nSent by notify"
+        );
+
+        // The template helpers registered on the notification service are available too
+        assert_eq!(
+            render(TemplateDefinition::Template(
+                "{{ 1234567 | format_number }}".to_string()
+            )),
+            "1,234,567"
+        );
+
+        // A stored template that doesn't compile is left out, rather than stopping every notification
+        NotificationTemplateRowRepository::new(&context.connection)
+            .insert_one(&NotificationTemplateRow {
+                id: "broken".to_string(),
+                name: "broken.md".to_string(),
+                template: "{% extends \"missing.md\" %}".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(render(file_template()), "Hello Clinic A");
+    }
+}
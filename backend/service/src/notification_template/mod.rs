@@ -0,0 +1,107 @@
+use self::{
+    create::{create_notification_template, CreateNotificationTemplate},
+    delete::{delete_notification_template, DeleteNotificationTemplateError},
+    query::{
+        get_notification_template, get_notification_template_versions, get_notification_templates,
+    },
+    update::{update_notification_template, UpdateNotificationTemplate},
+};
+
+use super::{ListError, ListResult};
+use crate::{service_provider::ServiceContext, SingleRecordError};
+
+use repository::{
+    NotificationTemplate, NotificationTemplateFilter, NotificationTemplateSort,
+    NotificationTemplateVersionRow, PaginationOption, RepositoryError,
+};
+
+mod tests;
+
+pub mod create;
+pub mod delete;
+pub mod load;
+pub mod query;
+pub mod update;
+pub mod validate;
+
+pub trait NotificationTemplateServiceTrait: Sync + Send {
+    fn get_notification_templates(
+        &self,
+        ctx: &ServiceContext,
+        pagination: Option<PaginationOption>,
+        filter: Option<NotificationTemplateFilter>,
+        sort: Option<NotificationTemplateSort>,
+    ) -> Result<ListResult<NotificationTemplate>, ListError> {
+        get_notification_templates(ctx, pagination, filter, sort)
+    }
+
+    fn get_notification_template(
+        &self,
+        ctx: &ServiceContext,
+        notification_template_id: String,
+    ) -> Result<NotificationTemplate, SingleRecordError> {
+        get_notification_template(ctx, notification_template_id)
+    }
+
+    fn get_notification_template_versions(
+        &self,
+        ctx: &ServiceContext,
+        notification_template_id: &str,
+    ) -> Result<Vec<NotificationTemplateVersionRow>, RepositoryError> {
+        get_notification_template_versions(ctx, notification_template_id)
+    }
+
+    fn delete_notification_template(
+        &self,
+        ctx: &ServiceContext,
+        notification_template_id: &str,
+    ) -> Result<String, DeleteNotificationTemplateError> {
+        delete_notification_template(ctx, notification_template_id)
+    }
+
+    fn create_notification_template(
+        &self,
+        ctx: &ServiceContext,
+        input: CreateNotificationTemplate,
+    ) -> Result<NotificationTemplate, ModifyNotificationTemplateError> {
+        create_notification_template(ctx, input)
+    }
+
+    fn update_notification_template(
+        &self,
+        ctx: &ServiceContext,
+        input: UpdateNotificationTemplate,
+    ) -> Result<NotificationTemplate, ModifyNotificationTemplateError> {
+        update_notification_template(ctx, input)
+    }
+}
+
+pub struct NotificationTemplateService {}
+impl NotificationTemplateServiceTrait for NotificationTemplateService {}
+
+#[derive(Debug, PartialEq)]
+pub enum ModifyNotificationTemplateError {
+    NotificationTemplateAlreadyExists,
+    NotificationTemplateDoesNotExist,
+    NameAlreadyExists,
+    InvalidNotificationTemplateName,
+    InvalidTemplate(String),
+    ModifiedRecordNotFound,
+    DatabaseError(RepositoryError),
+}
+
+impl From<RepositoryError> for ModifyNotificationTemplateError {
+    fn from(err: RepositoryError) -> Self {
+        ModifyNotificationTemplateError::DatabaseError(err)
+    }
+}
+
+impl From<SingleRecordError> for ModifyNotificationTemplateError {
+    fn from(error: SingleRecordError) -> Self {
+        use ModifyNotificationTemplateError::*;
+        match error {
+            SingleRecordError::DatabaseError(error) => DatabaseError(error),
+            SingleRecordError::NotFound(_) => ModifiedRecordNotFound,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use repository::{
+    EqualFilter, NotificationTemplateFilter, NotificationTemplateRepository,
+    NotificationTemplateSort, NotificationTemplateVersionRow,
+    NotificationTemplateVersionRowRepository, PaginationOption, RepositoryError,
+};
+use util::number_conversions::i64_to_u32;
+
+use crate::{
+    get_default_pagination, service_provider::ServiceContext, ListError, ListResult,
+    SingleRecordError,
+};
+
+use super::NotificationTemplate;
+
+pub const MAX_LIMIT: u32 = 1000;
+pub const MIN_LIMIT: u32 = 1;
+
+pub fn get_notification_templates(
+    ctx: &ServiceContext,
+    pagination: Option<PaginationOption>,
+    filter: Option<NotificationTemplateFilter>,
+    sort: Option<NotificationTemplateSort>,
+) -> Result<ListResult<NotificationTemplate>, ListError> {
+    let pagination = get_default_pagination(pagination, MAX_LIMIT, MIN_LIMIT)?;
+    let repository = NotificationTemplateRepository::new(&ctx.connection);
+
+    Ok(ListResult {
+        rows: repository.query(pagination, filter.clone(), sort)?,
+        count: i64_to_u32(repository.count(filter)?),
+    })
+}
+
+pub fn get_notification_template(
+    ctx: &ServiceContext,
+    id: String,
+) -> Result<NotificationTemplate, SingleRecordError> {
+    let repository = NotificationTemplateRepository::new(&ctx.connection);
+
+    let mut result = repository
+        .query_by_filter(NotificationTemplateFilter::new().id(EqualFilter::equal_to(&id)))?;
+
+    if let Some(record) = result.pop() {
+        Ok(record)
+    } else {
+        Err(SingleRecordError::NotFound(id))
+    }
+}
+
+/// Returns every saved version of the template, newest first
+pub fn get_notification_template_versions(
+    ctx: &ServiceContext,
+    notification_template_id: &str,
+) -> Result<Vec<NotificationTemplateVersionRow>, RepositoryError> {
+    NotificationTemplateVersionRowRepository::new(&ctx.connection)
+        .find_many_by_template_id(notification_template_id)
+}
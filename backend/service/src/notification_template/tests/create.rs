@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod notification_template_create_test {
+    use std::sync::Arc;
+
+    use repository::{mock::MockDataInserts, test_db::setup_all};
+    use repository::{NotificationTemplateRowRepository, NotificationTemplateVersionRowRepository};
+
+    use crate::notification_template::create::CreateNotificationTemplate;
+    use crate::notification_template::ModifyNotificationTemplateError;
+    use crate::service_provider::ServiceContext;
+    use crate::service_provider::ServiceProvider;
+    use crate::test_utils::get_test_settings;
+
+    #[actix_rt::test]
+    async fn notification_template_service_create_errors() {
+        let (_, _, connection_manager, _) = setup_all(
+            "notification_template_service_create_errors",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+        let service = &context.service_provider.notification_template_service;
+
+        service
+            .create_notification_template(
+                &context,
+                CreateNotificationTemplate {
+                    id: "id1".to_string(),
+                    name: "signature.md".to_string(),
+                    template: "Sent by notify".to_string(),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // Id already exists
+        assert_eq!(
+            service.create_notification_template(
+                &context,
+                CreateNotificationTemplate {
+                    id: "id1".to_string(),
+                    name: "footer.md".to_string(),
+                    template: "".to_string(),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyNotificationTemplateError::NotificationTemplateAlreadyExists)
+        );
+
+        // Name already exists (even with added whitespace)
+        assert_eq!(
+            service.create_notification_template(
+                &context,
+                CreateNotificationTemplate {
+                    id: "id2".to_string(),
+                    name: "signature.md  ".to_string(),
+                    template: "".to_string(),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyNotificationTemplateError::NameAlreadyExists)
+        );
+
+        // Names can't have spaces, as they are used to include templates
+        assert_eq!(
+            service.create_notification_template(
+                &context,
+                CreateNotificationTemplate {
+                    id: "id2".to_string(),
+                    name: "my signature".to_string(),
+                    template: "".to_string(),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyNotificationTemplateError::InvalidNotificationTemplateName)
+        );
+
+        // Template doesn't compile
+        assert!(matches!(
+            service.create_notification_template(
+                &context,
+                CreateNotificationTemplate {
+                    id: "id2".to_string(),
+                    name: "footer.md".to_string(),
+                    template: "{% if recipient %}".to_string(),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyNotificationTemplateError::InvalidTemplate(_))
+        ));
+
+        // Extends a template that doesn't exist
+        assert!(matches!(
+            service.create_notification_template(
+                &context,
+                CreateNotificationTemplate {
+                    id: "id2".to_string(),
+                    name: "footer.md".to_string(),
+                    template: "{% extends \"missing.md\" %}".to_string(),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyNotificationTemplateError::InvalidTemplate(_))
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn notification_template_service_create_success() {
+        let (_, _, connection_manager, _) = setup_all(
+            "notification_template_service_create_success",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let connection = connection_manager.connection().unwrap();
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+        let service = &context.service_provider.notification_template_service;
+
+        let template = service
+            .create_notification_template(
+                &context,
+                CreateNotificationTemplate {
+                    id: "id1".to_string(),
+                    name: " coldchain/temperature.md ".to_string(),
+                    description: Some("Temperature alert".to_string()),
+                    template: "{{ sensor.name }} is {{ temperature }}".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(template.name, "coldchain/temperature.md");
+        assert_eq!(template.version, 1);
+
+        let saved = NotificationTemplateRowRepository::new(&connection)
+            .find_one_by_id("id1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved, template);
+
+        let versions = NotificationTemplateVersionRowRepository::new(&connection)
+            .find_many_by_template_id("id1")
+            .unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version, 1);
+        assert_eq!(versions[0].template, template.template);
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod notification_template_delete_test {
+    use std::sync::Arc;
+
+    use repository::{mock::MockDataInserts, test_db::setup_all};
+    use repository::{NotificationTemplateRowRepository, NotificationTemplateVersionRowRepository};
+
+    use crate::notification_template::create::CreateNotificationTemplate;
+    use crate::notification_template::delete::DeleteNotificationTemplateError;
+    use crate::service_provider::ServiceContext;
+    use crate::service_provider::ServiceProvider;
+    use crate::test_utils::get_test_settings;
+
+    #[actix_rt::test]
+    async fn notification_template_service_delete() {
+        let (_, _, connection_manager, _) = setup_all(
+            "notification_template_service_delete",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let connection = connection_manager.connection().unwrap();
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+        let service = &context.service_provider.notification_template_service;
+
+        // Template does not exist
+        assert_eq!(
+            service.delete_notification_template(&context, "invalid_id"),
+            Err(DeleteNotificationTemplateError::NotificationTemplateDoesNotExist)
+        );
+
+        for (id, name, template) in [
+            ("id1", "signature.md", "Sent by notify"),
+            ("id2", "report.md", "{% extends \"signature.md\" %}"),
+        ] {
+            service
+                .create_notification_template(
+                    &context,
+                    CreateNotificationTemplate {
+                        id: id.to_string(),
+                        name: name.to_string(),
+                        template: template.to_string(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+
+        // Another template extends it
+        assert!(matches!(
+            service.delete_notification_template(&context, "id1"),
+            Err(DeleteNotificationTemplateError::TemplateInUse(_))
+        ));
+
+        assert_eq!(
+            service.delete_notification_template(&context, "id2"),
+            Ok("id2".to_string())
+        );
+        assert_eq!(
+            service.delete_notification_template(&context, "id1"),
+            Ok("id1".to_string())
+        );
+        assert_eq!(
+            NotificationTemplateRowRepository::new(&connection)
+                .find_one_by_id("id1")
+                .unwrap(),
+            None
+        );
+        assert!(NotificationTemplateVersionRowRepository::new(&connection)
+            .find_many_by_template_id("id1")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,6 @@
+#[cfg(test)]
+mod create;
+#[cfg(test)]
+mod delete;
+#[cfg(test)]
+mod update;
@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod notification_template_update_test {
+    use std::sync::Arc;
+
+    use repository::{mock::MockDataInserts, test_db::setup_all};
+    use repository::{NotificationTemplateRowRepository, NotificationTemplateVersionRowRepository};
+
+    use crate::notification_template::create::CreateNotificationTemplate;
+    use crate::notification_template::update::UpdateNotificationTemplate;
+    use crate::notification_template::ModifyNotificationTemplateError;
+    use crate::service_provider::ServiceContext;
+    use crate::service_provider::ServiceProvider;
+    use crate::test_utils::get_test_settings;
+
+    #[actix_rt::test]
+    async fn notification_template_service_update() {
+        let (_, _, connection_manager, _) = setup_all(
+            "notification_template_service_update",
+            MockDataInserts::none(),
+        )
+        .await;
+
+        let connection = connection_manager.connection().unwrap();
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+        let service = &context.service_provider.notification_template_service;
+
+        for (id, name) in [("id1", "signature.md"), ("id2", "footer.md")] {
+            service
+                .create_notification_template(
+                    &context,
+                    CreateNotificationTemplate {
+                        id: id.to_string(),
+                        name: name.to_string(),
+                        template: "Sent by notify".to_string(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+
+        // Template does not exist
+        assert_eq!(
+            service.update_notification_template(
+                &context,
+                UpdateNotificationTemplate {
+                    id: "new_id".to_string(),
+                    name: Some("new_name.md".to_string()),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyNotificationTemplateError::NotificationTemplateDoesNotExist)
+        );
+
+        // Name already exists
+        assert_eq!(
+            service.update_notification_template(
+                &context,
+                UpdateNotificationTemplate {
+                    id: "id1".to_string(),
+                    name: Some("footer.md".to_string()),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyNotificationTemplateError::NameAlreadyExists)
+        );
+
+        // Template doesn't compile, so isn't saved
+        assert!(matches!(
+            service.update_notification_template(
+                &context,
+                UpdateNotificationTemplate {
+                    id: "id1".to_string(),
+                    template: Some("{{ user".to_string()),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyNotificationTemplateError::InvalidTemplate(_))
+        ));
+
+        // Changing the description isn't a new version
+        let updated = service
+            .update_notification_template(
+                &context,
+                UpdateNotificationTemplate {
+                    id: "id1".to_string(),
+                    description: Some("Added to the end of reports".to_string()),
+                    template: Some("Sent by notify".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(updated.version, 1);
+        assert_eq!(updated.description, "Added to the end of reports");
+
+        // Changing the template is
+        let updated = service
+            .update_notification_template(
+                &context,
+                UpdateNotificationTemplate {
+                    id: "id1".to_string(),
+                    template: Some("Sent by {{ sender | default(value=\"notify\") }}".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(updated.version, 2);
+        assert_eq!(
+            NotificationTemplateRowRepository::new(&connection)
+                .find_one_by_id("id1")
+                .unwrap()
+                .unwrap(),
+            updated
+        );
+
+        let versions = service
+            .get_notification_template_versions(&context, "id1")
+            .unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].version, 2);
+        assert_eq!(versions[0].template, updated.template);
+        assert_eq!(versions[1].template, "Sent by notify");
+        assert_eq!(
+            NotificationTemplateVersionRowRepository::new(&connection)
+                .find_many_by_template_id("id2")
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Another template extends this one, so it can't be renamed
+        service
+            .create_notification_template(
+                &context,
+                CreateNotificationTemplate {
+                    id: "id3".to_string(),
+                    name: "report.md".to_string(),
+                    template: "{% extends \"signature.md\" %}".to_string(),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(matches!(
+            service.update_notification_template(
+                &context,
+                UpdateNotificationTemplate {
+                    id: "id1".to_string(),
+                    name: Some("new_signature.md".to_string()),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyNotificationTemplateError::InvalidTemplate(_))
+        ));
+    }
+}
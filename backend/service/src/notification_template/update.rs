@@ -0,0 +1,120 @@
+use super::{
+    create::new_version,
+    query::get_notification_template,
+    validate::{
+        check_notification_template_exists, check_notification_template_name_is_unique,
+        check_notification_template_name_is_valid, check_template_compiles,
+    },
+    ModifyNotificationTemplateError,
+};
+use crate::{audit_log::audit_log_entry, service_provider::ServiceContext};
+use chrono::Utc;
+use repository::{
+    LogType, NotificationTemplate, NotificationTemplateRow, NotificationTemplateRowRepository,
+    NotificationTemplateVersionRowRepository, StorageConnection,
+};
+
+#[derive(Clone, Default)]
+pub struct UpdateNotificationTemplate {
+    pub id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub template: Option<String>,
+}
+
+pub fn update_notification_template(
+    ctx: &ServiceContext,
+    updated_notification_template: UpdateNotificationTemplate,
+) -> Result<NotificationTemplate, ModifyNotificationTemplateError> {
+    let notification_template = ctx
+        .connection
+        .transaction_sync(|connection| {
+            let notification_template_row = validate(connection, &updated_notification_template)?;
+            let updated_notification_template_row = generate(
+                updated_notification_template.clone(),
+                notification_template_row.clone(),
+            );
+            check_template_compiles(
+                ctx,
+                connection,
+                &updated_notification_template_row.id,
+                &updated_notification_template_row.name,
+                &updated_notification_template_row.template,
+            )?;
+
+            NotificationTemplateRowRepository::new(connection)
+                .update_one(&updated_notification_template_row)?;
+            if updated_notification_template_row.version != notification_template_row.version {
+                NotificationTemplateVersionRowRepository::new(connection)
+                    .insert_one(&new_version(ctx, &updated_notification_template_row))?;
+            }
+
+            get_notification_template(ctx, updated_notification_template_row.id)
+                .map_err(ModifyNotificationTemplateError::from)
+        })
+        .map_err(|error| error.to_inner_error())?;
+
+    // Audit logging
+    audit_log_entry(
+        ctx,
+        LogType::NotificationTemplateUpdated,
+        Some(updated_notification_template.id),
+        Utc::now().naive_utc(),
+    )?;
+    Ok(notification_template)
+}
+
+pub fn validate(
+    connection: &StorageConnection,
+    new_notification_template: &UpdateNotificationTemplate,
+) -> Result<NotificationTemplateRow, ModifyNotificationTemplateError> {
+    if let Some(name) = &new_notification_template.name {
+        if !check_notification_template_name_is_valid(name) {
+            return Err(ModifyNotificationTemplateError::InvalidNotificationTemplateName);
+        }
+    }
+
+    let notification_template_row =
+        match check_notification_template_exists(&new_notification_template.id, connection)? {
+            Some(notification_template_row) => notification_template_row,
+            None => return Err(ModifyNotificationTemplateError::NotificationTemplateDoesNotExist),
+        };
+
+    if !check_notification_template_name_is_unique(
+        &new_notification_template.id,
+        new_notification_template.name.clone(),
+        connection,
+    )? {
+        return Err(ModifyNotificationTemplateError::NameAlreadyExists);
+    }
+
+    Ok(notification_template_row)
+}
+
+pub fn generate(
+    UpdateNotificationTemplate {
+        id: _id, //ID is already used for look up so we can assume it's the same
+        name,
+        description,
+        template,
+    }: UpdateNotificationTemplate,
+    current_notification_template_row: NotificationTemplateRow,
+) -> NotificationTemplateRow {
+    let mut new_notification_template_row = current_notification_template_row;
+    if let Some(name) = name {
+        new_notification_template_row.name = name.trim().to_string();
+    }
+    if let Some(description) = description {
+        new_notification_template_row.description = description;
+    }
+    if let Some(template) = template {
+        // Only a change to the template itself is a new version
+        if template != new_notification_template_row.template {
+            new_notification_template_row.template = template;
+            new_notification_template_row.version += 1;
+        }
+    }
+    new_notification_template_row.updated_at = Utc::now().naive_utc();
+
+    new_notification_template_row
+}
@@ -0,0 +1,75 @@
+use repository::{
+    EqualFilter, NotificationTemplateFilter, NotificationTemplateRepository,
+    NotificationTemplateRow, NotificationTemplateRowRepository, RepositoryError, StorageConnection,
+    StringFilter,
+};
+
+use super::{load::compile_templates, ModifyNotificationTemplateError};
+use crate::service_provider::ServiceContext;
+
+pub fn check_notification_template_exists(
+    id: &str,
+    connection: &StorageConnection,
+) -> Result<Option<NotificationTemplateRow>, RepositoryError> {
+    NotificationTemplateRowRepository::new(connection).find_one_by_id(id)
+}
+
+pub fn check_notification_template_does_not_exist(
+    id: &str,
+    connection: &StorageConnection,
+) -> Result<bool, RepositoryError> {
+    let notification_template = check_notification_template_exists(id, connection)?;
+
+    Ok(notification_template.is_none())
+}
+
+pub fn check_notification_template_name_is_unique(
+    id: &str,
+    name: Option<String>,
+    connection: &StorageConnection,
+) -> Result<bool, RepositoryError> {
+    let Some(name) = name else {
+        return Ok(true);
+    };
+
+    let notification_templates = NotificationTemplateRepository::new(connection).query_by_filter(
+        NotificationTemplateFilter::new()
+            .name(StringFilter::equal_to(name.trim()))
+            .id(EqualFilter::not_equal_to(id)),
+    )?;
+
+    Ok(notification_templates.is_empty())
+}
+
+/// Template names are used in `include` and `extends` tags, so can't contain quotes or spaces
+pub fn check_notification_template_name_is_valid(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty()
+        && name.len() <= 100
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '\'')
+}
+
+/// Compiles the template with Tera, along with the other stored templates with this change applied,
+/// so a change can't break another template that extends or imports this one
+pub fn check_template_compiles(
+    ctx: &ServiceContext,
+    connection: &StorageConnection,
+    id: &str,
+    name: &str,
+    template: &str,
+) -> Result<(), ModifyNotificationTemplateError> {
+    let mut templates = NotificationTemplateRowRepository::new(connection).find_all()?;
+    templates.retain(|row| row.id != id);
+    templates.push(NotificationTemplateRow {
+        id: id.to_string(),
+        name: name.trim().to_string(),
+        template: template.to_string(),
+        ..Default::default()
+    });
+
+    compile_templates(ctx, &templates)
+        .map_err(|e| ModifyNotificationTemplateError::InvalidTemplate(format!("{:?}", e)))?;
+    Ok(())
+}
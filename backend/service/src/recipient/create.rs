@@ -1,6 +1,8 @@
 use super::{
     query::get_recipient,
-    validate::{check_recipient_does_not_exist, check_to_address_is_unique},
+    validate::{
+        check_locale, check_quiet_hours, check_recipient_does_not_exist, check_to_address_is_unique,
+    },
     ModifyRecipientError,
 };
 use crate::audit_log::audit_log_entry;
@@ -11,12 +13,19 @@ use repository::{
     LogType, NotificationType, Recipient, RecipientRow, RecipientRowRepository, StorageConnection,
 };
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct CreateRecipient {
     pub id: String,
     pub name: String,
     pub notification_type: NotificationType,
     pub to_address: String,
+    /// Local time `HH:MM`
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    /// IANA timezone name, e.g. `Pacific/Auckland`
+    pub timezone: Option<String>,
+    /// Language tag, e.g. `fr` or `fr-CA`
+    pub locale: Option<String>,
 }
 
 pub fn upsert_recipient(
@@ -34,7 +43,22 @@ pub fn upsert_recipient(
                     new_recipient_row
                 }
                 Err(ModifyRecipientError::RecipientAlreadyExists) => {
-                    let new_recipient_row = generate(new_recipient.clone())?;
+                    let mut new_recipient_row = generate(new_recipient.clone())?;
+                    // Keep any quiet hours or locale that have already been set up for this recipient
+                    if let Some(existing) =
+                        RecipientRowRepository::new(connection).find_one_by_id(&new_recipient.id)?
+                    {
+                        if new_recipient_row.quiet_hours_start.is_none() {
+                            new_recipient_row.quiet_hours_start = existing.quiet_hours_start;
+                            new_recipient_row.quiet_hours_end = existing.quiet_hours_end;
+                        }
+                        if new_recipient_row.timezone.is_none() {
+                            new_recipient_row.timezone = existing.timezone;
+                        }
+                        if new_recipient_row.locale.is_none() {
+                            new_recipient_row.locale = existing.locale;
+                        }
+                    }
                     RecipientRowRepository::new(connection).update_one(&new_recipient_row)?;
                     new_recipient_row
                 }
@@ -101,6 +125,16 @@ pub fn validate(
         return Err(ModifyRecipientError::RecipientAlreadyExists);
     }
 
+    check_quiet_hours(
+        &trimmed(new_recipient.quiet_hours_start.clone()),
+        &trimmed(new_recipient.quiet_hours_end.clone()),
+        &trimmed(new_recipient.timezone.clone()),
+    )
+    .map_err(ModifyRecipientError::InvalidQuietHours)?;
+
+    check_locale(&trimmed(new_recipient.locale.clone()))
+        .map_err(ModifyRecipientError::InvalidLocale)?;
+
     Ok(())
 }
 
@@ -110,6 +144,10 @@ pub fn generate(
         name,
         notification_type,
         to_address,
+        quiet_hours_start,
+        quiet_hours_end,
+        timezone,
+        locale,
     }: CreateRecipient,
 ) -> Result<RecipientRow, ModifyRecipientError> {
     Ok(RecipientRow {
@@ -118,5 +156,16 @@ pub fn generate(
         name: name.trim().to_string(),
         to_address: to_address.trim().to_ascii_lowercase(),
         deleted_datetime: None,
+        quiet_hours_start: trimmed(quiet_hours_start),
+        quiet_hours_end: trimmed(quiet_hours_end),
+        timezone: trimmed(timezone),
+        locale: trimmed(locale),
     })
 }
+
+/// Empty values are treated as not set
+pub fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
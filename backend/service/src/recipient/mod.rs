@@ -71,6 +71,8 @@ pub enum ModifyRecipientError {
     ModifiedRecordNotFound,
     DatabaseError(RepositoryError),
     RecipientDoesNotExist,
+    InvalidQuietHours(String),
+    InvalidLocale(String),
     GenericError(String),
 }
 
@@ -13,7 +13,7 @@ fn blank_telegram_recipient() -> RecipientRow {
         name: "".to_string(),
         notification_type: NotificationType::Telegram,
         to_address: "".to_string(),
-        deleted_datetime: None,
+        ..Default::default()
     }
 }
 
@@ -80,6 +80,7 @@ pub async fn update_telegram_recipients(
                     name: cached_recipient.name.clone(),
                     notification_type: NotificationType::Telegram,
                     to_address: cached_recipient.to_address.clone(),
+                    ..Default::default()
                 };
 
                 match upsert_recipient(&ctx, new_recipient) {
@@ -274,6 +275,7 @@ mod test {
             name: "Notification Group 1".to_string(),
             to_address: "-9999".to_string(),
             notification_type: NotificationType::Telegram,
+            ..Default::default()
         };
 
         let result = service_provider
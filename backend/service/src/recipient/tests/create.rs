@@ -6,7 +6,7 @@ mod recipient_create_test {
     use std::sync::Arc;
     use util::uuid::uuid;
 
-    use crate::recipient::create::CreateRecipient;
+    use crate::recipient::create::{upsert_recipient, CreateRecipient};
     use crate::recipient::ModifyRecipientError;
     use crate::service_provider::ServiceContext;
     use crate::service_provider::ServiceProvider;
@@ -36,6 +36,7 @@ mod recipient_create_test {
                     name: "some name".to_string(),
                     to_address: "some@address.com".to_string(),
                     notification_type: NotificationType::Email,
+                    ..Default::default()
                 },
             ),
             Err(ModifyRecipientError::RecipientAlreadyExists)
@@ -50,6 +51,7 @@ mod recipient_create_test {
                     name: "some name".to_string(),
                     to_address: "some@address.com".to_string(),
                     notification_type: NotificationType::Email,
+                    ..Default::default()
                 },
             ),
             Err(ModifyRecipientError::RecipientAlreadyExists)
@@ -64,10 +66,61 @@ mod recipient_create_test {
                     name: "some name".to_string(),
                     to_address: mock_recipient_a().to_address.clone(),
                     notification_type: NotificationType::Email,
+                    ..Default::default()
                 },
             ),
             Err(ModifyRecipientError::RecipientAlreadyExists)
         );
+
+        // Quiet hours without an end time
+        assert!(matches!(
+            service.create_recipient(
+                &context,
+                CreateRecipient {
+                    id: "some-new-id".to_string(),
+                    name: "some name".to_string(),
+                    to_address: "night@shift.com".to_string(),
+                    notification_type: NotificationType::Email,
+                    quiet_hours_start: Some("22:00".to_string()),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyRecipientError::InvalidQuietHours(_))
+        ));
+
+        // Unknown timezone
+        assert!(matches!(
+            service.create_recipient(
+                &context,
+                CreateRecipient {
+                    id: "some-new-id".to_string(),
+                    name: "some name".to_string(),
+                    to_address: "night@shift.com".to_string(),
+                    notification_type: NotificationType::Email,
+                    quiet_hours_start: Some("22:00".to_string()),
+                    quiet_hours_end: Some("06:00".to_string()),
+                    timezone: Some("Somewhere/Else".to_string()),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyRecipientError::InvalidQuietHours(_))
+        ));
+
+        // Locale that isn't a language tag
+        assert!(matches!(
+            service.create_recipient(
+                &context,
+                CreateRecipient {
+                    id: "some-new-id".to_string(),
+                    name: "some name".to_string(),
+                    to_address: "french@example.com".to_string(),
+                    notification_type: NotificationType::Email,
+                    locale: Some("French".to_string()),
+                    ..Default::default()
+                },
+            ),
+            Err(ModifyRecipientError::InvalidLocale(_))
+        ));
     }
 
     #[actix_rt::test]
@@ -92,6 +145,7 @@ mod recipient_create_test {
                 name: "new_recipient".to_string(),
                 to_address: "New_recipient@test.com".to_string(),
                 notification_type: NotificationType::Email,
+                ..Default::default()
             },
         );
 
@@ -137,6 +191,7 @@ mod recipient_create_test {
                 to_address: mock_recipient_a().to_address.clone(),
                 // mock_recipient_a is Email type, so same to_address for Telegram type should succeed
                 notification_type: NotificationType::Telegram,
+                ..Default::default()
             },
         );
 
@@ -152,4 +207,40 @@ mod recipient_create_test {
         // Recipient now exists
         assert!(result.is_some());
     }
+
+    #[actix_rt::test]
+    async fn upsert_recipient_restores_deleted_recipient() {
+        let (_, _, connection_manager, _) = setup_all(
+            "upsert_recipient_restores_deleted_recipient",
+            MockDataInserts::none().recipients(),
+        )
+        .await;
+
+        let connection = connection_manager.connection().unwrap();
+        let recipient_row_repository = RecipientRowRepository::new(&connection);
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        // Upserting a soft deleted recipient brings it back
+        let result = upsert_recipient(
+            &context,
+            CreateRecipient {
+                id: mock_recipient_d_deleted().id.clone(),
+                name: "recipient_d".to_string(),
+                to_address: mock_recipient_d_deleted().to_address.clone(),
+                notification_type: NotificationType::Email,
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok());
+
+        let result = recipient_row_repository
+            .find_one_by_id(&mock_recipient_d_deleted().id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.deleted_datetime, None);
+    }
 }
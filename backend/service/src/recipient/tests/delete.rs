@@ -131,6 +131,7 @@ mod recipient_delete_test {
                 name: "recreated recipient A".to_string(),
                 to_address: mock_recipient_a().to_address.clone(),
                 notification_type: NotificationType::Email,
+                ..Default::default()
             },
         );
 
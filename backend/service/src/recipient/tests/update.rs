@@ -37,6 +37,7 @@ mod recipient_update_tests {
                     id: "new_id".to_string(),
                     name: Some("new_name".to_string()),
                     to_address: None,
+                    ..Default::default()
                 },
             ),
             Err(ModifyRecipientError::RecipientDoesNotExist)
@@ -50,6 +51,7 @@ mod recipient_update_tests {
                     id: mock_recipient_a().id.clone(),
                     to_address: Some(mock_recipient_b().to_address.clone()),
                     name: None,
+                    ..Default::default()
                 },
             ),
             Err(ModifyRecipientError::RecipientAlreadyExists)
@@ -80,6 +82,7 @@ mod recipient_update_tests {
                     name: "new_recipient_1".to_string(),
                     to_address: "new_recipient_1@test.com".to_string(),
                     notification_type: NotificationType::Email,
+                    ..Default::default()
                 },
             )
             .unwrap();
@@ -94,6 +97,7 @@ mod recipient_update_tests {
                     id: "id1".to_string(),
                     name: Some("name_for_id1".to_string()),
                     to_address: None,
+                    ..Default::default()
                 },
             )
             .unwrap();
@@ -110,10 +114,33 @@ mod recipient_update_tests {
                     id: "id1".to_string(),
                     name: None,
                     to_address: Some("id1@example.com".to_string()),
+                    ..Default::default()
                 },
             )
             .unwrap();
 
         assert_eq!(updated_recipient.to_address, "id1@example.com".to_string());
+
+        // Set and remove the locale
+        let service = &context.service_provider.recipient_service;
+        let update_locale = |locale: &str| {
+            service.update_recipient(
+                &context,
+                UpdateRecipient {
+                    id: "id1".to_string(),
+                    locale: Some(locale.to_string()),
+                    ..Default::default()
+                },
+            )
+        };
+        assert_eq!(
+            update_locale(" fr-CA ").unwrap().locale,
+            Some("fr-CA".to_string())
+        );
+        assert!(matches!(
+            update_locale("fr CA"),
+            Err(ModifyRecipientError::InvalidLocale(_))
+        ));
+        assert_eq!(update_locale("").unwrap().locale, None);
     }
 }
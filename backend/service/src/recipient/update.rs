@@ -1,17 +1,25 @@
 use super::{
+    create::trimmed,
     query::get_recipient,
-    validate::{check_recipient_exists, check_to_address_is_unique},
+    validate::{
+        check_locale, check_quiet_hours, check_recipient_exists, check_to_address_is_unique,
+    },
     ModifyRecipientError,
 };
 use crate::{audit_log::audit_log_entry, service_provider::ServiceContext};
 use chrono::Utc;
 use repository::{LogType, Recipient, RecipientRow, RecipientRowRepository, StorageConnection};
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct UpdateRecipient {
     pub id: String,
     pub name: Option<String>,
     pub to_address: Option<String>,
+    /// Set to an empty string to remove the quiet hours, timezone or locale
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
 }
 
 pub fn update_recipient(
@@ -23,6 +31,14 @@ pub fn update_recipient(
         .transaction_sync(|connection| {
             let recipient_row = validate(connection, &updated_recipient)?;
             let updated_recipient_row = generate(updated_recipient.clone(), recipient_row)?;
+            check_quiet_hours(
+                &updated_recipient_row.quiet_hours_start,
+                &updated_recipient_row.quiet_hours_end,
+                &updated_recipient_row.timezone,
+            )
+            .map_err(ModifyRecipientError::InvalidQuietHours)?;
+            check_locale(&updated_recipient_row.locale)
+                .map_err(ModifyRecipientError::InvalidLocale)?;
             RecipientRowRepository::new(connection).update_one(&updated_recipient_row)?;
 
             get_recipient(ctx, updated_recipient_row.id).map_err(ModifyRecipientError::from)
@@ -65,6 +81,10 @@ pub fn generate(
         id: _id, //ID is already used for look up so we can assume it's the same
         name,
         to_address,
+        quiet_hours_start,
+        quiet_hours_end,
+        timezone,
+        locale,
     }: UpdateRecipient,
     current_recipient_row: RecipientRow,
 ) -> Result<RecipientRow, ModifyRecipientError> {
@@ -75,6 +95,18 @@ pub fn generate(
     if let Some(to_address) = to_address {
         new_recipient_row.to_address = to_address.trim().to_ascii_lowercase();
     }
+    if let Some(quiet_hours_start) = quiet_hours_start {
+        new_recipient_row.quiet_hours_start = trimmed(Some(quiet_hours_start));
+    }
+    if let Some(quiet_hours_end) = quiet_hours_end {
+        new_recipient_row.quiet_hours_end = trimmed(Some(quiet_hours_end));
+    }
+    if let Some(timezone) = timezone {
+        new_recipient_row.timezone = trimmed(Some(timezone));
+    }
+    if let Some(locale) = locale {
+        new_recipient_row.locale = trimmed(Some(locale));
+    }
 
     Ok(new_recipient_row)
 }
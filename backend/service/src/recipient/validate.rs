@@ -1,3 +1,4 @@
+use crate::notification::{locale, quiet_hours::QuietHours};
 use repository::{
     EqualFilter, NotificationType, RecipientFilter, RecipientRepository, RecipientRow,
     RecipientRowRepository, RepositoryError, StorageConnection, StringFilter,
@@ -39,3 +40,19 @@ pub fn check_to_address_is_unique(
         }
     }
 }
+
+pub fn check_quiet_hours(
+    quiet_hours_start: &Option<String>,
+    quiet_hours_end: &Option<String>,
+    timezone: &Option<String>,
+) -> Result<(), String> {
+    QuietHours::parse(quiet_hours_start, quiet_hours_end, timezone)?;
+    Ok(())
+}
+
+pub fn check_locale(locale: &Option<String>) -> Result<(), String> {
+    match locale {
+        Some(locale) => locale::check_locale(locale),
+        None => Ok(()),
+    }
+}
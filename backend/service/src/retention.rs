@@ -0,0 +1,281 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Duration, NaiveDateTime};
+use flate2::{write::GzEncoder, Compression};
+use repository::{
+    AuditLogRowRepository, NotificationEventRowRepository, NotificationEventStatus, RepositoryError,
+};
+use serde::Serialize;
+
+use crate::{service_provider::ServiceContext, settings::RetentionSettings};
+
+/// Records are deleted in batches, so we don't load everything into memory at once
+const BATCH_SIZE: i64 = 500;
+
+#[derive(Debug)]
+pub enum RetentionError {
+    DatabaseError(RepositoryError),
+    ExportError(String),
+}
+
+impl From<RepositoryError> for RetentionError {
+    fn from(error: RepositoryError) -> Self {
+        RetentionError::DatabaseError(error)
+    }
+}
+
+impl From<std::io::Error> for RetentionError {
+    fn from(error: std::io::Error) -> Self {
+        RetentionError::ExportError(error.to_string())
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RetentionResult {
+    pub notification_events_deleted: usize,
+    pub audit_logs_deleted: usize,
+}
+
+/// Deletes notification events and audit logs that are older than the configured retention periods.
+/// If an `export_path` is configured, the records are written to gzipped JSON lines files before they are deleted.
+pub fn purge_old_records(
+    ctx: &ServiceContext,
+    settings: &RetentionSettings,
+    now: NaiveDateTime,
+) -> Result<RetentionResult, RetentionError> {
+    let mut result = RetentionResult::default();
+    let export_path = settings.export_path.as_ref().map(Path::new);
+    let event_repo = NotificationEventRowRepository::new(&ctx.connection);
+
+    let mut events_export = Export::new(export_path, "notification_event", now);
+    let event_policies = [
+        (
+            settings.sent_days,
+            vec![
+                NotificationEventStatus::Sent,
+                NotificationEventStatus::Deduplicated,
+                NotificationEventStatus::Cancelled,
+            ],
+        ),
+        (settings.failed_days, vec![NotificationEventStatus::Failed]),
+    ];
+    for (days, statuses) in event_policies {
+        let Some(days) = days else {
+            continue;
+        };
+        let before = now - Duration::days(days as i64);
+        loop {
+            let rows = event_repo.find_older_than(statuses.clone(), before, BATCH_SIZE)?;
+            if rows.is_empty() {
+                break;
+            }
+            events_export.write(&rows)?;
+            let ids: Vec<String> = rows.iter().map(|row| row.id.clone()).collect();
+            result.notification_events_deleted += event_repo.delete_by_ids(&ids)?;
+            if (rows.len() as i64) < BATCH_SIZE {
+                break;
+            }
+        }
+    }
+    events_export.finish()?;
+
+    if let Some(days) = settings.audit_log_days {
+        let audit_log_repo = AuditLogRowRepository::new(&ctx.connection);
+        let mut audit_log_export = Export::new(export_path, "audit_log", now);
+        let before = now - Duration::days(days as i64);
+        loop {
+            let rows = audit_log_repo.find_older_than(before, BATCH_SIZE)?;
+            if rows.is_empty() {
+                break;
+            }
+            audit_log_export.write(&rows)?;
+            let ids: Vec<String> = rows.iter().map(|row| row.id.clone()).collect();
+            result.audit_logs_deleted += audit_log_repo.delete_by_ids(&ids)?;
+            if (rows.len() as i64) < BATCH_SIZE {
+                break;
+            }
+        }
+        audit_log_export.finish()?;
+    }
+
+    Ok(result)
+}
+
+/// Writes rows to a gzipped JSON lines file, e.g. `notification_event_20240312_020000.jsonl.gz`.
+/// The file is only created once there is something to write.
+struct Export {
+    path: Option<PathBuf>,
+    encoder: Option<GzEncoder<File>>,
+}
+
+impl Export {
+    fn new(export_path: Option<&Path>, table_name: &str, now: NaiveDateTime) -> Self {
+        Export {
+            path: export_path.map(|export_path| {
+                export_path.join(format!(
+                    "{}_{}.jsonl.gz",
+                    table_name,
+                    now.format("%Y%m%d_%H%M%S")
+                ))
+            }),
+            encoder: None,
+        }
+    }
+
+    fn write<T: Serialize>(&mut self, rows: &[T]) -> Result<(), RetentionError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if self.encoder.is_none() {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let file = File::create(path)?;
+            self.encoder = Some(GzEncoder::new(file, Compression::default()));
+        }
+        let Some(encoder) = self.encoder.as_mut() else {
+            return Ok(());
+        };
+
+        for row in rows {
+            let line = serde_json::to_string(row)
+                .map_err(|e| RetentionError::ExportError(e.to_string()))?;
+            encoder.write_all(line.as_bytes())?;
+            encoder.write_all(b"\n")?;
+        }
+        // Make sure the rows are written before they are deleted
+        encoder.flush()?;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), RetentionError> {
+        if let Some(encoder) = self.encoder {
+            encoder.finish()?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Read, sync::Arc};
+
+    use chrono::{Duration, Utc};
+    use flate2::read::GzDecoder;
+    use repository::{
+        mock::MockDataInserts, test_db::setup_all, AuditLogRow, AuditLogRowRepository, LogType,
+        NotificationEventRow, NotificationEventRowRepository, NotificationEventStatus,
+    };
+
+    use crate::{
+        retention::{purge_old_records, RetentionResult},
+        service_provider::{ServiceContext, ServiceProvider},
+        settings::RetentionSettings,
+        test_utils::get_test_settings,
+    };
+
+    #[actix_rt::test]
+    async fn test_purge_old_records() {
+        let (_, _, connection_manager, _) =
+            setup_all("test_purge_old_records", MockDataInserts::none()).await;
+
+        let connection = connection_manager.connection().unwrap();
+        let service_provider = Arc::new(ServiceProvider::new(
+            connection_manager,
+            get_test_settings(""),
+        ));
+        let context = ServiceContext::as_server_admin(service_provider).unwrap();
+
+        let now = Utc::now().naive_utc();
+        let event_repo = NotificationEventRowRepository::new(&connection);
+        let event = |id: &str, status: NotificationEventStatus, age_days: i64| {
+            event_repo
+                .insert_one(&NotificationEventRow {
+                    id: id.to_string(),
+                    status,
+                    created_at: now - Duration::days(age_days),
+                    updated_at: now - Duration::days(age_days),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        event("old_sent", NotificationEventStatus::Sent, 100);
+        event("new_sent", NotificationEventStatus::Sent, 10);
+        event("old_failed", NotificationEventStatus::Failed, 400);
+        event("newer_failed", NotificationEventStatus::Failed, 100);
+        // Still waiting to be re-tried, so never deleted
+        event("old_errored", NotificationEventStatus::Errored, 400);
+
+        let audit_log_repo = AuditLogRowRepository::new(&connection);
+        let audit_log = |id: &str, age_days: i64| {
+            audit_log_repo
+                .insert_one(&AuditLogRow {
+                    id: id.to_string(),
+                    record_type: LogType::UserLoggedIn,
+                    user_id: None,
+                    record_id: None,
+                    datetime: now - Duration::days(age_days),
+                })
+                .unwrap()
+        };
+        audit_log("old_log", 400);
+        audit_log("new_log", 10);
+
+        let export_path = std::env::temp_dir().join("notify_test_purge_old_records");
+        let _ = std::fs::remove_dir_all(&export_path);
+        let settings = RetentionSettings {
+            sent_days: Some(90),
+            failed_days: Some(365),
+            audit_log_days: Some(365),
+            export_path: Some(export_path.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        let result = purge_old_records(&context, &settings, now).unwrap();
+        assert_eq!(
+            result,
+            RetentionResult {
+                notification_events_deleted: 2,
+                audit_logs_deleted: 1,
+            }
+        );
+
+        for id in ["new_sent", "newer_failed", "old_errored"] {
+            assert!(event_repo.find_one_by_id(id).unwrap().is_some());
+        }
+        for id in ["old_sent", "old_failed"] {
+            assert!(event_repo.find_one_by_id(id).unwrap().is_none());
+        }
+        assert!(audit_log_repo.find_one_by_id("old_log").unwrap().is_none());
+        assert!(audit_log_repo.find_one_by_id("new_log").unwrap().is_some());
+
+        // Deleted records are exported
+        let read_export = |table_name: &str| {
+            let file_name = format!("{}_{}.jsonl.gz", table_name, now.format("%Y%m%d_%H%M%S"));
+            let mut lines = String::new();
+            GzDecoder::new(std::fs::File::open(export_path.join(file_name)).unwrap())
+                .read_to_string(&mut lines)
+                .unwrap();
+            lines
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let exported_events = read_export("notification_event");
+        assert_eq!(exported_events.len(), 2);
+        assert_eq!(exported_events[0]["id"], "old_sent");
+        assert_eq!(exported_events[0]["status"], "SENT");
+        let exported_logs = read_export("audit_log");
+        assert_eq!(exported_logs.len(), 1);
+        assert_eq!(exported_logs[0]["record_type"], "USER_LOGGED_IN");
+
+        // Nothing left to delete
+        let result = purge_old_records(&context, &settings, now).unwrap();
+        assert_eq!(result, RetentionResult::default());
+    }
+}
@@ -8,10 +8,14 @@ use crate::{
     datasource::{DatasourceService, DatasourceServiceTrait},
     email::{EmailService, EmailServiceTrait},
     log_service::{LogService, LogServiceTrait},
-    notification::{NotificationService, NotificationServiceTrait},
+    notification::{
+        dispatch::NotificationDispatchTrigger, NotificationService, NotificationServiceTrait,
+    },
     notification_config::{NotificationConfigService, NotificationConfigServiceTrait},
     notification_event::{NotificationEventService, NotificationEventServiceTrait},
     notification_query::{NotificationQueryService, NotificationQueryServiceTrait},
+    notification_run::{NotificationRunService, NotificationRunServiceTrait},
+    notification_template::{NotificationTemplateService, NotificationTemplateServiceTrait},
     plugin_store::{PluginService, PluginServiceTrait},
     recipient::{RecipientService, RecipientServiceTrait},
     recipient_list::{RecipientListService, RecipientListServiceTrait},
@@ -32,7 +36,10 @@ pub struct ServiceProvider {
     pub sql_recipient_list_service: Box<dyn SqlRecipientListServiceTrait>,
     pub notification_query_service: Box<dyn NotificationQueryServiceTrait>,
     pub notification_event_service: Box<dyn NotificationEventServiceTrait>,
+    pub notification_run_service: Box<dyn NotificationRunServiceTrait>,
+    pub notification_template_service: Box<dyn NotificationTemplateServiceTrait>,
     pub notification_service: Box<dyn NotificationServiceTrait>,
+    pub notification_dispatch: NotificationDispatchTrigger,
     pub plugin_service: Box<dyn PluginServiceTrait>,
     pub settings: Settings,
     pub telegram: Option<TelegramClient>,
@@ -98,7 +105,10 @@ impl ServiceProvider {
             sql_recipient_list_service: Box::new(SqlRecipientListService {}),
             notification_query_service: Box::new(NotificationQueryService {}),
             notification_event_service: Box::new(NotificationEventService {}),
+            notification_run_service: Box::new(NotificationRunService {}),
+            notification_template_service: Box::new(NotificationTemplateService {}),
             notification_service: Box::new(NotificationService::new(settings.clone())),
+            notification_dispatch: NotificationDispatchTrigger::new(),
             plugin_service: Box::new(PluginService {}),
             settings,
             telegram,
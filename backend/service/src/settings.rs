@@ -1,7 +1,7 @@
 use std::fmt::{Display, Formatter, Result};
 
 use datasource::database_settings::PostgresSettings;
-use repository::database_settings::SqliteSettings;
+use repository::{database_settings::SqliteSettings, NotificationType};
 #[derive(serde::Deserialize, Clone)]
 pub struct Settings {
     pub server: ServerSettings,
@@ -12,6 +12,16 @@ pub struct Settings {
     pub logging: Option<LoggingSettings>,
     #[serde(default)]
     pub backup: BackupSettings,
+    #[serde(default)]
+    pub dispatch: DispatchSettings,
+    #[serde(default)]
+    pub retry: RetrySettings,
+    #[serde(default)]
+    pub rate_limit: RateLimitSettings,
+    #[serde(default)]
+    pub dedup: DedupSettings,
+    #[serde(default)]
+    pub retention: RetentionSettings,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -106,6 +116,9 @@ pub struct MailSettings {
     pub username: String,
     pub password: String,
     pub from: String,
+    /// Url of the logo shown at the top of notification emails
+    #[serde(default)]
+    pub logo_url: Option<String>,
 }
 #[derive(serde::Deserialize, Clone)]
 pub struct TelegramSettings {
@@ -119,3 +132,192 @@ pub struct BackupSettings {
     pub path: String,
     pub filename: String,
 }
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct DispatchSettings {
+    /// Max number of emails to send at the same time
+    pub email_concurrency: usize,
+    /// Max number of telegram messages to send at the same time
+    pub telegram_concurrency: usize,
+    /// How often to check for queued notifications that are due (e.g. retries), in seconds
+    pub sweep_interval_seconds: u64,
+}
+
+impl Default for DispatchSettings {
+    fn default() -> Self {
+        DispatchSettings {
+            email_concurrency: 4,
+            telegram_concurrency: 4,
+            sweep_interval_seconds: 30,
+        }
+    }
+}
+
+impl DispatchSettings {
+    /// Max number of notifications of this type to send at the same time (at least 1)
+    pub fn concurrency(&self, notification_type: &NotificationType) -> usize {
+        let concurrency = match notification_type {
+            NotificationType::Email => self.email_concurrency,
+            NotificationType::Telegram => self.telegram_concurrency,
+            NotificationType::Unknown => 1,
+        };
+        concurrency.max(1)
+    }
+}
+
+/// How failed notifications are re-tried, for each notification type
+#[derive(serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct RetrySettings {
+    pub email: RetryPolicy,
+    pub telegram: RetryPolicy,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        RetrySettings {
+            email: RetryPolicy {
+                base_delay_seconds: 15 * 60,
+                ..Default::default()
+            },
+            telegram: RetryPolicy {
+                base_delay_seconds: 60,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl RetrySettings {
+    pub fn policy(&self, notification_type: &NotificationType) -> RetryPolicy {
+        match notification_type {
+            NotificationType::Email => self.email.clone(),
+            NotificationType::Telegram => self.telegram.clone(),
+            // Unknown notification types can never be sent, so don't retry them
+            NotificationType::Unknown => RetryPolicy {
+                max_attempts: 1,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Number of times to try sending before the notification is marked as failed
+    pub max_attempts: i32,
+    /// Delay before the first retry, in seconds
+    pub base_delay_seconds: u64,
+    /// Each retry waits this many times longer than the last, e.g. 2.0 doubles the delay each time
+    pub backoff_factor: f64,
+    /// Randomly vary the delay by up to this fraction (e.g. 0.1 = +/- 10%), so retries are spread out
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay_seconds: 60,
+            backoff_factor: 2.0,
+            jitter: 0.1,
+        }
+    }
+}
+
+/// Limits how quickly notifications are sent, for each notification type
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RateLimitSettings {
+    pub email: ChannelRateLimits,
+    pub telegram: ChannelRateLimits,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        RateLimitSettings {
+            // No limits by default, but some SMTP relays have hourly caps
+            email: ChannelRateLimits::default(),
+            // https://core.telegram.org/bots/faq#my-bot-is-hitting-limits-how-do-i-avoid-this
+            telegram: ChannelRateLimits {
+                global: Some(RateLimit {
+                    max: 30,
+                    per_seconds: 1,
+                }),
+                per_destination: Some(RateLimit {
+                    max: 20,
+                    per_seconds: 60,
+                }),
+            },
+        }
+    }
+}
+
+impl RateLimitSettings {
+    pub fn limits(&self, notification_type: &NotificationType) -> ChannelRateLimits {
+        match notification_type {
+            NotificationType::Email => self.email.clone(),
+            NotificationType::Telegram => self.telegram.clone(),
+            NotificationType::Unknown => ChannelRateLimits::default(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ChannelRateLimits {
+    /// Limit for all notifications of this type
+    pub global: Option<RateLimit>,
+    /// Limit for each `to_address`, e.g. a telegram group
+    pub per_destination: Option<RateLimit>,
+}
+
+/// Allows up to `max` notifications to be sent in `per_seconds`
+#[derive(serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct RateLimit {
+    pub max: u32,
+    pub per_seconds: u64,
+}
+
+#[derive(serde::Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct DedupSettings {
+    /// A notification isn't sent if the same notification was queued for the recipient within this many minutes (0, the default, to turn off)
+    pub window_minutes: u32,
+}
+
+/// Old records are kept forever unless the number of days is set
+#[derive(serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct RetentionSettings {
+    /// When to delete old records, the same format as the backup cron
+    pub cron: String,
+    /// Delete notifications that have been sent (or weren't sent as they were duplicates or cancelled) after this many days
+    pub sent_days: Option<u32>,
+    /// Delete notifications that failed to send after this many days
+    pub failed_days: Option<u32>,
+    /// Delete audit log entries after this many days
+    pub audit_log_days: Option<u32>,
+    /// If set, deleted records are saved to gzipped JSON lines files in this directory first
+    pub export_path: Option<String>,
+}
+
+impl RetentionSettings {
+    pub fn is_enabled(&self) -> bool {
+        self.sent_days.is_some() || self.failed_days.is_some() || self.audit_log_days.is_some()
+    }
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        RetentionSettings {
+            cron: "0 0 2 * * *".to_string(),
+            sent_days: None,
+            failed_days: None,
+            audit_log_days: None,
+            export_path: None,
+        }
+    }
+}
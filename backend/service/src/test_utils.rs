@@ -9,7 +9,10 @@ use repository::{test_db::get_test_db_settings, StorageConnectionManager};
 use crate::{
     datasource::DatasourceServiceTrait,
     datasource::QueryResult,
-    email::{send::EmailSendError, EmailServiceError, EmailServiceTrait},
+    email::{
+        send::{EmailSendError, InlineImage},
+        EmailServiceError, EmailServiceTrait,
+    },
     service_provider::{ServiceContext, ServiceProvider},
     settings::{MailSettings, ServerSettings, Settings, TelegramSettings},
 };
@@ -52,6 +55,7 @@ pub fn get_test_settings(db_name: &str) -> Settings {
             username: "".to_string(),
             password: "".to_string(),
             from: "no-reply@msupply.foundation".to_string(),
+            logo_url: None,
         },
         telegram: TelegramSettings {
             token: telegram_token,
@@ -65,6 +69,11 @@ pub fn get_test_settings(db_name: &str) -> Settings {
         },
         logging: None,
         backup: Default::default(),
+        dispatch: Default::default(),
+        retry: Default::default(),
+        rate_limit: Default::default(),
+        dedup: Default::default(),
+        retention: Default::default(),
     }
 }
 
@@ -85,6 +94,7 @@ impl EmailServiceTrait for MockEmailService {
         _subject: String,
         _html_body: String,
         _text_body: String,
+        _inline_images: Vec<InlineImage>,
     ) -> Result<(), EmailSendError> {
         Ok(())
     }
@@ -107,6 +117,20 @@ impl DatasourceServiceTrait for MockDatasourceService {
         todo!()
     }
 
+    fn run_sql_query_with_limits(
+        &self,
+        sql_query: String,
+        _parameters: serde_json::Value,
+        _limits: datasource::QueryLimits,
+    ) -> Result<QueryResult, crate::datasource::DatasourceServiceError> {
+        Ok(QueryResult {
+            results: "[]".to_string(),
+            query: sql_query,
+            query_error: None,
+            truncated: false,
+        })
+    }
+
     fn run_recipient_query(
         &self,
         _sql_query: String,
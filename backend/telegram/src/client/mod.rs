@@ -3,9 +3,14 @@ use serde::Serialize;
 use serde_json::{self, Value};
 use std::time::Duration;
 
-use crate::{TelegramApiResponse, TelegramChat, TelegramMessage};
+use crate::{
+    service::markdown::{split_telegram_v2, telegram_v2_to_plain_text},
+    TelegramApiResponse, TelegramChat, TelegramMessage,
+};
 
 const DEFAULT_REQUEST_TIMEOUT: u64 = 60;
+/// The longest message telegram accepts, see https://core.telegram.org/bots/api#sendmessage
+pub const MAX_MESSAGE_LENGTH: usize = 4096;
 
 #[derive(Clone)]
 pub struct TelegramClient {
@@ -19,6 +24,11 @@ pub enum TemporaryErrorType {
     ConnectionError(String),
     InternalServerError(String),
     Flood(String),
+    /// Telegram has asked us to wait this many seconds before sending again (HTTP 429)
+    TooManyRequests {
+        retry_after_seconds: u64,
+        description: String,
+    },
     Other(String),
 }
 
@@ -28,6 +38,14 @@ pub enum TelegramError {
     Temporary(TemporaryErrorType),
 }
 
+/// The messages sent for a markdown message, see `TelegramClient::send_markdown_message`
+#[derive(Debug, Default)]
+pub struct SentMarkdownMessage {
+    pub messages: Vec<TelegramMessage>,
+    /// Telegram's error, if it couldn't parse the formatting and the message was sent as plain text instead
+    pub plain_text_fallback: Option<String>,
+}
+
 #[derive(Serialize)]
 struct GetUpdatesParams {
     offset: Option<i64>,
@@ -64,16 +82,63 @@ impl From<reqwest::Error> for TelegramError {
     }
 }
 
+/// Used if telegram doesn't tell us how long to wait after a 429 response
+const DEFAULT_RETRY_AFTER_SECONDS: u64 = 30;
+
+/// Converts an unsuccessful telegram api response into an error, so rate limits and server errors can be re-tried
+fn response_error(telegram_response: &TelegramApiResponse, response_text: String) -> TelegramError {
+    match telegram_response.error_code {
+        Some(429) => TelegramError::Temporary(TemporaryErrorType::TooManyRequests {
+            retry_after_seconds: telegram_response
+                .retry_after()
+                .map(|seconds| seconds.max(0) as u64)
+                .unwrap_or(DEFAULT_RETRY_AFTER_SECONDS),
+            description: response_text,
+        }),
+        Some(code) if code >= 500 => {
+            TelegramError::Temporary(TemporaryErrorType::InternalServerError(response_text))
+        }
+        _ => TelegramError::Fatal(response_text),
+    }
+}
+
+/// If telegram couldn't send a message because its formatting is invalid, returns telegram's description of the problem
+/// e.g. "Bad Request: can't parse entities: Can't find end of Bold entity at byte offset 6"
+fn entity_parse_error(telegram_response: &TelegramApiResponse) -> Option<String> {
+    match (telegram_response.error_code, &telegram_response.description) {
+        (Some(400), Some(description)) if description.contains("can't parse entities") => {
+            Some(description.clone())
+        }
+        _ => None,
+    }
+}
+
+fn message_from_response(
+    telegram_response: TelegramApiResponse,
+    response_text: String,
+) -> Result<TelegramMessage, TelegramError> {
+    if !telegram_response.ok {
+        return Err(response_error(&telegram_response, response_text));
+    }
+
+    serde_json::from_value(telegram_response.result)
+        .map_err(|e| TelegramError::Fatal(format!("Unable to interpret message - {:?}", e)))
+}
+
 impl TelegramClient {
     pub fn new(token: String) -> TelegramClient {
+        TelegramClient::with_base_url(format!("https://api.telegram.org/bot{}", token))
+    }
+
+    /// Uses a different bot api url, e.g. a local bot api server, or a mock server in tests
+    pub fn with_base_url(base_url: String) -> TelegramClient {
         let http_client = reqwest::Client::builder()
             .timeout(Duration::from_secs(DEFAULT_REQUEST_TIMEOUT))
             .build()
             .expect("Something went unexpectedly wrong building the telegram reqwest client");
-        let url = format!("https://api.telegram.org/bot{}", token);
         TelegramClient {
-            http_client: http_client,
-            base_url: url,
+            http_client,
+            base_url,
         }
     }
 
@@ -119,16 +184,85 @@ impl TelegramClient {
     }
 
     /// Sends a telegram MarkdownV2 message, to translate from common markdown to telegram markdown see: markdown::cmark_telegram_v2
+    /// Messages longer than telegram allows are split into several messages, which are sent in order.
+    /// If telegram can't parse the formatting of a message, it is sent again as plain text.
+    /// Returns an error as soon as any part fails to send, use `send_markdown_part` to carry on from the part that failed.
     pub async fn send_markdown_message(
         &self,
         chat_id: &str,
         markdown_v2: &str,
+    ) -> Result<SentMarkdownMessage, TelegramError> {
+        let mut sent = SentMarkdownMessage::default();
+        for part in split_telegram_v2(markdown_v2, MAX_MESSAGE_LENGTH) {
+            let part_sent = self.send_markdown_part(chat_id, &part).await?;
+            if let Some(description) = part_sent.plain_text_fallback {
+                sent.plain_text_fallback.get_or_insert(description);
+            }
+            sent.messages.extend(part_sent.messages);
+        }
+        Ok(sent)
+    }
+
+    /// Sends one part of a MarkdownV2 message that has been split with markdown::split_telegram_v2.
+    /// If telegram can't parse the formatting, it is sent again as plain text.
+    pub async fn send_markdown_part(
+        &self,
+        chat_id: &str,
+        markdown_v2_part: &str,
+    ) -> Result<SentMarkdownMessage, TelegramError> {
+        let (telegram_response, response_text) = self
+            .post_message(chat_id, markdown_v2_part, Some("MarkdownV2"))
+            .await?;
+
+        let Some(description) = entity_parse_error(&telegram_response) else {
+            return Ok(SentMarkdownMessage {
+                messages: vec![message_from_response(telegram_response, response_text)?],
+                plain_text_fallback: None,
+            });
+        };
+
+        log::warn!(
+            "Telegram couldn't parse the formatting of a message to {}, sending it as plain text - {}",
+            chat_id,
+            description
+        );
+        let (telegram_response, response_text) = self
+            .post_message(chat_id, &telegram_v2_to_plain_text(markdown_v2_part), None)
+            .await?;
+        Ok(SentMarkdownMessage {
+            messages: vec![message_from_response(telegram_response, response_text)?],
+            plain_text_fallback: Some(description),
+        })
+    }
+
+    /// Sends a message, returning telegram's response so the caller can decide what to do with any errors
+    async fn post_message(
+        &self,
+        chat_id: &str,
+        text: &str,
+        parse_mode: Option<&str>,
+    ) -> Result<(TelegramApiResponse, String), TelegramError> {
+        let mut params = vec![("chat_id", chat_id), ("text", text)];
+        if let Some(parse_mode) = parse_mode {
+            params.push(("parse_mode", parse_mode));
+        }
+        let url = format!("{}/sendMessage", self.base_url);
+
+        let response = self.http_client.post(&url).form(&params).send().await?;
+        let response_text = response.text().await?;
+
+        let telegram_response: TelegramApiResponse = serde_json::from_str(&response_text)
+            .map_err(|e| TelegramError::Fatal(format!("{}-{}", e, response_text)))?;
+
+        Ok((telegram_response, response_text))
+    }
+
+    pub async fn send_html_message(
+        &self,
+        chat_id: &str,
+        html: &str,
     ) -> Result<TelegramMessage, TelegramError> {
-        let params = [
-            ("chat_id", chat_id),
-            ("text", &markdown_v2),
-            ("parse_mode", "MarkdownV2"),
-        ];
+        let params = [("chat_id", chat_id), ("text", html), ("parse_mode", "HTML")];
         let url = format!("{}/sendMessage", self.base_url);
 
         let response = self.http_client.post(&url).form(&params).send().await?;
@@ -138,7 +272,7 @@ impl TelegramClient {
             .map_err(|e| TelegramError::Fatal(format!("{}-{}", e.to_string(), response_text)))?;
 
         if !telegram_response.ok {
-            return Err(TelegramError::Fatal(response_text));
+            return Err(response_error(&telegram_response, response_text));
         }
 
         let message: TelegramMessage = serde_json::from_value(telegram_response.result)
@@ -147,22 +281,32 @@ impl TelegramClient {
         Ok(message)
     }
 
-    pub async fn send_html_message(
+    /// Sends a png image, with an optional plain text caption
+    pub async fn send_photo(
         &self,
         chat_id: &str,
-        html: &str,
+        png: Vec<u8>,
+        caption: Option<&str>,
     ) -> Result<TelegramMessage, TelegramError> {
-        let params = [("chat_id", chat_id), ("text", html), ("parse_mode", "HTML")];
-        let url = format!("{}/sendMessage", self.base_url);
+        let photo = reqwest::multipart::Part::bytes(png)
+            .file_name("chart.png")
+            .mime_str("image/png")?;
+        let mut form = reqwest::multipart::Form::new()
+            .text("chat_id", chat_id.to_string())
+            .part("photo", photo);
+        if let Some(caption) = caption {
+            form = form.text("caption", caption.to_string());
+        }
+        let url = format!("{}/sendPhoto", self.base_url);
 
-        let response = self.http_client.post(&url).form(&params).send().await?;
+        let response = self.http_client.post(&url).multipart(form).send().await?;
         let response_text = response.text().await?;
 
         let telegram_response: TelegramApiResponse = serde_json::from_str(&response_text)
-            .map_err(|e| TelegramError::Fatal(format!("{}-{}", e.to_string(), response_text)))?;
+            .map_err(|e| TelegramError::Fatal(format!("{}-{}", e, response_text)))?;
 
         if !telegram_response.ok {
-            return Err(TelegramError::Fatal(response_text));
+            return Err(response_error(&telegram_response, response_text));
         }
 
         let message: TelegramMessage = serde_json::from_value(telegram_response.result)
@@ -206,6 +350,53 @@ impl TelegramClient {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_response_error() {
+        let response_text = r#"{"ok":false,"error_code":429,"description":"Too Many Requests: retry after 5","parameters":{"retry_after":5}}"#;
+        let telegram_response: TelegramApiResponse = serde_json::from_str(response_text).unwrap();
+        assert!(matches!(
+            response_error(&telegram_response, response_text.to_string()),
+            TelegramError::Temporary(TemporaryErrorType::TooManyRequests {
+                retry_after_seconds: 5,
+                ..
+            })
+        ));
+
+        let response_text = r#"{"ok":false,"error_code":502,"description":"Bad Gateway"}"#;
+        let telegram_response: TelegramApiResponse = serde_json::from_str(response_text).unwrap();
+        assert!(matches!(
+            response_error(&telegram_response, response_text.to_string()),
+            TelegramError::Temporary(TemporaryErrorType::InternalServerError(_))
+        ));
+
+        let response_text =
+            r#"{"ok":false,"error_code":400,"description":"Bad Request: chat not found"}"#;
+        let telegram_response: TelegramApiResponse = serde_json::from_str(response_text).unwrap();
+        assert!(matches!(
+            response_error(&telegram_response, response_text.to_string()),
+            TelegramError::Fatal(_)
+        ));
+        assert_eq!(entity_parse_error(&telegram_response), None);
+    }
+
+    #[test]
+    fn test_entity_parse_error() {
+        let response_text = r#"{"ok":false,"error_code":400,"description":"Bad Request: can't parse entities: Can't find end of Bold entity at byte offset 6"}"#;
+        let telegram_response: TelegramApiResponse = serde_json::from_str(response_text).unwrap();
+        assert_eq!(
+            entity_parse_error(&telegram_response),
+            Some(
+                "Bad Request: can't parse entities: Can't find end of Bold entity at byte offset 6"
+                    .to_string()
+            )
+        );
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "telegram-tests")]
 mod telegram_test {
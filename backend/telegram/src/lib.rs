@@ -180,9 +180,36 @@ impl TelegramUpdate {
 pub struct TelegramApiResponse {
     pub ok: bool,
     pub description: Option<String>,
+    // Error responses don't include a result
+    #[serde(default)]
     pub result: serde_json::Value,
     pub error_code: Option<i64>,
     pub retry_after: Option<i64>,
+    pub parameters: Option<TelegramResponseParameters>,
+}
+
+/*
+{
+    "ok": false,
+    "error_code": 429,
+    "description": "Too Many Requests: retry after 5",
+    "parameters": { "retry_after": 5 }
+}
+*/
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct TelegramResponseParameters {
+    pub retry_after: Option<i64>,
+    pub migrate_to_chat_id: Option<i64>,
+}
+
+impl TelegramApiResponse {
+    /// The number of seconds telegram has asked us to wait before sending again
+    pub fn retry_after(&self) -> Option<i64> {
+        self.parameters
+            .as_ref()
+            .and_then(|parameters| parameters.retry_after)
+            .or(self.retry_after)
+    }
 }
 
 // Test cases for the TelegramUpdate struct
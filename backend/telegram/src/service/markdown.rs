@@ -2,7 +2,7 @@
    This module is used to parse common markdown text to telegram safe markdown
 */
 
-use pulldown_cmark::{html, Event, Parser};
+use pulldown_cmark::{html, Alignment, Event, Options, Parser};
 use reqwest::Url;
 
 /*
@@ -37,14 +37,44 @@ Custom emoji entities can only be used by bots that purchased additional usernam
 https://core.telegram.org/bots/api#markdownv2-style
 */
 pub fn cmark_to_telegram_v2(common_markdown: &str) -> String {
-    let parser = Parser::new(common_markdown);
+    let parser = Parser::new_ext(common_markdown, Options::ENABLE_TABLES);
 
     let mut t_markdown_v2 = String::new();
 
     // vars to track state
-    let mut list_number: Option<u64> = None;
+    // The next number of each list we're in, or None for unordered lists, so nested lists can be indented
+    let mut list_numbers: Vec<Option<u64>> = vec![];
+    let mut table: Option<Table> = None;
 
     let parser = parser.map(|event| {
+        if let Some(current_table) = table.as_mut() {
+            match &event {
+                Event::End(pulldown_cmark::Tag::Table(_)) => {
+                    t_markdown_v2.push_str(&current_table.to_telegram_v2());
+                    table = None;
+                }
+                Event::Start(pulldown_cmark::Tag::TableHead)
+                | Event::Start(pulldown_cmark::Tag::TableRow) => {
+                    current_table.rows.push(vec![]);
+                }
+                Event::Start(pulldown_cmark::Tag::TableCell) => {
+                    if let Some(row) = current_table.rows.last_mut() {
+                        row.push(String::new());
+                    }
+                }
+                Event::Text(text) | Event::Code(text) => {
+                    if let Some(cell) = current_table.rows.last_mut().and_then(|row| row.last_mut())
+                    {
+                        cell.push_str(text);
+                    }
+                }
+                _ => {
+                    // Formatting can't be shown inside the table's pre-formatted text
+                }
+            }
+            return event;
+        }
+
         match &event {
             Event::Text(text) => {
                 // DEBUG EVENT println!("Text: {:?}", text);
@@ -160,24 +190,39 @@ pub fn cmark_to_telegram_v2(common_markdown: &str) -> String {
                 // Telegram doesn't support block quotes, so we'll just do Italics, Quotes and extra lines
                 t_markdown_v2.push_str("\"_\n");
             }
+            Event::Start(pulldown_cmark::Tag::Table(alignments)) => {
+                // The cells are collected until the end of the table, so the columns can be lined up
+                table = Some(Table {
+                    alignments: alignments.clone(),
+                    rows: vec![],
+                });
+            }
             Event::Start(pulldown_cmark::Tag::List(num)) => {
-                list_number = *num;
+                // A nested list starts on the line after the item it's in
+                if !list_numbers.is_empty() && !t_markdown_v2.ends_with('\n') {
+                    t_markdown_v2.push('\n');
+                }
+                list_numbers.push(*num);
             }
             Event::End(pulldown_cmark::Tag::List(_num)) => {
                 // println!("End List {:?}", _num);
-                list_number = None;
-                t_markdown_v2.push_str("\n");
+                list_numbers.pop();
+                if list_numbers.is_empty() {
+                    t_markdown_v2.push('\n');
+                }
             }
             Event::Start(pulldown_cmark::Tag::Item) => {
                 // println!("Start List Item");
-                match list_number {
-                    Some(number) => {
+                // Items in nested lists are indented under their parent item
+                t_markdown_v2.push_str(&LIST_INDENT.repeat(list_numbers.len().saturating_sub(1)));
+                match list_numbers.last_mut() {
+                    Some(Some(number)) => {
                         // println!("Ordered List");
                         // Telegram doesn't support ordered lists, so we'll make out own!
                         t_markdown_v2.push_str(format!("{}\\. ", number).as_str());
-                        list_number = Some(number + 1)
+                        *number += 1;
                     }
-                    None => {
+                    _ => {
                         // DEBUG EVENT: println!("Unordered List");
                         // Telegram doesn't support unordered lists, so we'll make out own!
                         t_markdown_v2.push_str("\\- ");
@@ -186,7 +231,10 @@ pub fn cmark_to_telegram_v2(common_markdown: &str) -> String {
             }
             Event::End(pulldown_cmark::Tag::Item) => {
                 // println!("End Item");
-                t_markdown_v2.push_str("\n");
+                // Items containing a nested list or paragraphs already end with a new line
+                if !t_markdown_v2.ends_with('\n') {
+                    t_markdown_v2.push('\n');
+                }
             }
             Event::Start(_tag) => {
                 // Telegram doesn't support this, and we're not going to bother with it
@@ -215,6 +263,70 @@ pub fn cmark_to_telegram_v2(common_markdown: &str) -> String {
         .to_string();
 }
 
+/// How far each level of a nested list is indented
+const LIST_INDENT: &str = "    ";
+
+/// The text of each cell in a markdown table, the first row is the header
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Telegram doesn't support tables, so we show them as pre-formatted text with the columns lined up
+    fn to_telegram_v2(&self) -> String {
+        let column_count = self.rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..column_count)
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut lines = vec![];
+        for (index, row) in self.rows.iter().enumerate() {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(column, width)| {
+                    let cell = row.get(column).map(String::as_str).unwrap_or_default();
+                    let padding = " ".repeat(width - cell.chars().count());
+                    match self.alignments.get(column) {
+                        Some(Alignment::Right) => format!("{}{}", padding, cell),
+                        _ => format!("{}{}", cell, padding),
+                    }
+                })
+                .collect();
+            lines.push(cells.join(" | ").trim_end().to_string());
+
+            // Underline the header row
+            if index == 0 {
+                let underlines: Vec<String> =
+                    widths.iter().map(|width| "-".repeat(*width)).collect();
+                lines.push(underlines.join("-+-"));
+            }
+        }
+
+        format!("```\n{}\n```\n\n", escape_telegram_code(&lines.join("\n")))
+    }
+}
+
+// Inside pre and code entities, all '`' and '\' characters must be escaped with a preceding '\' character.
+fn escape_telegram_code(text: &str) -> String {
+    let mut escaped_text = String::new();
+    for c in text.chars() {
+        if c == '`' || c == '\\' {
+            escaped_text.push('\\');
+        }
+        escaped_text.push(c);
+    }
+    escaped_text
+}
+
 // In all other places characters '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!' must be escaped with the preceding character '\'.
 // https://core.telegram.org/bots/api#markdownv2-style
 fn escape_telegram_markdown(text: &str) -> String {
@@ -232,6 +344,282 @@ fn escape_telegram_markdown(text: &str) -> String {
     escaped_text
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BreakKind {
+    Word,
+    Line,
+    Paragraph,
+}
+
+/// A place a long message can be split without breaking any MarkdownV2 entities
+#[derive(Debug)]
+struct Break {
+    /// Byte index the next part starts at
+    index: usize,
+    /// Length of the message up to the break, see `message_length`
+    length: usize,
+    kind: BreakKind,
+    /// If the break is inside a pre-formatted code block, the opening of the block e.g. "```python\n",
+    /// so the block can be closed at the end of one part and re-opened at the start of the next
+    code_block: Option<String>,
+}
+
+/// Telegram limits messages to 4096 UTF-16 code units, counting the MarkdownV2 text is a little cautious as it includes the formatting
+fn message_length(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Finds the places a MarkdownV2 message can be split, i.e. the ends of lines and words outside of any bold, italic, link etc
+fn find_breaks(markdown_v2: &str) -> Vec<Break> {
+    let chars: Vec<(usize, char)> = markdown_v2.char_indices().collect();
+    let starts_with = |i: usize, pattern: &str| markdown_v2[chars[i].0..].starts_with(pattern);
+
+    let mut breaks = vec![];
+    let mut open_entities: Vec<&str> = vec![];
+    let mut code_block: Option<String> = None;
+    let mut in_code = false;
+    let mut in_url = false;
+    let mut length = 0;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let (index, c) = chars[i];
+        // How many chars the current token uses, escaped characters are skipped with their backslash
+        let mut skip = 1;
+
+        if c == '\\' {
+            skip = 2;
+        } else if let Some(opening) = &code_block {
+            if starts_with(i, "```") {
+                code_block = None;
+                skip = 3;
+            } else if c == '\n' && !(i + 1 < chars.len() && starts_with(i + 1, "```")) {
+                breaks.push(Break {
+                    index: index + 1,
+                    length: length + 1,
+                    kind: BreakKind::Line,
+                    code_block: Some(opening.clone()),
+                });
+            }
+        } else if in_code {
+            in_code = c != '`';
+        } else if in_url {
+            in_url = c != ')';
+        } else if starts_with(i, "```") {
+            // The language of the code block is the rest of the line, unless the block ends on the same line
+            let line_end = markdown_v2[index..]
+                .find('\n')
+                .map(|end| index + end)
+                .unwrap_or(markdown_v2.len());
+            let (opening, opening_end) = match markdown_v2[index + 3..line_end].contains('`') {
+                true => ("```\n".to_string(), index + 3),
+                false => (
+                    format!("{}\n", &markdown_v2[index..line_end]),
+                    (line_end + 1).min(markdown_v2.len()),
+                ),
+            };
+            skip = chars[i..]
+                .iter()
+                .take_while(|(char_index, _)| *char_index < opening_end)
+                .count();
+            code_block = Some(opening);
+        } else if starts_with(i, "](") {
+            open_entities.retain(|entity| *entity != "[");
+            in_url = true;
+            skip = 2;
+        } else {
+            let entity = match c {
+                '`' => {
+                    in_code = true;
+                    None
+                }
+                '[' => Some("["),
+                '*' => Some("*"),
+                '~' => Some("~"),
+                '_' if starts_with(i, "__") => Some("__"),
+                '_' => Some("_"),
+                '|' if starts_with(i, "||") => Some("||"),
+                _ => None,
+            };
+            match entity {
+                Some(entity) => {
+                    skip = entity.len();
+                    match open_entities.iter().position(|open| *open == entity) {
+                        Some(position) if entity != "[" => {
+                            open_entities.remove(position);
+                        }
+                        _ => open_entities.push(entity),
+                    }
+                }
+                None if open_entities.is_empty() && (c == '\n' || c == ' ') => {
+                    let kind = match c {
+                        ' ' => BreakKind::Word,
+                        _ if i > 0 && chars[i - 1].1 == '\n' => BreakKind::Paragraph,
+                        _ => BreakKind::Line,
+                    };
+                    breaks.push(Break {
+                        index: index + 1,
+                        length: length + 1,
+                        kind,
+                        code_block: None,
+                    });
+                }
+                None => {}
+            }
+        }
+
+        let end = (i + skip).min(chars.len());
+        length += chars[i..end]
+            .iter()
+            .map(|(_, c)| c.len_utf16())
+            .sum::<usize>();
+        i = end;
+    }
+    breaks
+}
+
+/// Splits a MarkdownV2 message into parts no longer than `max_length`, so it can be sent as several telegram messages.
+/// Messages are split between paragraphs if possible, then between lines or words, without breaking any formatting.
+/// Code blocks are closed at the end of a part and re-opened in the next part.
+/// Only if a single word is too long is it split wherever it needs to be, in which case some formatting can be lost.
+pub fn split_telegram_v2(markdown_v2: &str, max_length: usize) -> Vec<String> {
+    let breaks = find_breaks(markdown_v2);
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut start_length = 0;
+    let mut reopen_code_block: Option<String> = None;
+
+    loop {
+        let prefix = reopen_code_block.take().unwrap_or_default();
+        let prefix_length = message_length(&prefix);
+        let rest = &markdown_v2[start..];
+        if prefix_length + message_length(rest) <= max_length {
+            parts.push(format!("{}{}", prefix, rest));
+            break;
+        }
+
+        let fits = |split: &&Break| {
+            if split.index <= start {
+                return false;
+            }
+            // Code blocks are closed at the end of the part, otherwise the space or newline we split on is trimmed
+            let part_length = match split.code_block {
+                Some(_) => split.length - start_length + 3,
+                None => split.length - start_length - 1,
+            };
+            prefix_length + part_length <= max_length
+        };
+        let split = [BreakKind::Paragraph, BreakKind::Line, BreakKind::Word]
+            .iter()
+            .find_map(|kind| {
+                // The last break of each kind that fits makes the longest part
+                breaks
+                    .iter()
+                    .rev()
+                    .filter(|split| split.kind == *kind)
+                    .find(fits)
+            });
+
+        match split {
+            Some(split) => {
+                let closing = split.code_block.as_ref().map(|_| "```").unwrap_or_default();
+                parts.push(format!(
+                    "{}{}{}",
+                    prefix,
+                    &markdown_v2[start..split.index],
+                    closing
+                ));
+                start = split.index;
+                start_length = split.length;
+                reopen_code_block = split.code_block.clone();
+            }
+            None => {
+                // Nowhere to split the message nicely, so fill the part with as much as we can
+                let mut end = start;
+                let mut end_length = start_length;
+                for (offset, c) in rest.char_indices() {
+                    if end > start
+                        && prefix_length + end_length - start_length + c.len_utf16() > max_length
+                    {
+                        break;
+                    }
+                    end = start + offset + c.len_utf8();
+                    end_length += c.len_utf16();
+                }
+                // Don't separate an escaped character from its backslash
+                let backslashes = markdown_v2[start..end]
+                    .chars()
+                    .rev()
+                    .take_while(|c| *c == '\\')
+                    .count();
+                if backslashes % 2 == 1 && end - start > 1 {
+                    end -= 1;
+                    end_length -= 1;
+                }
+                parts.push(format!("{}{}", prefix, &markdown_v2[start..end]));
+                start = end;
+                start_length = end_length;
+            }
+        }
+    }
+
+    parts
+        .into_iter()
+        .map(|part| part.trim_end().trim_start_matches('\n').to_string())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// Removes the formatting from a MarkdownV2 message, so it can be sent as plain text if telegram can't parse it.
+/// Links are shown as their text followed by the url in brackets.
+pub fn telegram_v2_to_plain_text(markdown_v2: &str) -> String {
+    let chars: Vec<char> = markdown_v2.chars().collect();
+    let mut text = String::new();
+    // Inside inline code and code blocks only '`' and '\' are special
+    let mut in_code = false;
+    let mut in_url = false;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+
+        if c == '\\' {
+            if let Some(escaped) = chars.get(i) {
+                text.push(*escaped);
+                i += 1;
+            }
+        } else if c == '`' {
+            if chars[i..].starts_with(&['`', '`']) {
+                i += 2;
+                // Skip the language at the start of a code block, e.g. ```python
+                let language_length = chars[i..].iter().take_while(|c| **c != '\n').count();
+                let is_language = chars[i..i + language_length]
+                    .iter()
+                    .all(|c| c.is_alphanumeric());
+                if !in_code && is_language && i + language_length < chars.len() {
+                    i += language_length + 1;
+                }
+            }
+            in_code = !in_code;
+        } else if in_code || in_url {
+            in_url = in_url && c != ')';
+            text.push(c);
+        } else {
+            match c {
+                '*' | '_' | '~' | '|' | '[' => {}
+                ']' if chars.get(i) == Some(&'(') => {
+                    text.push_str(" (");
+                    in_url = true;
+                    i += 1;
+                }
+                _ => text.push(c),
+            }
+        }
+    }
+    text
+}
+
 // Function to determine if a string is a valid URL
 // Used to determine if we should escape a string or not.
 // Telegram says to escape characters in a URL, but we don't want to do that if it's a URL
@@ -405,6 +793,126 @@ Here's another paragraph.
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_split_telegram_v2() {
+        // Short messages aren't split
+        assert_eq!(split_telegram_v2("*Hello*", 20), vec!["*Hello*"]);
+
+        // Split between paragraphs rather than lines
+        let message = "Paragraph 1\n\nLine 1\nLine 2\n\nParagraph 3";
+        assert_eq!(
+            split_telegram_v2(message, 30),
+            vec!["Paragraph 1\n\nLine 1\nLine 2", "Paragraph 3"]
+        );
+
+        // Then between lines
+        let message = "\\- Item 1\n\\- Item 2\n\\- Item 3";
+        assert_eq!(
+            split_telegram_v2(message, 22),
+            vec!["\\- Item 1\n\\- Item 2", "\\- Item 3"]
+        );
+
+        // Formatting isn't broken, even if it means splitting between words
+        let message = "Some *bold\ntext* here";
+        assert_eq!(
+            split_telegram_v2(message, 15),
+            vec!["Some", "*bold\ntext*", "here"]
+        );
+        let message = "See [the\nreport](http://example.com/a b) now";
+        assert_eq!(
+            split_telegram_v2(message, 40),
+            vec!["See [the\nreport](http://example.com/a b)", "now"]
+        );
+
+        // Code blocks are closed and re-opened
+        let message = "Results\n\n```sql\nSELECT 1;\nSELECT 2;\n```";
+        assert_eq!(
+            split_telegram_v2(message, 30),
+            vec!["Results", "```sql\nSELECT 1;\nSELECT 2;\n```"]
+        );
+        assert_eq!(
+            split_telegram_v2(message, 25),
+            vec![
+                "Results",
+                "```sql\nSELECT 1;\n```",
+                "```sql\nSELECT 2;\n```"
+            ]
+        );
+
+        // Long words are split, but not between a backslash and the character it escapes
+        assert_eq!(split_telegram_v2("abc\\.def", 4), vec!["abc", "\\.de", "f"]);
+
+        // Length is counted in UTF-16 code units, as telegram does
+        assert_eq!(split_telegram_v2("👍👍 👍", 4), vec!["👍👍", "👍"]);
+
+        // Every part of a long message fits
+        let markdown_v2 = cmark_to_telegram_v2(&"**Clinic** is _low_ on stock\\.\n\n".repeat(500));
+        let parts = split_telegram_v2(&markdown_v2, 4096);
+        assert!(parts.len() > 1);
+        assert!(parts.iter().all(|part| message_length(part) <= 4096));
+        assert!(parts.iter().all(|part| part.starts_with("*Clinic*")));
+        assert_eq!(parts.join("\n\n"), markdown_v2);
+    }
+
+    // Test nested lists
+    #[test]
+    fn test_cmark_telegram_nested_list() {
+        let cmarkdown = r#"
+- Fruit
+  - Apple
+  - Pear
+- Vegetables
+  1. Carrot
+  1. Leek
+"#; // Don't indent this!
+        let expected =
+            "\\- Fruit\n    \\- Apple\n    \\- Pear\n\\- Vegetables\n    1\\. Carrot\n    2\\. Leek";
+        let result = cmark_to_telegram_v2(cmarkdown);
+        assert_eq!(result, expected);
+    }
+
+    // Test tables
+    #[test]
+    fn test_cmark_telegram_table() {
+        let cmarkdown = r#"
+**Low stock**
+
+| Item | Stock |
+|------|------:|
+| Paracetamol | 3 |
+| Amoxicillin `500mg` | 120 |
+"#; // Don't indent this!
+        let expected = r#"*Low stock*
+
+```
+Item              | Stock
+------------------+------
+Paracetamol       |     3
+Amoxicillin 500mg |   120
+```"#; // Don't indent this!
+        let result = cmark_to_telegram_v2(cmarkdown);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_telegram_v2_to_plain_text() {
+        let markdown_v2 = "*__Stock report__*\n*Clinic\\-A* is _low_ on ~stock~ \\(3 items\\)\\.\n[View the report](http://example.com/report?id=1)\n`code\\``";
+        let expected = "Stock report\nClinic-A is low on stock (3 items).\nView the report (http://example.com/report?id=1)\ncode`";
+        assert_eq!(telegram_v2_to_plain_text(markdown_v2), expected);
+
+        let markdown_v2 = "```sql\nSELECT * FROM item;\n```";
+        assert_eq!(
+            telegram_v2_to_plain_text(markdown_v2),
+            "SELECT * FROM item;\n"
+        );
+
+        // Unbalanced formatting, which telegram can't parse
+        assert_eq!(
+            telegram_v2_to_plain_text("*Bold _italic* text"),
+            "Bold italic text"
+        );
+    }
+
     #[test]
     fn test_escape_telegram_markdown() {
         let text = "This is a test of markdown - escaping.";